@@ -1,6 +1,10 @@
-use std::path::PathBuf;
-use clap::{App, ArgMatches, SubCommand};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use clap::{App, Arg, ArgMatches, SubCommand};
 use mdbook::MDBook;
+use mdbook::book::{append_orphans, diagnose_summary, fix_summary_with, Level};
+use mdbook::config::{Config, ConfigBuilder, OrphanPolicy};
 use mdbook::errors::Result;
 use {get_book_dir, open};
 
@@ -13,6 +17,20 @@ pub fn make_subcommand<'a, 'b>() -> App<'a, 'b> {
             "-d, --dest-dir=[dest-dir] 'The output directory for your book{n}(Defaults to ./book \
              when omitted)'",
         )
+        .arg_from_usage(
+            "--deny-warnings 'Treat warnings about SUMMARY.md and the book layout as errors'",
+        )
+        .arg_from_usage("--fix 'Automatically fix simple problems in SUMMARY.md before building'")
+        .arg_from_usage(
+            "--profile=[profile] 'Use the settings from a [profile.<name>] table in book.toml \
+             (e.g. dev or release)'",
+        )
+        .arg(
+            Arg::from_usage(
+                "--message-format=[format] 'How to report problems in SUMMARY.md{n}(Defaults \
+                 to human when omitted)'",
+            ).possible_values(&["human", "json"]),
+        )
         .arg_from_usage(
             "[dir] 'A directory for your book{n}(Defaults to Current Directory when omitted)'",
         )
@@ -21,12 +39,18 @@ pub fn make_subcommand<'a, 'b>() -> App<'a, 'b> {
 // Build command implementation
 pub fn execute(args: &ArgMatches) -> Result<()> {
     let book_dir = get_book_dir(args);
-    let mut book = MDBook::load(&book_dir)?;
 
-    if let Some(dest_dir) = args.value_of("dest-dir") {
-        book.config.build.build_dir = PathBuf::from(dest_dir);
+    if args.is_present("fix") {
+        fix_summary_file(&book_dir, args)?;
+    }
+
+    if args.value_of("message-format") == Some("json") {
+        report_json_diagnostics(&book_dir, args)?;
     }
 
+    let config = load_config(&book_dir, args)?;
+    let mut book = MDBook::load_with_config(&book_dir, config)?;
+
     book.build()?;
 
     if args.is_present("open") {
@@ -35,3 +59,72 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
 
     Ok(())
 }
+
+/// Print every problem in the book's `SUMMARY.md` to stdout as a line of
+/// JSON, failing if any of them are errors (or warnings, when they're
+/// denied).
+fn report_json_diagnostics(book_dir: &Path, args: &ArgMatches) -> Result<()> {
+    let config = load_config(book_dir, args)?;
+    let summary = book_dir.join(&config.book.src).join("SUMMARY.md");
+    let diagnostics = diagnose_summary(&summary, &config.build)?;
+
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic.to_json());
+    }
+
+    let deny_warnings = config.build.deny_warnings;
+    if diagnostics.iter().any(|d| deny_warnings || d.level == Level::Error) {
+        return Err("Unable to build the book, SUMMARY.md contains errors".into());
+    }
+
+    Ok(())
+}
+
+/// Rewrite the book's `SUMMARY.md` with any safe fixes applied, adding
+/// orphaned files as drafts when `build.orphans` is `"append"`.
+fn fix_summary_file(book_dir: &Path, args: &ArgMatches) -> Result<()> {
+    let config = load_config(book_dir, args)?;
+    let src_dir = book_dir.join(&config.book.src);
+    let summary = src_dir.join("SUMMARY.md");
+
+    let mut original = String::new();
+    File::open(&summary)?.read_to_string(&mut original)?;
+
+    let fixed = fix_summary_with(&original, config.build.summary_indent);
+    if fixed != original {
+        println!("Fixing {}", summary.display());
+        File::create(&summary)?.write_all(fixed.as_bytes())?;
+    }
+
+    if config.build.orphans == OrphanPolicy::Append {
+        for orphan in append_orphans(&src_dir, &config.book.sub_books)? {
+            println!("Adding {} to {} as a draft", orphan.display(), summary.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Load the book's config, layering the environment and any command-line
+/// flags over `book.toml` (or `book.json`).
+pub fn load_config(book_dir: &Path, args: &ArgMatches) -> Result<Config> {
+    config_builder(book_dir, args)?.build()
+}
+
+/// Set up a `ConfigBuilder` with every layer of the book's config.
+pub fn config_builder(book_dir: &Path, args: &ArgMatches) -> Result<ConfigBuilder> {
+    let mut builder = ConfigBuilder::new();
+    builder.with_book_dir(book_dir)?.with_env()?;
+
+    if let Some(profile) = args.value_of("profile") {
+        builder.with_profile(profile);
+    }
+    if let Some(dest_dir) = args.value_of("dest-dir") {
+        builder.set("build.build-dir", dest_dir)?;
+    }
+    if args.is_present("deny-warnings") {
+        builder.set("build.deny-warnings", true)?;
+    }
+
+    Ok(builder)
+}
@@ -0,0 +1,66 @@
+use clap::{App, AppSettings, ArgMatches, SubCommand};
+use mdbook::errors::Result;
+use build::{config_builder, load_config};
+use get_book_dir;
+
+// Create clap subcommand arguments
+pub fn make_subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("config")
+        .about("Inspect the book's configuration")
+        .setting(AppSettings::SubcommandRequired)
+        .subcommand(
+            SubCommand::with_name("dump")
+                .about(
+                    "Print the book's configuration, with every available option and its default",
+                )
+                .arg_from_usage(
+                    "--profile=[profile] 'Use the settings from a [profile.<name>] table in \
+                     book.toml'",
+                )
+                .arg_from_usage(
+                    "[dir] 'A directory for your book{n}(Defaults to Current Directory when \
+                     omitted)'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("Print every config value along with where it was set")
+                .arg_from_usage(
+                    "--profile=[profile] 'Use the settings from a [profile.<name>] table in \
+                     book.toml'",
+                )
+                .arg_from_usage(
+                    "[dir] 'A directory for your book{n}(Defaults to Current Directory when \
+                     omitted)'",
+                ),
+        )
+}
+
+// Config command implementation
+pub fn execute(args: &ArgMatches) -> Result<()> {
+    match args.subcommand() {
+        ("dump", Some(sub_matches)) => dump(sub_matches),
+        ("explain", Some(sub_matches)) => explain(sub_matches),
+        (_, _) => unreachable!(),
+    }
+}
+
+fn dump(args: &ArgMatches) -> Result<()> {
+    let book_dir = get_book_dir(args);
+    let config = load_config(&book_dir, args)?;
+
+    print!("{}", config.to_commented_toml()?);
+
+    Ok(())
+}
+
+fn explain(args: &ArgMatches) -> Result<()> {
+    let book_dir = get_book_dir(args);
+    let builder = config_builder(&book_dir, args)?;
+
+    for value in builder.explain()? {
+        println!("{}", value);
+    }
+
+    Ok(())
+}
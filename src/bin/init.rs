@@ -1,7 +1,8 @@
 use std::io;
 use std::io::Write;
-use clap::{App, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use mdbook::MDBook;
+use mdbook::config::Config;
 use mdbook::errors::Result;
 use get_book_dir;
 
@@ -13,6 +14,11 @@ pub fn make_subcommand<'a, 'b>() -> App<'a, 'b> {
         .arg_from_usage("[dir] 'A directory for your book{n}(Defaults to Current Directory \
                          when omitted)'")
         .arg_from_usage("--theme 'Copies the default theme into your source folder'")
+        .arg_from_usage("--title=[title] 'The title of the book'")
+        .arg(
+            Arg::from_usage("--author=[author]... 'An author of the book (can be repeated)'")
+                .number_of_values(1),
+        )
         .arg_from_usage("--force 'skip confirmation prompts'")
 }
 
@@ -21,6 +27,15 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     let book_dir = get_book_dir(args);
     let mut builder = MDBook::init(&book_dir);
 
+    let mut config = Config::default();
+    if let Some(title) = args.value_of("title") {
+        config.book.title = Some(title.to_string());
+    }
+    if let Some(authors) = args.values_of("author") {
+        config.book.authors = authors.map(String::from).collect();
+    }
+    builder.with_config(config);
+
     // If flag `--theme` is present, copy theme to src
     if args.is_present("theme") {
         // Skip this if `--force` is present
@@ -15,6 +15,7 @@ use env_logger::LogBuilder;
 use error_chain::ChainedError;
 
 pub mod build;
+pub mod config;
 pub mod init;
 pub mod test;
 #[cfg(feature = "serve")]
@@ -40,6 +41,7 @@ fn main() {
                              at: https://github.com/rust-lang-nursery/mdBook")
                 .subcommand(init::make_subcommand())
                 .subcommand(build::make_subcommand())
+                .subcommand(config::make_subcommand())
                 .subcommand(test::make_subcommand());
 
     #[cfg(feature = "watch")]
@@ -51,6 +53,7 @@ fn main() {
     let res = match app.get_matches().subcommand() {
         ("init", Some(sub_matches)) => init::execute(sub_matches),
         ("build", Some(sub_matches)) => build::execute(sub_matches),
+        ("config", Some(sub_matches)) => config::execute(sub_matches),
         #[cfg(feature = "watch")]
         ("watch", Some(sub_matches)) => watch::execute(sub_matches),
         #[cfg(feature = "serve")]
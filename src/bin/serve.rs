@@ -3,11 +3,13 @@ extern crate staticfile;
 extern crate ws;
 
 use std;
+use std::path::Path;
 use self::iron::{status, AfterMiddleware, Chain, Iron, IronError, IronResult, Request, Response,
                  Set};
 use clap::{App, ArgMatches, SubCommand};
 use mdbook::MDBook;
 use mdbook::errors::*;
+use build::load_config;
 use {get_book_dir, open};
 #[cfg(feature = "watch")]
 use watch;
@@ -21,6 +23,13 @@ pub fn make_subcommand<'a, 'b>() -> App<'a, 'b> {
         .arg_from_usage(
             "[dir] 'A directory for your book{n}(Defaults to Current Directory when omitted)'",
         )
+        .arg_from_usage(
+            "-d, --dest-dir=[dest-dir] 'The output directory for your book{n}(Defaults to ./book \
+             when omitted)'",
+        )
+        .arg_from_usage(
+            "--profile=[profile] 'Use the settings from a [profile.<name>] table in book.toml'",
+        )
         .arg_from_usage("-p, --port=[port] 'Use another port{n}(Defaults to 3000)'")
         .arg_from_usage(
             "-w, --websocket-port=[ws-port] 'Use another port for the websocket connection \
@@ -41,7 +50,8 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     const RELOAD_COMMAND: &'static str = "reload";
 
     let book_dir = get_book_dir(args);
-    let mut book = MDBook::load(&book_dir)?;
+    let config = load_config(&book_dir, args)?;
+    let mut book = MDBook::load_lazily_with_config(&book_dir, config)?;
 
     let port = args.value_of("port").unwrap_or("3000");
     let ws_port = args.value_of("websocket-port").unwrap_or("3001");
@@ -52,7 +62,7 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     let address = format!("{}:{}", interface, port);
     let ws_address = format!("{}:{}", interface, ws_port);
 
-    book.livereload = Some(format!(
+    let livereload = format!(
         r#"
     <script type="text/javascript">
         var socket = new WebSocket("ws://{}:{}");
@@ -69,13 +79,12 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     </script>
 "#,
         public_address, ws_port, RELOAD_COMMAND
-    ));
+    );
+    book.livereload = Some(livereload.clone());
 
     book.build()?;
 
-    let mut chain = Chain::new(staticfile::Static::new(book.get_destination()));
-    chain.link_after(ErrorRecover);
-    let _iron = Iron::new(chain)
+    let _iron = Iron::new(static_server(book.get_destination()))
         .http(&*address)
         .chain_err(|| "Unable to launch the server")?;
 
@@ -98,9 +107,21 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     #[cfg(feature = "watch")]
     watch::trigger_on_change(&mut book, move |path, book_dir| {
         println!("File changed: {:?}\nBuilding book...\n", path);
-        match MDBook::load(&book_dir).and_then(|mut b| b.build()) {
+        // rebuild with the same settings, keeping the livereload script
+        let result = load_config(book_dir, args)
+            .and_then(|config| MDBook::load_lazily_with_config(book_dir, config))
+            .and_then(|mut b| {
+                b.livereload = Some(livereload.clone());
+                b.build()?;
+                Ok(b)
+            });
+
+        match result {
             Err(e) => println!("Error while building: {:?}", e),
-            _ => broadcaster.send(RELOAD_COMMAND).unwrap(),
+            Ok(b) => {
+                watch::print_dependents(&b, path);
+                broadcaster.send(RELOAD_COMMAND).unwrap();
+            }
         }
         println!("");
     });
@@ -108,6 +129,15 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
+/// Serve the files in `dir`, using `index.html` for directories and guessing
+/// each file's MIME type from its extension (both handled by `staticfile`).
+/// Anything missing is a 404.
+fn static_server<P: AsRef<Path>>(dir: P) -> Chain {
+    let mut chain = Chain::new(staticfile::Static::new(dir.as_ref()));
+    chain.link_after(ErrorRecover);
+    chain
+}
+
 impl AfterMiddleware for ErrorRecover {
     fn catch(&self, _: &mut Request, err: IronError) -> IronResult<Response> {
         match err.response.status {
@@ -117,3 +147,62 @@ impl AfterMiddleware for ErrorRecover {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+
+    use std::fs::{self, File};
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use self::tempdir::TempDir;
+    use super::*;
+
+    /// Make a raw HTTP request and return the response, lowercased so the
+    /// headers are easy to check.
+    fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path)
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response.to_lowercase()
+    }
+
+    #[test]
+    fn serve_files_with_their_mime_types_and_directory_indices() {
+        let temp = TempDir::new("book").unwrap();
+        fs::create_dir(temp.path().join("nested")).unwrap();
+        File::create(temp.path().join("index.html"))
+            .unwrap()
+            .write_all(b"<p>The index</p>")
+            .unwrap();
+        File::create(temp.path().join("nested").join("index.html"))
+            .unwrap()
+            .write_all(b"<p>The nested index</p>")
+            .unwrap();
+        File::create(temp.path().join("book.css"))
+            .unwrap()
+            .write_all(b"body {}")
+            .unwrap();
+
+        let mut server = Iron::new(static_server(temp.path())).http("127.0.0.1:0").unwrap();
+        let address = server.socket;
+
+        let index = get(address, "/");
+        assert!(index.starts_with("http/1.1 200"));
+        assert!(index.contains("content-type: text/html"));
+        assert!(index.contains("<p>the index</p>"));
+
+        let nested = get(address, "/nested/");
+        assert!(nested.contains("<p>the nested index</p>"));
+
+        let css = get(address, "/book.css");
+        assert!(css.contains("content-type: text/css"));
+
+        assert!(get(address, "/missing.html").starts_with("http/1.1 404"));
+
+        server.close().unwrap();
+    }
+}
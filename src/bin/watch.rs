@@ -7,6 +7,7 @@ use std::sync::mpsc::channel;
 use clap::{App, ArgMatches, SubCommand};
 use mdbook::MDBook;
 use mdbook::errors::Result;
+use build::load_config;
 use {get_book_dir, open};
 
 // Create clap subcommand arguments
@@ -14,6 +15,9 @@ pub fn make_subcommand<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("watch")
         .about("Watch the files for changes")
         .arg_from_usage("-o, --open 'Open the compiled book in a web browser'")
+        .arg_from_usage(
+            "--profile=[profile] 'Use the settings from a [profile.<name>] table in book.toml'",
+        )
         .arg_from_usage(
             "[dir] 'A directory for your book{n}(Defaults to Current Directory when omitted)'",
         )
@@ -22,7 +26,8 @@ pub fn make_subcommand<'a, 'b>() -> App<'a, 'b> {
 // Watch command implementation
 pub fn execute(args: &ArgMatches) -> Result<()> {
     let book_dir = get_book_dir(args);
-    let mut book = MDBook::load(&book_dir)?;
+    let config = load_config(&book_dir, args)?;
+    let mut book = MDBook::load_lazily_with_config(&book_dir, config)?;
 
     if args.is_present("open") {
         book.build()?;
@@ -31,10 +36,16 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
 
     trigger_on_change(&book, |path, book_dir| {
         println!("File changed: {:?}\nBuilding book...\n", path);
-        let result = MDBook::load(&book_dir).and_then(|mut b| b.build());
+        let result = load_config(book_dir, args)
+            .and_then(|config| MDBook::load_lazily_with_config(book_dir, config))
+            .and_then(|mut b| {
+                b.build()?;
+                Ok(b)
+            });
 
-        if let Err(e) = result {
-            println!("Error while building: {}", e);
+        match result {
+            Ok(b) => print_dependents(&b, path),
+            Err(e) => println!("Error while building: {}", e),
         }
         println!();
     });
@@ -42,6 +53,23 @@ pub fn execute(args: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
+/// Point out which chapters include a file that changed, since those are
+/// the pages the change shows up in.
+///
+/// The whole book is still rebuilt every time. Only re-rendering these
+/// chapters needs the renderers to support partial builds, which they
+/// don't yet.
+pub fn print_dependents(book: &MDBook, path: &Path) {
+    let names: Vec<&str> = book.chapters_depending_on(path)
+        .into_iter()
+        .map(|ch| ch.name.as_str())
+        .collect();
+
+    if !names.is_empty() {
+        println!("{} is included by: {}", path.display(), names.join(", "));
+    }
+}
+
 /// Calls the closure when a book source file is changed, blocking indefinitely.
 pub fn trigger_on_change<F>(book: &MDBook, closure: F)
 where
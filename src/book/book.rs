@@ -1,65 +1,495 @@
 use std::fmt::{self, Display, Formatter};
-use std::path::{Path, PathBuf};
-use std::collections::VecDeque;
-use std::fs::{self, File};
+use std::path::{Component, Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Write};
 
-use super::summary::{parse_summary, Link, SectionNumber, Summary, SummaryItem};
-use config::BuildConfig;
+use pulldown_cmark::{Event, Parser, Tag};
+
+use super::lint::{lint_summary, report_lints};
+use super::summary::{percent_decode, read_summary_with_map, Link, ParseOptions, SectionNumber,
+                     Summary, SummaryItem};
+use config::{BuildConfig, Config, OrphanPolicy};
+use preprocess::links;
+#[cfg(feature = "yaml")]
+use serde_yaml;
+use toml;
+use utils::{self, FrontMatterFormat};
 use errors::*;
 
 
 /// Load a book into memory from its `src/` directory.
 pub fn load_book<P: AsRef<Path>>(src_dir: P, cfg: &BuildConfig) -> Result<Book> {
+    load(src_dir.as_ref(), cfg, &[], false)
+}
+
+/// Load a book like `load_book()` (or `load_book_lazily()`, if `lazy` is
+/// set) and add each of the `sub_books` directories (relative to `src_dir`,
+/// or absolute paths inside it) to the end of it with `add_sub_book()`.
+/// Files in the sub-books aren't counted as orphans.
+pub fn load_book_with_sub_books<P: AsRef<Path>>(
+    src_dir: P,
+    cfg: &BuildConfig,
+    sub_books: &[PathBuf],
+    lazy: bool,
+) -> Result<Book> {
     let src_dir = src_dir.as_ref();
-    let summary_md = src_dir.join("SUMMARY.md");
 
-    let mut summary_content = String::new();
-    File::open(summary_md)
-        .chain_err(|| "Couldn't open SUMMARY.md")?
-        .read_to_string(&mut summary_content)?;
+    let mut book = load(src_dir, cfg, sub_books, lazy)?;
+    for dir in sub_books {
+        let dir = if dir.is_absolute() {
+            match dir.strip_prefix(src_dir) {
+                Ok(dir) => dir,
+                Err(_) => bail!(
+                    "The sub-book in {} isn't inside the source directory, {}",
+                    dir.display(),
+                    src_dir.display()
+                ),
+            }
+        } else {
+            dir.as_path()
+        };
+
+        if !cfg.allow_symlinks {
+            check_path_for_symlinks(src_dir, dir, "The sub-book")?;
+        }
+        load_sub_book(&mut book, src_dir, dir, lazy)?;
+    }
+    if !cfg.allow_symlinks && !sub_books.is_empty() {
+        // the sub-books were loaded with their own config
+        book.check_for_symlinks(src_dir)?;
+    }
+
+    Ok(book)
+}
+
+/// Load a book from disk like `load_book()`, but without reading any of the
+/// chapters' files until their content is asked for with
+/// `Chapter::content()`.
+pub fn load_book_lazily<P: AsRef<Path>>(src_dir: P, cfg: &BuildConfig) -> Result<Book> {
+    load(src_dir.as_ref(), cfg, &[], true)
+}
+
+fn load(src_dir: &Path, cfg: &BuildConfig, sub_books: &[PathBuf], lazy: bool) -> Result<Book> {
+    let summary_path = src_dir.join("SUMMARY.md");
+    let options = ParseOptions {
+        strict: cfg.deny_warnings,
+        indentation: cfg.summary_indent,
+    };
+    let (summary_src, source_map) = read_summary_with_map(&summary_path)?;
+    let mut summary = Summary::from_source(&summary_path, &summary_src, &source_map, &options)?;
+    summary.validate().chain_err(|| "Invalid SUMMARY.md")?;
+    if let Some(max_depth) = cfg.max_depth {
+        summary.check_depth(max_depth).chain_err(|| "Invalid SUMMARY.md")?;
+    }
+
+    if cfg.use_heading_titles {
+        use_heading_titles(&src_dir, &mut summary);
+    }
+
+    let filename = summary_path.display().to_string();
+    let lint_config = if cfg.deny_warnings {
+        cfg.lints.deny_warnings()
+    } else {
+        cfg.lints.clone()
+    };
+    let mut lints = lint_summary(&summary, &summary_src, &filename, &lint_config);
+    for lint in &mut lints {
+        source_map.remap(lint);
+    }
+    report_lints(&lints, &summary_path)?;
+
+    let orphans = match cfg.orphans {
+        OrphanPolicy::Warn if cfg.deny_warnings => OrphanPolicy::Error,
+        other => other,
+    };
+    if orphans != OrphanPolicy::Ignore {
+        check_orphans(&src_dir, &mut summary, orphans, sub_books)?;
+    }
 
-    let summary = parse_summary(&summary_content).chain_err(|| "Summary parsing failed")?;
+    if !cfg.allow_symlinks {
+        for file in source_map.files() {
+            check_path_for_symlinks(src_dir, file, "SUMMARY.md")?;
+        }
+        check_for_symlinks(&src_dir, &summary)?;
+    }
 
     if cfg.create_missing {
         create_missing(&src_dir, &summary).chain_err(|| "Unable to create missing chapters")?;
     }
 
-    load_book_from_disk(&summary, src_dir)
+    let book = load_book_from_disk(&summary, src_dir, lazy)?;
+    if !cfg.allow_symlinks {
+        book.check_for_symlinks(src_dir)?;
+    }
+    check_assets(src_dir, &book, cfg.deny_warnings)?;
+
+    Ok(book)
 }
 
-fn create_missing(src_dir: &Path, summary: &Summary) -> Result<()> {
-    let mut items: Vec<_> = summary
-        .prefix_chapters
-        .iter()
-        .chain(summary.numbered_chapters.iter())
-        .chain(summary.suffix_chapters.iter())
+/// Warn about (or, if `deny_warnings` is set, refuse to load a book with)
+/// assets chapters refer to which don't exist.
+fn check_assets(src_dir: &Path, book: &Book, deny_warnings: bool) -> Result<()> {
+    let missing: Vec<_> = book.assets()
+        .into_iter()
+        .filter(|asset| !src_dir.join(asset).exists())
         .collect();
 
-    while !items.is_empty() {
-        let next = items.pop().expect("already checked");
+    if missing.is_empty() {
+        return Ok(());
+    }
 
-        if let SummaryItem::Link(ref link) = *next {
-            let filename = src_dir.join(&link.location);
-            if !filename.exists() {
-                if let Some(parent) = filename.parent() {
-                    if !parent.exists() {
-                        fs::create_dir_all(parent)?;
-                    }
-                }
-                debug!("[*] Creating missing file {}", filename.display());
+    if deny_warnings {
+        let missing: Vec<_> = missing.iter().map(|m| m.display().to_string()).collect();
+        bail!("These files are used by chapters but don't exist: {}", missing.join(", "));
+    }
+
+    for asset in &missing {
+        warn!("{} is used by a chapter but doesn't exist", asset.display());
+    }
+
+    Ok(())
+}
+
+/// Load the book in `dir`, a directory inside `src_dir` with its own
+/// `SUMMARY.md` and (optionally) `book.toml`, and add it to the end of `book`
+/// under a part title.
+///
+/// The sub-book's chapter paths are prefixed with `dir`, so they stay
+/// relative to `src_dir`.
+pub fn add_sub_book<P: AsRef<Path>>(book: &mut Book, src_dir: &Path, dir: P) -> Result<()> {
+    load_sub_book(book, src_dir, dir.as_ref(), false)
+}
+
+fn load_sub_book(book: &mut Book, src_dir: &Path, dir: &Path, lazy: bool) -> Result<()> {
+    let root = src_dir.join(dir);
+    debug!("[*] Loading the sub-book in {}", root.display());
+
+    let config_location = root.join("book.toml");
+    let config = if config_location.exists() {
+        Config::from_disk(&config_location)?
+    } else {
+        Config::default()
+    };
+
+    let sub_book = load_book_with_sub_books(&root, &config.build, &config.book.sub_books, lazy)
+        .chain_err(|| format!("Unable to load the sub-book in {}", root.display()))?;
+
+    let title = config.book.title.unwrap_or_else(|| dir.display().to_string());
+    book.merge(sub_book, dir, &title);
+
+    Ok(())
+}
+
+fn prefix_paths(items: &mut [BookItem], prefix: &Path) {
+    for item in items {
+        if let BookItem::Chapter(ref mut ch) = *item {
+            if !ch.is_draft() {
+                ch.path = prefix.join(&ch.path);
+            }
+            for dependency in &mut ch.dependencies {
+                *dependency = prefix.join(&*dependency);
+            }
+            for asset in &mut ch.assets {
+                *asset = prefix.join(&*asset);
+            }
+            prefix_paths(&mut ch.sub_items, prefix);
+        }
+    }
+}
+
+/// Make sure no chapter is a symlink or inside a symlinked directory, and
+/// that none lives outside of `src_dir`.
+fn check_for_symlinks(src_dir: &Path, summary: &Summary) -> Result<()> {
+    for (link, _) in summary.iter() {
+        if !link.is_draft() {
+            let what = format!("\"{}\"", link.name);
+            check_path_for_symlinks(src_dir, &link.location, &what)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Make sure the file at `location` (relative to `src_dir`) isn't a symlink
+/// or inside a symlinked directory, and doesn't live outside of `src_dir`.
+/// The error message describes the file as `what`.
+fn check_path_for_symlinks(src_dir: &Path, location: &Path, what: &str) -> Result<()> {
+    let full_path = src_dir.join(location);
+    let relative = match full_path.strip_prefix(src_dir) {
+        Ok(relative) if !relative.components().any(|c| c == Component::ParentDir) => {
+            relative.to_path_buf()
+        }
+        _ => bail!(
+            "{} ({}) is outside the source directory, which isn't allowed when \
+             `build.allow-symlinks` is false",
+            what,
+            location.display()
+        ),
+    };
+
+    let mut path = src_dir.to_path_buf();
+    for component in relative.components() {
+        path.push(component.as_os_str());
+        let is_symlink = fs::symlink_metadata(&path)
+            .map(|meta| meta.file_type().is_symlink())
+            .unwrap_or(false);
+
+        if is_symlink {
+            bail!(
+                "{} ({}) goes through a symlink at {}, which isn't allowed when \
+                 `build.allow-symlinks` is false",
+                what,
+                location.display(),
+                path.display()
+            );
+        }
+    }
+
+    Ok(())
+}
 
-                let mut f = File::create(&filename)?;
-                writeln!(f, "# {}", link.name)?;
+fn create_missing(src_dir: &Path, summary: &Summary) -> Result<()> {
+    for (link, _) in summary.iter() {
+        let filename = src_dir.join(&link.location);
+        if !link.is_draft() && !filename.exists() {
+            if let Some(parent) = filename.parent() {
+                if !parent.exists() {
+                    fs::create_dir_all(parent)?;
+                }
             }
+            debug!("[*] Creating missing file {}", filename.display());
+
+            let mut f = File::create(&filename)?;
+            writeln!(f, "# {}", link.name)?;
+        }
+    }
+
+    Ok(())
+}
 
-            items.extend(&link.nested_items);
+fn check_orphans(
+    src_dir: &Path,
+    summary: &mut Summary,
+    policy: OrphanPolicy,
+    sub_books: &[PathBuf],
+) -> Result<()> {
+    let orphans = summary.find_orphans_excluding(src_dir, sub_books);
+    if orphans.is_empty() {
+        return Ok(());
+    }
+
+    match policy {
+        OrphanPolicy::Ignore => {}
+        OrphanPolicy::Warn => for orphan in &orphans {
+            warn!("{} isn't used by any chapter in SUMMARY.md", orphan.display());
+        },
+        OrphanPolicy::Error => {
+            let orphans: Vec<_> = orphans.iter().map(|o| o.display().to_string()).collect();
+            bail!("These files aren't used by SUMMARY.md: {}", orphans.join(", "));
+        }
+        OrphanPolicy::Append => {
+            add_orphan_drafts(src_dir, summary, &orphans)
+                .chain_err(|| "Unable to add orphaned files to the book")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Add each orphaned file to the end of the in-memory `Summary` as a draft
+/// suffix chapter, named after its first heading. `SUMMARY.md` itself is left
+/// alone, use `append_orphans()` to write the drafts out.
+fn add_orphan_drafts(src_dir: &Path, summary: &mut Summary, orphans: &[PathBuf]) -> Result<()> {
+    for orphan in orphans {
+        let name = orphan_name(src_dir, orphan)?;
+        warn!(
+            "{} isn't used by any chapter in SUMMARY.md, adding it as a draft",
+            orphan.display()
+        );
+
+        if !has_draft(summary, &name) {
+            summary.suffix_chapters.push(SummaryItem::Link(Link::new(name, "")));
         }
     }
 
     Ok(())
 }
 
+/// Append a draft chapter to the end of `src_dir/SUMMARY.md` for each file
+/// which isn't used by any chapter (or in one of the `sub_books`), followed
+/// by a comment saying which file it is for. Orphans which already have a
+/// draft with the same name are skipped, so running this twice doesn't add
+/// them again.
+///
+/// Returns the files which were added.
+pub fn append_orphans<P: AsRef<Path>>(src_dir: P, sub_books: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let src_dir = src_dir.as_ref();
+    let summary_path = src_dir.join("SUMMARY.md");
+    let summary = Summary::from_path(&summary_path)?;
+
+    let mut added = Vec::new();
+    let mut drafts = String::new();
+    for orphan in summary.find_orphans_excluding(src_dir, sub_books) {
+        let name = orphan_name(src_dir, &orphan)?;
+        if has_draft(&summary, &name) {
+            continue;
+        }
+
+        debug!("[*] Adding {} to SUMMARY.md", orphan.display());
+        let draft = Link::new(name, "");
+        drafts.push_str(&format!("\n{} <!-- {} -->\n", draft, orphan.display()));
+        added.push(orphan);
+    }
+
+    if added.is_empty() {
+        return Ok(added);
+    }
+
+    let mut existing = String::new();
+    File::open(&summary_path)?.read_to_string(&mut existing)?;
+    let mut f = OpenOptions::new().append(true).open(&summary_path)?;
+    if !existing.ends_with('\n') {
+        writeln!(f)?;
+    }
+    f.write_all(drafts.as_bytes())?;
+
+    Ok(added)
+}
+
+/// The name to give an orphaned file's draft chapter, either its first
+/// heading or the file's name.
+fn orphan_name(src_dir: &Path, orphan: &Path) -> Result<String> {
+    let mut content = String::new();
+    File::open(src_dir.join(orphan))?.read_to_string(&mut content)?;
+
+    Ok(first_heading(&content).unwrap_or_else(|| {
+        orphan.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+    }))
+}
+
+/// Does the summary already contain a draft chapter called `name`?
+fn has_draft(summary: &Summary, name: &str) -> bool {
+    summary.iter().any(|(link, _)| link.is_draft() && link.name == name)
+}
+
+/// Give any unnamed chapters the name of the first heading in their file.
+/// Chapters whose file can't be read are left alone.
+fn use_heading_titles(src_dir: &Path, summary: &mut Summary) {
+    summary.walk_mut(|link| {
+        if link.is_draft() || !link.name.trim().is_empty() {
+            return;
+        }
+
+        let mut content = String::new();
+        let read = File::open(src_dir.join(&link.location))
+            .and_then(|mut f| f.read_to_string(&mut content));
+
+        if read.is_ok() {
+            if let Some(heading) = first_heading(&content) {
+                debug!("[*] Naming {} \"{}\"", link.location.display(), heading);
+                link.name = heading;
+            }
+        }
+    });
+}
+
+/// Find the files included by the chapter at `path`, relative to the `src`
+/// directory.
+fn find_dependencies(path: &Path, content: &str) -> Vec<PathBuf> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    links::dependencies(content)
+        .into_iter()
+        .map(|dep| utils::fs::collapse_path(dir.join(dep)))
+        .collect()
+}
+
+/// Find the images and files (other than chapters) which the chapter at
+/// `path` links to, relative to the `src` directory.
+///
+/// Images are always assets, but links only count when they point at a file
+/// with an extension, so links to directories or pages generated by the
+/// renderer aren't mistaken for missing files.
+fn find_assets(path: &Path, content: &str) -> Vec<PathBuf> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut assets = Vec::new();
+
+    for event in Parser::new(content) {
+        let (dest, is_image) = match event {
+            Event::Start(Tag::Image(dest, _)) => (dest, true),
+            Event::Start(Tag::Link(dest, _)) => (dest, false),
+            _ => continue,
+        };
+
+        if dest.is_empty() || dest.starts_with('#') || dest.starts_with('/')
+            || dest.contains("://") || dest.starts_with("mailto:")
+        {
+            continue;
+        }
+
+        let file = dest.split(|c: char| c == '#' || c == '?').next().unwrap_or("");
+        let file = percent_decode(file);
+        let is_asset = match Path::new(&file).extension() {
+            Some(ext) => ext != "md" && ext != "markdown" && ext != "html",
+            None => is_image,
+        };
+        if !is_asset {
+            continue;
+        }
+
+        let asset = utils::fs::collapse_path(dir.join(&file));
+        if !assets.contains(&asset) {
+            assets.push(asset);
+        }
+    }
+
+    assets
+}
+
+/// Count the words in a chapter's text, ignoring code blocks and markup.
+fn count_words(content: &str) -> usize {
+    let mut in_code_block = false;
+    let mut words = 0;
+
+    for event in Parser::new(content) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            Event::Text(ref text) if !in_code_block => words += text.split_whitespace().count(),
+            _ => {}
+        }
+    }
+
+    words
+}
+
+/// Get the text of the first top-level (`# ...`) heading in a chapter.
+pub fn first_heading(content: &str) -> Option<String> {
+    let mut events = Parser::new(content);
+
+    while let Some(event) = events.next() {
+        if let Event::Start(Tag::Header(1)) = event {
+            let mut heading = String::new();
+
+            for event in &mut events {
+                match event {
+                    Event::End(Tag::Header(1)) => break,
+                    Event::Text(text) => heading.push_str(&text),
+                    _ => {}
+                }
+            }
+
+            let heading = heading.trim();
+            if !heading.is_empty() {
+                return Some(heading.to_string());
+            }
+        }
+    }
+
+    None
+}
+
 
 /// A dumb tree structure representing a book.
 ///
@@ -82,6 +512,318 @@ impl Book {
             items: self.sections.iter().collect(),
         }
     }
+
+    /// Find every chapter which includes the file at `path` (relative to the
+    /// book's `src` directory), and so needs rebuilding if it changes.
+    pub fn chapters_depending_on<P: AsRef<Path>>(&self, path: P) -> Vec<&Chapter> {
+        let path = path.as_ref();
+
+        self.chapters()
+            .map(|(_, _, ch)| ch)
+            .filter(|ch| ch.dependencies.iter().any(|dep| dep == path))
+            .collect()
+    }
+
+    /// Get the content hash of every (non-draft) chapter in the book.
+    pub fn content_hashes(&self) -> ContentHashes {
+        self.chapters()
+            .map(|(_, _, ch)| ch)
+            .filter(|ch| !ch.is_draft())
+            .map(|ch| (ch.path.clone(), ch.content_hash))
+            .collect()
+    }
+
+    /// Find the chapters which are new, or whose source or includes changed,
+    /// since the `previous` hashes were taken.
+    pub fn changed_since(&self, previous: &ContentHashes) -> Vec<&Chapter> {
+        self.chapters()
+            .map(|(_, _, ch)| ch)
+            .filter(|ch| !ch.is_draft())
+            .filter(|ch| previous.get(&ch.path) != Some(&ch.content_hash))
+            .collect()
+    }
+
+    /// Get every image and file (other than chapters) linked to by the book's
+    /// chapters, relative to the `src` directory.
+    pub fn assets(&self) -> BTreeSet<PathBuf> {
+        self.chapters()
+            .flat_map(|(_, _, ch)| ch.assets.iter().cloned())
+            .collect()
+    }
+
+    /// How many words of prose there are in the whole book.
+    pub fn word_count(&self) -> usize {
+        self.chapters().map(|(_, _, ch)| ch.word_count).sum()
+    }
+
+    /// Get an iterator over every chapter in reading order, along with its
+    /// section number (empty for unnumbered chapters) and depth.
+    pub fn chapters(&self) -> Chapters {
+        Chapters { items: self.iter() }
+    }
+
+    /// Fill in every draft chapter whose `generator` attribute is `name`
+    /// with content from `generator`, giving it the path `<name>.md`. When
+    /// several chapters use the same generator they get the paths
+    /// `<name>-1.md`, `<name>-2.md` and so on instead.
+    ///
+    /// It's an error for one of these paths to belong to a chapter which is
+    /// already in the book.
+    pub fn generate(&mut self, name: &str, generator: &Generator) -> Result<()> {
+        let mut generated = Vec::new();
+        for item in self.iter() {
+            if let BookItem::Chapter(ref ch) = *item {
+                if is_generated_by(ch, name) {
+                    let content = generator
+                        .generate(ch, self)
+                        .chain_err(|| format!("Unable to generate \"{}\"", ch.name))?;
+                    generated.push(content);
+                }
+            }
+        }
+
+        if generated.is_empty() {
+            warn!("No chapters use the \"{}\" generator", name);
+            return Ok(());
+        }
+
+        let paths: Vec<PathBuf> = if generated.len() == 1 {
+            vec![PathBuf::from(format!("{}.md", name))]
+        } else {
+            (1..generated.len() + 1)
+                .map(|n| PathBuf::from(format!("{}-{}.md", name, n)))
+                .collect()
+        };
+        for path in &paths {
+            let existing = self.chapters()
+                .map(|(_, _, ch)| ch)
+                .find(|ch| !ch.is_draft() && output_key(&ch.path) == output_key(path));
+            if let Some(ch) = existing {
+                bail!(
+                    "The \"{}\" generator can't use {} for its chapter, \"{}\" ({}) is \
+                     already written there",
+                    name,
+                    path.display(),
+                    ch.name,
+                    ch.path.display()
+                );
+            }
+        }
+
+        let mut generated = generated.into_iter().zip(paths);
+        self.for_each_mut(|ch| if is_generated_by(ch, name) {
+            let (content, path) = generated.next().expect("Every generated chapter has content");
+            ch.content = content;
+            ch.word_count = count_words(&ch.content);
+            ch.path = path;
+        });
+        link_neighbours(self);
+
+        Ok(())
+    }
+
+    /// Add every chapter in `other` to the end of this book under a part
+    /// title, with their paths (and those of the files they include) moved
+    /// into the `prefix` directory.
+    pub fn merge<P: AsRef<Path>>(&mut self, mut other: Book, prefix: P, title: &str) {
+        prefix_paths(&mut other.sections, prefix.as_ref());
+
+        self.sections.push(BookItem::PartTitle(title.to_string()));
+        self.sections.extend(other.sections);
+        link_neighbours(self);
+    }
+
+    /// Make sure none of the chapters' files, the files they include or the
+    /// assets they link to are symlinks (or inside a symlinked directory) or
+    /// outside of `src_dir`, for builds with `build.allow-symlinks` turned
+    /// off. Chapters which haven't been read yet only have their own file
+    /// checked.
+    pub fn check_for_symlinks<P: AsRef<Path>>(&self, src_dir: P) -> Result<()> {
+        let src_dir = src_dir.as_ref();
+
+        for item in self.iter() {
+            let ch = match *item {
+                BookItem::Chapter(ref ch) if !ch.is_draft() => ch,
+                _ => continue,
+            };
+
+            let name = format!("\"{}\"", ch.name);
+            check_path_for_symlinks(src_dir, &ch.path, &name)?;
+
+            let included = format!("A file included by \"{}\"", ch.name);
+            for dependency in &ch.dependencies {
+                check_path_for_symlinks(src_dir, dependency, &included)?;
+            }
+            let linked = format!("A file linked to by \"{}\"", ch.name);
+            for asset in &ch.assets {
+                check_path_for_symlinks(src_dir, asset, &linked)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the content of every chapter which hasn't been read yet, for
+    /// books loaded with `load_book_lazily()`. Chapters which have already
+    /// been read aren't touched.
+    pub fn load_content(&mut self) -> Result<()> {
+        load_content(&mut self.sections)
+    }
+
+    /// Remove every unpublished chapter (and the chapters nested inside it)
+    /// from the book.
+    ///
+    /// Chapters loaded lazily only know they're unpublished from their front
+    /// matter once `load_content()` has read it.
+    pub fn remove_unpublished(&mut self) {
+        remove_unpublished(&mut self.sections);
+        link_neighbours(self);
+    }
+
+    /// Make sure every chapter can be written to its own file inside the
+    /// output directory on any platform.
+    ///
+    /// This catches paths which escape the output directory (e.g. with `..`),
+    /// names Windows can't use, and different files which would be rendered
+    /// to the same page, either because they only differ by extension
+    /// (`intro.md` and `intro.markdown`) or by case (which would overwrite
+    /// each other on a case-insensitive file system). Chapters linking to
+    /// anchors in the same file are fine.
+    pub fn check_output_paths(&self) -> Result<()> {
+        let mut seen: HashMap<String, (&Path, &str)> = HashMap::new();
+
+        for item in self.iter() {
+            let ch = match *item {
+                BookItem::Chapter(ref ch) if !ch.is_draft() => ch,
+                _ => continue,
+            };
+
+            if let Some(problem) = output_path_problem(&ch.path) {
+                bail!("\"{}\" ({}) {}", ch.name, ch.path.display(), problem);
+            }
+
+            let output = ch.path.with_extension("html");
+            let key = output_key(&ch.path);
+            if let Some(&(other_path, other_name)) = seen.get(&key) {
+                if other_path != ch.path.as_path() {
+                    bail!(
+                        "\"{}\" ({}) and \"{}\" ({}) would both be written to {} (file names \
+                         are compared case-insensitively)",
+                        other_name,
+                        other_path.display(),
+                        ch.name,
+                        ch.path.display(),
+                        output.display()
+                    );
+                }
+            }
+            seen.insert(key, (ch.path.as_path(), ch.name.as_str()));
+        }
+
+        Ok(())
+    }
+
+    /// Call `func` on every chapter in reading order, so it can change them
+    /// in place.
+    pub fn for_each_mut<F: FnMut(&mut Chapter)>(&mut self, mut func: F) {
+        for_each_mut(&mut func, &mut self.sections);
+    }
+}
+
+/// Explain why a chapter can't be rendered to `path`, if it can't.
+fn output_path_problem(path: &Path) -> Option<&'static str> {
+    const RESERVED_NAMES: &'static [&'static str] = &[
+        "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+        "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+    ];
+
+    for component in path.components() {
+        let part = match component {
+            Component::Normal(part) => part.to_string_lossy(),
+            Component::CurDir => continue,
+            _ => return Some("would be written outside the output directory"),
+        };
+
+        if part.chars().any(|c| "<>:\"|?*\\".contains(c) || c.is_control()) {
+            return Some("contains characters which aren't allowed in file names on Windows");
+        }
+        if part.ends_with('.') || part.ends_with(' ') {
+            return Some("has a file or directory name ending in a dot or space");
+        }
+
+        let stem = part.split('.').next().unwrap_or("").to_lowercase();
+        if RESERVED_NAMES.contains(&stem.as_str()) {
+            return Some("uses a file or directory name which is reserved on Windows");
+        }
+    }
+
+    None
+}
+
+/// The file a chapter at `path` is rendered to, lowercased because some
+/// filesystems ignore case.
+fn output_key(path: &Path) -> String {
+    path.with_extension("html").to_string_lossy().to_lowercase()
+}
+
+fn is_generated_by(ch: &Chapter, name: &str) -> bool {
+    ch.is_draft() && ch.attributes.get("generator").map(|g| g.as_str()) == Some(name)
+}
+
+/// Something which produces the content of a chapter programmatically (e.g.
+/// an index or changelog) instead of it being read from disk.
+///
+/// Generated chapters are declared in `SUMMARY.md` as draft chapters with a
+/// `generator` attribute naming the generator to use.
+///
+/// ```markdown
+/// - [Changelog]() {generator=changelog}
+/// ```
+pub trait Generator {
+    /// Produce the content for `chapter`, given the rest of the book.
+    fn generate(&self, chapter: &Chapter, book: &Book) -> Result<String>;
+}
+
+impl<F> Generator for F
+where
+    F: Fn(&Chapter, &Book) -> Result<String>,
+{
+    fn generate(&self, chapter: &Chapter, book: &Book) -> Result<String> {
+        self(chapter, book)
+    }
+}
+
+fn load_content(items: &mut [BookItem]) -> Result<()> {
+    for item in items {
+        if let BookItem::Chapter(ref mut ch) = *item {
+            ch.content()?;
+            load_content(&mut ch.sub_items)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn remove_unpublished(items: &mut Vec<BookItem>) {
+    items.retain(|item| match *item {
+        BookItem::Chapter(ref ch) => !ch.is_unpublished(),
+        _ => true,
+    });
+
+    for item in items {
+        if let BookItem::Chapter(ref mut ch) = *item {
+            remove_unpublished(&mut ch.sub_items);
+        }
+    }
+}
+
+fn for_each_mut<F: FnMut(&mut Chapter)>(func: &mut F, items: &mut [BookItem]) {
+    for item in items {
+        if let BookItem::Chapter(ref mut ch) = *item {
+            func(ch);
+            for_each_mut(func, &mut ch.sub_items);
+        }
+    }
 }
 
 /// Enum representing any type of item which can be added to a book.
@@ -91,6 +833,8 @@ pub enum BookItem {
     Chapter(Chapter),
     /// A section separator.
     Separator,
+    /// A part title.
+    PartTitle(String),
 }
 
 /// The representation of a "chapter", usually mapping to a single file on
@@ -99,7 +843,15 @@ pub enum BookItem {
 pub struct Chapter {
     /// The chapter's name.
     pub name: String,
-    /// The chapter's contents.
+    /// The chapter's name with any inline markdown (e.g. code spans) intact,
+    /// if it has formatting.
+    pub markdown_name: Option<String>,
+    /// The link title given in `SUMMARY.md`, if there was one.
+    pub title: Option<String>,
+    /// The chapter's contents, once they have been read. For a book loaded
+    /// lazily this stays empty (along with the `metadata`, `word_count`,
+    /// `dependencies`, `assets` and `content_hash`) until `content()` or
+    /// `Book::load_content()` reads the chapter's file.
     pub content: String,
     /// The chapter's section number, if it has one.
     pub number: Option<SectionNumber>,
@@ -107,6 +859,81 @@ pub struct Chapter {
     pub sub_items: Vec<BookItem>,
     /// The chapter's location, relative to the `SUMMARY.md` file.
     pub path: PathBuf,
+    /// The anchor to link to within the chapter, if `SUMMARY.md` gave one.
+    pub fragment: Option<String>,
+    /// Any attributes given after the chapter's link in `SUMMARY.md`.
+    pub attributes: HashMap<String, String>,
+    /// The names of the chapters this one is nested inside, outermost first.
+    pub parent_names: Vec<String>,
+    /// How deeply this chapter is nested, where top-level chapters have a
+    /// depth of `0`.
+    pub depth: usize,
+    /// The path of the chapter before this one in reading order, skipping
+    /// drafts.
+    pub previous: Option<PathBuf>,
+    /// The path of the chapter after this one in reading order, skipping
+    /// drafts.
+    pub next: Option<PathBuf>,
+    /// Metadata from the front matter at the top of the chapter's file.
+    pub metadata: ChapterMetadata,
+    /// How many words of prose the chapter has, not counting code blocks.
+    pub word_count: usize,
+    /// The files pulled into the chapter by `{{#include}}` and `{{#playpen}}`
+    /// links, relative to the book's `src` directory.
+    pub dependencies: Vec<PathBuf>,
+    /// The images and other files the chapter links to, relative to the
+    /// book's `src` directory.
+    pub assets: Vec<PathBuf>,
+    /// A hash of the chapter's source file and everything it includes, for
+    /// telling whether it changed between builds.
+    pub content_hash: u64,
+    /// The file to read the chapter's content (and metadata) from the first
+    /// time `content()` is called, if the book was loaded lazily.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+/// The `content_hash` of every chapter in a book, keyed by path.
+///
+/// Hashes are only comparable between builds made with the same version of
+/// mdBook (and the compiler it was built with); anything else just looks
+/// like every chapter changed.
+pub type ContentHashes = BTreeMap<PathBuf, u64>;
+
+/// The reading speed used to estimate how long a chapter takes to read.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Information about a chapter given in a block of front matter at the very
+/// top of its file, which is removed from the chapter's `content`.
+///
+/// ```markdown
+/// +++
+/// author = "Jane Doe"
+/// date = "2017-11-20"
+/// keywords = ["intro", "setup"]
+/// +++
+///
+/// # Getting Started
+/// ```
+///
+/// The front matter can also be YAML, between `---` lines, when mdbook is
+/// built with the `yaml` feature. A `---` block which isn't a YAML mapping
+/// (a horizontal rule, say) is left in the chapter as normal text.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChapterMetadata {
+    /// Who wrote the chapter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// When the chapter was written or last updated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// Keywords describing the chapter's contents.
+    pub keywords: Vec<String>,
+    /// Whether the chapter is still a work in progress.
+    pub draft: bool,
+    /// Leave the chapter out of the built book.
+    pub unpublished: bool,
 }
 
 impl Chapter {
@@ -114,18 +941,55 @@ impl Chapter {
     pub fn new<P: Into<PathBuf>>(name: &str, content: String, path: P) -> Chapter {
         Chapter {
             name: name.to_string(),
+            word_count: count_words(&content),
+            content_hash: content_hash(&content, Path::new(""), &[]),
             content: content,
             path: path.into(),
             ..Default::default()
         }
     }
+
+    /// Roughly how many minutes it takes to read the chapter, rounded up.
+    pub fn reading_time(&self) -> usize {
+        (self.word_count + WORDS_PER_MINUTE - 1) / WORDS_PER_MINUTE
+    }
+
+    /// Should this chapter be left out of the built book? Chapters can be
+    /// unpublished with an `unpublished` attribute in `SUMMARY.md` or with
+    /// `unpublished = true` in their front matter.
+    pub fn is_unpublished(&self) -> bool {
+        let attribute = match self.attributes.get("unpublished") {
+            Some(value) => value != "false",
+            None => false,
+        };
+
+        attribute || self.metadata.unpublished
+    }
+
+    /// Is this a draft chapter which doesn't have a source file yet?
+    pub fn is_draft(&self) -> bool {
+        self.path.as_os_str().is_empty()
+    }
+
+    /// Get the chapter's content, reading it from disk first if the book was
+    /// loaded lazily and nothing has asked for it yet.
+    pub fn content(&mut self) -> Result<&str> {
+        if let Some(location) = self.source.clone() {
+            read_chapter(self, &location)?;
+            self.source = None;
+        }
+
+        Ok(&self.content)
+    }
 }
 
 /// Use the provided `Summary` to load a `Book` from disk.
 ///
 /// You need to pass in the book's source directory because all the links in
 /// `SUMMARY.md` give the chapter locations relative to it.
-fn load_book_from_disk<P: AsRef<Path>>(summary: &Summary, src_dir: P) -> Result<Book> {
+///
+/// If `lazy` is set, the chapters' files are only checked for and not read.
+fn load_book_from_disk<P: AsRef<Path>>(summary: &Summary, src_dir: P, lazy: bool) -> Result<Book> {
     debug!("[*] Loading the book from disk");
     let src_dir = src_dir.as_ref();
 
@@ -138,51 +1002,230 @@ fn load_book_from_disk<P: AsRef<Path>>(summary: &Summary, src_dir: P) -> Result<
     let mut chapters = Vec::new();
 
     for summary_item in summary_items {
-        let chapter = load_summary_item(summary_item, src_dir)?;
+        let chapter = load_summary_item(summary_item, src_dir, lazy)?;
         chapters.push(chapter);
     }
 
-    Ok(Book { sections: chapters })
+    assign_parents(&mut chapters, &[]);
+
+    let mut book = Book { sections: chapters };
+    link_neighbours(&mut book);
+
+    Ok(book)
 }
 
-fn load_summary_item<P: AsRef<Path>>(item: &SummaryItem, src_dir: P) -> Result<BookItem> {
+fn link_neighbours(book: &mut Book) {
+    let paths: Vec<PathBuf> = book.iter()
+        .filter_map(|item| match *item {
+            BookItem::Chapter(ref ch) if !ch.is_draft() => Some(ch.path.clone()),
+            _ => None,
+        })
+        .collect();
+    assign_neighbours(&mut book.sections, &paths, &mut 0);
+}
+
+/// Tell each chapter which chapters it's nested inside.
+fn assign_parents(items: &mut [BookItem], parent_names: &[String]) {
+    for item in items {
+        if let BookItem::Chapter(ref mut ch) = *item {
+            ch.parent_names = parent_names.to_vec();
+            ch.depth = parent_names.len();
+
+            let mut names = parent_names.to_vec();
+            names.push(ch.name.clone());
+            assign_parents(&mut ch.sub_items, &names);
+        }
+    }
+}
+
+/// Give each (non-draft) chapter the paths of the chapters either side of
+/// it, where `paths` lists every chapter in depth-first order and `index` is
+/// the position of the next one we'll visit.
+fn assign_neighbours(items: &mut [BookItem], paths: &[PathBuf], index: &mut usize) {
+    for item in items {
+        if let BookItem::Chapter(ref mut ch) = *item {
+            if !ch.is_draft() {
+                ch.previous = if *index > 0 {
+                    paths.get(*index - 1).cloned()
+                } else {
+                    None
+                };
+                ch.next = paths.get(*index + 1).cloned();
+                *index += 1;
+            }
+
+            assign_neighbours(&mut ch.sub_items, paths, index);
+        }
+    }
+}
+
+fn load_summary_item<P: AsRef<Path>>(
+    item: &SummaryItem,
+    src_dir: P,
+    lazy: bool,
+) -> Result<BookItem> {
     match *item {
         SummaryItem::Separator => Ok(BookItem::Separator),
-        SummaryItem::Link(ref link) => load_chapter(link, src_dir).map(|c| BookItem::Chapter(c)),
+        SummaryItem::PartTitle(ref title) => Ok(BookItem::PartTitle(title.clone())),
+        SummaryItem::Link(ref link) => {
+            load_chapter(link, src_dir, lazy).map(|c| BookItem::Chapter(c))
+        }
     }
 }
 
-fn load_chapter<P: AsRef<Path>>(link: &Link, src_dir: P) -> Result<Chapter> {
+fn load_chapter<P: AsRef<Path>>(link: &Link, src_dir: P, lazy: bool) -> Result<Chapter> {
     debug!("[*] Loading {} ({})", link.name, link.location.display());
     let src_dir = src_dir.as_ref();
 
+    if link.is_draft() {
+        let mut ch = Chapter::new(&link.name, String::new(), PathBuf::new());
+        ch.markdown_name = link.markdown_name.clone();
+        ch.title = link.title.clone();
+        ch.fragment = link.fragment.clone();
+        ch.attributes = link.attributes.clone();
+        ch.number = link.number.clone();
+        ch.sub_items = load_nested_items(link, src_dir, lazy)?;
+        return Ok(ch);
+    }
+
     let location = if link.location.is_absolute() {
         link.location.clone()
     } else {
         src_dir.join(&link.location)
     };
 
-    let mut f = File::open(&location)
-        .chain_err(|| format!("Chapter file not found, {}", link.location.display()))?;
-
-    let mut content = String::new();
-    f.read_to_string(&mut content)?;
+    if !location.exists() {
+        let kind = ErrorKind::MissingChapter(
+            link.name.clone(),
+            link.location.clone(),
+            link.span,
+            None,
+        );
+        return Err(kind.into());
+    }
 
     let stripped = location
         .strip_prefix(&src_dir)
-        .expect("Chapters are always inside a book");
+        .expect("Chapters are always inside a book")
+        .to_path_buf();
 
-    let mut ch = Chapter::new(&link.name, content, stripped);
+    let mut ch = Chapter::new(&link.name, String::new(), stripped);
+    if lazy {
+        ch.source = Some(location);
+    } else {
+        read_chapter(&mut ch, &location)?;
+    }
+    ch.markdown_name = link.markdown_name.clone();
+    ch.title = link.title.clone();
+    ch.fragment = link.fragment.clone();
+    ch.attributes = link.attributes.clone();
     ch.number = link.number.clone();
+    ch.sub_items = load_nested_items(link, src_dir, lazy)?;
 
-    let sub_items = link.nested_items
-        .iter()
-        .map(|i| load_summary_item(i, src_dir))
-        .collect::<Result<Vec<_>>>()?;
+    Ok(ch)
+}
 
-    ch.sub_items = sub_items;
+/// Deserialize a chapter's front matter, or return `None` if the block turns
+/// out not to be front matter after all.
+fn parse_metadata(format: FrontMatterFormat, src: &str) -> Option<Result<ChapterMetadata>> {
+    match format {
+        FrontMatterFormat::Toml => Some(toml::from_str(src).chain_err(|| "Invalid TOML")),
+        FrontMatterFormat::Yaml => parse_yaml_metadata(src),
+    }
+}
 
-    Ok(ch)
+/// `---` lines are also how markdown writes a horizontal rule or underlines a
+/// heading, so a block only counts as front matter if it's a YAML mapping.
+#[cfg(feature = "yaml")]
+fn parse_yaml_metadata(src: &str) -> Option<Result<ChapterMetadata>> {
+    match serde_yaml::from_str::<serde_yaml::Value>(src) {
+        Ok(serde_yaml::Value::Mapping(_)) => {
+            Some(serde_yaml::from_str(src).chain_err(|| "Invalid YAML"))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "yaml"))]
+fn parse_yaml_metadata(_src: &str) -> Option<Result<ChapterMetadata>> {
+    debug!("[*] Treating a `---` block as text because the `yaml` feature is disabled");
+    None
+}
+
+/// Read a chapter's file at `location` into `ch`, splitting off any front
+/// matter and filling in everything derived from its content.
+fn read_chapter(ch: &mut Chapter, location: &Path) -> Result<()> {
+    let mut raw = String::new();
+    File::open(location)
+        .and_then(|mut f| f.read_to_string(&mut raw))
+        .chain_err(|| format!("Unable to read \"{}\" ({})", ch.name, location.display()))?;
+
+    let mut metadata = ChapterMetadata::default();
+    let mut body_start = 0;
+    if let Some((format, src, start)) = utils::any_front_matter(&raw)
+        .map(|(format, src, start)| (format, src.to_string(), start))
+    {
+        if let Some(parsed) = parse_metadata(format, &src) {
+            metadata = parsed
+                .chain_err(|| format!("Invalid front matter in {}", location.display()))?;
+            body_start = start;
+        }
+    }
+    let content = raw[body_start..].to_string();
+
+    // the chapter's path was joined onto the source directory to get here
+    let mut src_dir = location.to_path_buf();
+    for _ in ch.path.components() {
+        src_dir.pop();
+    }
+
+    ch.word_count = count_words(&content);
+    ch.dependencies = find_dependencies(&ch.path, &content);
+    ch.assets = find_assets(&ch.path, &content);
+    ch.content_hash = content_hash(&raw, &src_dir, &ch.dependencies);
+    ch.content = content;
+    ch.metadata = metadata;
+
+    Ok(())
+}
+
+/// Hash a chapter's raw source along with the contents of every file it
+/// includes.
+///
+/// The hashes are saved between builds, so this uses 64-bit FNV-1a rather
+/// than `DefaultHasher`, whose algorithm can change between Rust releases.
+fn content_hash(raw: &str, src_dir: &Path, dependencies: &[PathBuf]) -> u64 {
+    let mut hash = fnv1a(FNV_OFFSET_BASIS, raw.as_bytes());
+
+    for dependency in dependencies {
+        if let Ok(content) = utils::fs::file_to_string(src_dir.join(dependency)) {
+            // separate the files so moving text between them changes the hash
+            hash = fnv1a(hash, &[0xff]);
+            hash = fnv1a(hash, content.as_bytes());
+        }
+    }
+
+    hash
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Continue an FNV-1a hash over `bytes`.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    hash
+}
+
+fn load_nested_items(link: &Link, src_dir: &Path, lazy: bool) -> Result<Vec<BookItem>> {
+    link.nested_items
+        .iter()
+        .map(|i| load_summary_item(i, src_dir, lazy))
+        .collect()
 }
 
 /// A depth-first iterator over the items in a book.
@@ -214,6 +1257,31 @@ impl<'a> Iterator for BookItems<'a> {
     }
 }
 
+/// Iterator over every chapter in a book, yielding `(number, depth, chapter)`.
+///
+/// This struct shouldn't be created directly, instead prefer the
+/// [`Book::chapters()`] method.
+///
+/// [`Book::chapters()`]: struct.Book.html#method.chapters
+pub struct Chapters<'a> {
+    items: BookItems<'a>,
+}
+
+impl<'a> Iterator for Chapters<'a> {
+    type Item = (SectionNumber, usize, &'a Chapter);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for item in &mut self.items {
+            if let BookItem::Chapter(ref ch) = *item {
+                let number = ch.number.clone().unwrap_or_default();
+                return Some((number, ch.depth, ch));
+            }
+        }
+
+        None
+    }
+}
+
 impl Display for Chapter {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         if let Some(ref section_number) = self.number {
@@ -231,6 +1299,16 @@ mod tests {
     use tempdir::TempDir;
     use std::io::Write;
 
+    /// Get every chapter in `book`, in reading order.
+    fn chapters(book: &Book) -> Vec<&Chapter> {
+        book.iter()
+            .filter_map(|item| match *item {
+                BookItem::Chapter(ref ch) => Some(ch),
+                _ => None,
+            })
+            .collect()
+    }
+
     const DUMMY_SRC: &'static str = "
 # Dummy Chapter
 
@@ -282,16 +1360,143 @@ And here is some \
         let (link, temp_dir) = dummy_link();
         let should_be = Chapter::new("Chapter 1", DUMMY_SRC.to_string(), "chapter_1.md");
 
-        let got = load_chapter(&link, temp_dir.path()).unwrap();
+        let got = load_chapter(&link, temp_dir.path(), false).unwrap();
         assert_eq!(got, should_be);
     }
 
+    #[test]
+    fn front_matter_is_removed_from_chapters() {
+        let temp = TempDir::new("book").unwrap();
+        let chapter_path = temp.path().join("chapter.md");
+        File::create(&chapter_path)
+            .unwrap()
+            .write_all(b"+++\nauthor = \"Jane Doe\"\ndraft = true\n+++\n# Chapter\n")
+            .unwrap();
+        let link = Link::new("Chapter", &chapter_path);
+
+        let got = load_chapter(&link, temp.path(), false).unwrap();
+
+        assert_eq!(got.content, "# Chapter\n");
+        let should_be = ChapterMetadata {
+            author: Some(String::from("Jane Doe")),
+            draft: true,
+            ..Default::default()
+        };
+        assert_eq!(got.metadata, should_be);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_front_matter_is_removed_from_chapters() {
+        let temp = TempDir::new("book").unwrap();
+        let chapter_path = temp.path().join("chapter.md");
+        File::create(&chapter_path)
+            .unwrap()
+            .write_all(b"---\nauthor: Jane Doe\nkeywords: [intro]\n---\n# Chapter\n")
+            .unwrap();
+        let link = Link::new("Chapter", &chapter_path);
+
+        let got = load_chapter(&link, temp.path(), false).unwrap();
+
+        assert_eq!(got.content, "# Chapter\n");
+        let should_be = ChapterMetadata {
+            author: Some(String::from("Jane Doe")),
+            keywords: vec![String::from("intro")],
+            ..Default::default()
+        };
+        assert_eq!(got.metadata, should_be);
+    }
+
+    #[test]
+    fn a_leading_horizontal_rule_isnt_front_matter() {
+        let temp = TempDir::new("book").unwrap();
+        let chapter_path = temp.path().join("chapter.md");
+        File::create(&chapter_path)
+            .unwrap()
+            .write_all(b"---\n# Chapter\n")
+            .unwrap();
+        let link = Link::new("Chapter", &chapter_path);
+
+        let got = load_chapter(&link, temp.path(), false).unwrap();
+
+        assert_eq!(got.content, "---\n# Chapter\n");
+        assert_eq!(got.metadata, ChapterMetadata::default());
+    }
+
+    #[test]
+    fn horizontal_rules_and_heading_underlines_arent_front_matter() {
+        let temp = TempDir::new("book").unwrap();
+        let chapter_path = temp.path().join("chapter.md");
+        let src = "---\n\nSome text after a rule.\n\nA Heading\n---\n\nMore text.\n";
+        File::create(&chapter_path)
+            .unwrap()
+            .write_all(src.as_bytes())
+            .unwrap();
+        let link = Link::new("Chapter", &chapter_path);
+
+        let got = load_chapter(&link, temp.path(), false).unwrap();
+
+        assert_eq!(got.content, src);
+        assert_eq!(got.metadata, ChapterMetadata::default());
+    }
+
+    #[test]
+    fn lazy_chapters_are_read_on_first_access() {
+        let (link, temp_dir) = dummy_link();
+
+        let mut got = load_chapter(&link, temp_dir.path(), true).unwrap();
+        assert!(got.content.is_empty());
+
+        assert_eq!(got.content().unwrap(), DUMMY_SRC);
+        assert_eq!(got.source, None);
+    }
+
+    #[test]
+    fn a_lazy_book_can_be_read_all_at_once() {
+        let temp = TempDir::new("book").unwrap();
+        File::create(temp.path().join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [First](first.md)\n    - [Nested](nested.md)\n")
+            .unwrap();
+        for name in &["first.md", "nested.md"] {
+            File::create(temp.path().join(name))
+                .unwrap()
+                .write_all(b"# Heading\n")
+                .unwrap();
+        }
+
+        let mut book = load_book_lazily(temp.path(), &BuildConfig::default()).unwrap();
+        book.load_content().unwrap();
+
+        let contents: Vec<(&str, bool)> = chapters(&book)
+            .into_iter()
+            .map(|ch| (ch.content.as_str(), ch.source.is_none()))
+            .collect();
+        assert_eq!(contents, vec![("# Heading\n", true), ("# Heading\n", true)]);
+    }
+
     #[test]
     fn cant_load_a_nonexistent_chapter() {
         let link = Link::new("Chapter 1", "/foo/bar/baz.md");
 
-        let got = load_chapter(&link, "");
-        assert!(got.is_err());
+        let got = load_chapter(&link, "", false);
+        match *got.unwrap_err().kind() {
+            ErrorKind::MissingChapter(ref name, ref location, _, _) => {
+                assert_eq!(name, "Chapter 1");
+                assert_eq!(location, Path::new("/foo/bar/baz.md"));
+            }
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+    }
+
+    #[test]
+    fn draft_chapters_dont_need_a_file() {
+        let link = Link::new("Coming soon", "");
+        let should_be = Chapter::new("Coming soon", String::new(), "");
+
+        let got = load_chapter(&link, "/foo/bar", false).unwrap();
+        assert_eq!(got, should_be);
+        assert!(got.is_draft());
     }
 
     #[test]
@@ -301,13 +1506,17 @@ And here is some \
         let nested = Chapter {
             name: String::from("Nested Chapter 1"),
             content: String::from("Hello World!"),
+            word_count: 2,
+            content_hash: content_hash("Hello World!", Path::new(""), &[]),
             number: Some(SectionNumber(vec![1, 2])),
             path: PathBuf::from("second.md"),
-            sub_items: Vec::new(),
+            ..Default::default()
         };
         let should_be = BookItem::Chapter(Chapter {
             name: String::from("Chapter 1"),
             content: String::from(DUMMY_SRC),
+            word_count: count_words(DUMMY_SRC),
+            content_hash: content_hash(DUMMY_SRC, Path::new(""), &[]),
             number: None,
             path: PathBuf::from("chapter_1.md"),
             sub_items: vec![
@@ -315,9 +1524,56 @@ And here is some \
                 BookItem::Separator,
                 BookItem::Chapter(nested.clone()),
             ],
+            ..Default::default()
         });
 
-        let got = load_summary_item(&SummaryItem::Link(root), temp.path()).unwrap();
+        let got = load_summary_item(&SummaryItem::Link(root), temp.path(), false).unwrap();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn chapters_know_their_parents_names() {
+        let (root, temp) = nested_links();
+        let summary = Summary {
+            numbered_chapters: vec![SummaryItem::Link(root)],
+            ..Default::default()
+        };
+
+        let book = load_book_from_disk(&summary, temp.path(), false).unwrap();
+
+        let got: Vec<_> = chapters(&book)
+            .into_iter()
+            .map(|ch| ch.parent_names.clone())
+            .collect();
+        let parent = vec![String::from("Chapter 1")];
+        assert_eq!(got, vec![Vec::new(), parent.clone(), parent]);
+    }
+
+    #[test]
+    fn chapters_know_their_neighbours() {
+        let (root, temp) = nested_links();
+        let summary = Summary {
+            numbered_chapters: vec![
+                SummaryItem::Link(root),
+                SummaryItem::Link(Link::new("Draft", "")),
+            ],
+            ..Default::default()
+        };
+
+        let book = load_book_from_disk(&summary, temp.path(), false).unwrap();
+
+        let got: Vec<_> = chapters(&book)
+            .into_iter()
+            .map(|ch| (ch.depth, ch.previous.clone(), ch.next.clone()))
+            .collect();
+        let first = Some(PathBuf::from("chapter_1.md"));
+        let second = Some(PathBuf::from("second.md"));
+        let should_be = vec![
+            (0, None, second.clone()),
+            (1, first, second.clone()),
+            (1, second, None),
+            (0, None, None),
+        ];
         assert_eq!(got, should_be);
     }
 
@@ -333,17 +1589,541 @@ And here is some \
                 BookItem::Chapter(Chapter {
                     name: String::from("Chapter 1"),
                     content: String::from(DUMMY_SRC),
+                    word_count: count_words(DUMMY_SRC),
+                    content_hash: content_hash(DUMMY_SRC, Path::new(""), &[]),
                     path: PathBuf::from("chapter_1.md"),
                     ..Default::default()
                 }),
             ],
         };
 
-        let got = load_book_from_disk(&summary, temp.path()).unwrap();
+        let got = load_book_from_disk(&summary, temp.path(), false).unwrap();
 
         assert_eq!(got, should_be);
     }
 
+    #[test]
+    fn find_the_first_heading_in_a_chapter() {
+        assert_eq!(first_heading(DUMMY_SRC), Some(String::from("Dummy Chapter")));
+        assert_eq!(first_heading("## Subheading\n\n# The `Real` Title"),
+                   Some(String::from("The Real Title")));
+        assert_eq!(first_heading("No headings here"), None);
+    }
+
+    #[test]
+    fn count_the_words_in_a_chapter() {
+        let content = "# A Title\n\nSome *emphasised* text.\n\n```rust\nfn main() {}\n```\n";
+        let mut chapter = Chapter::new("Chapter", content.to_string(), "chapter.md");
+
+        assert_eq!(chapter.word_count, 5);
+        assert_eq!(chapter.reading_time(), 1);
+
+        chapter.word_count = 401;
+        assert_eq!(chapter.reading_time(), 3);
+    }
+
+    #[test]
+    fn find_the_chapters_including_a_file() {
+        let temp = TempDir::new("book").unwrap();
+        fs::create_dir(temp.path().join("nested")).unwrap();
+        File::create(temp.path().join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [First](nested/first.md)\n- [Second](second.md)")
+            .unwrap();
+        File::create(temp.path().join("nested/first.md"))
+            .unwrap()
+            .write_all(b"{{#include ../listings/main.rs}}")
+            .unwrap();
+        File::create(temp.path().join("second.md")).unwrap();
+
+        let book = load_book(temp.path(), &BuildConfig::default()).unwrap();
+
+        let got: Vec<_> = book.chapters_depending_on("listings/main.rs")
+            .into_iter()
+            .map(|ch| ch.name.as_str())
+            .collect();
+        assert_eq!(got, vec!["First"]);
+    }
+
+    #[test]
+    fn detect_chapters_which_changed() {
+        let (link, temp) = dummy_link();
+        let summary = Summary {
+            numbered_chapters: vec![SummaryItem::Link(link)],
+            ..Default::default()
+        };
+        let book = load_book_from_disk(&summary, temp.path(), false).unwrap();
+        let hashes = book.content_hashes();
+        assert!(book.changed_since(&hashes).is_empty());
+        assert_eq!(book.changed_since(&ContentHashes::new()).len(), 1);
+
+        File::create(temp.path().join("chapter_1.md"))
+            .unwrap()
+            .write_all(b"# Rewritten")
+            .unwrap();
+        let book = load_book_from_disk(&summary, temp.path(), false).unwrap();
+
+        let got: Vec<_> = book.changed_since(&hashes).iter().map(|ch| ch.path.clone()).collect();
+        assert_eq!(got, vec![PathBuf::from("chapter_1.md")]);
+    }
+
+    #[test]
+    fn content_hashes_dont_depend_on_the_compiler() {
+        // these are the published FNV-1a test vectors, so a hash saved by an
+        // older mdbook still matches
+        assert_eq!(content_hash("", Path::new(""), &[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(content_hash("a", Path::new(""), &[]), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn find_the_assets_a_chapter_uses() {
+        let content = "![Logo](../images/logo.png) [Download](files/book.pdf?raw#top)\n\n\
+                       [Next](next.md) [Rust](https://www.rust-lang.org/)\n\n\
+                       ![Again](../images/logo.png) [Listings](listings/) [Index](genindex)\n\n\
+                       ![Spaced](my%20diagram.svg)";
+
+        let got = find_assets(Path::new("nested/chapter.md"), content);
+
+        let should_be = vec![
+            PathBuf::from("images/logo.png"),
+            PathBuf::from("nested/files/book.pdf"),
+            PathBuf::from("nested/my diagram.svg"),
+        ];
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn missing_assets_are_an_error_when_denying_warnings() {
+        let mut chapter = Chapter::new("Chapter", String::new(), "chapter.md");
+        chapter.assets = vec![PathBuf::from("missing.png")];
+        let book = Book {
+            sections: vec![BookItem::Chapter(chapter)],
+        };
+        let temp = TempDir::new("book").unwrap();
+
+        assert!(check_assets(temp.path(), &book, false).is_ok());
+        assert!(check_assets(temp.path(), &book, true).is_err());
+    }
+
+    #[test]
+    fn unnamed_chapters_use_their_first_heading() {
+        let (mut link, temp) = dummy_link();
+        link.name = String::new();
+        let mut summary = Summary {
+            numbered_chapters: vec![SummaryItem::Link(link)],
+            ..Default::default()
+        };
+
+        use_heading_titles(temp.path(), &mut summary);
+
+        let (link, _) = summary.iter().next().unwrap();
+        assert_eq!(link.name, "Dummy Chapter");
+    }
+
+    fn book_with_an_orphan() -> TempDir {
+        let temp = TempDir::new("book").unwrap();
+        File::create(temp.path().join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [First](first.md)")
+            .unwrap();
+        File::create(temp.path().join("first.md")).unwrap();
+        File::create(temp.path().join("orphan.md"))
+            .unwrap()
+            .write_all(b"# Forgotten Chapter")
+            .unwrap();
+        temp
+    }
+
+    fn read_to_string(path: &Path) -> String {
+        let mut content = String::new();
+        File::open(path).unwrap().read_to_string(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn orphaned_files_are_added_as_drafts_without_touching_the_summary() {
+        let temp = book_with_an_orphan();
+        let mut summary = Summary::from_path(temp.path().join("SUMMARY.md")).unwrap();
+
+        check_orphans(temp.path(), &mut summary, OrphanPolicy::Append, &[]).unwrap();
+
+        assert_eq!(
+            summary.suffix_chapters,
+            vec![SummaryItem::Link(Link::new("Forgotten Chapter", ""))]
+        );
+        assert_eq!(read_to_string(&temp.path().join("SUMMARY.md")), "- [First](first.md)");
+    }
+
+    #[test]
+    fn orphaned_files_can_be_appended_to_the_summary_as_drafts() {
+        let temp = book_with_an_orphan();
+        let summary_path = temp.path().join("SUMMARY.md");
+
+        let added = append_orphans(temp.path(), &[]).unwrap();
+
+        assert_eq!(added, vec![PathBuf::from("orphan.md")]);
+        let should_be = "- [First](first.md)\n\n[Forgotten Chapter]() <!-- orphan.md -->\n";
+        assert_eq!(read_to_string(&summary_path), should_be);
+
+        let reparsed = Summary::from_path(&summary_path).unwrap();
+        assert_eq!(
+            reparsed.suffix_chapters,
+            vec![SummaryItem::Link(Link::new("Forgotten Chapter", ""))]
+        );
+
+        // the orphan already has a draft, so it isn't added a second time
+        assert!(append_orphans(temp.path(), &[]).unwrap().is_empty());
+        assert_eq!(read_to_string(&summary_path), should_be);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_chapters_can_be_forbidden() {
+        use std::os::unix::fs::symlink;
+
+        let temp = TempDir::new("book").unwrap();
+        File::create(temp.path().join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [Linked](linked/chapter.md)")
+            .unwrap();
+        let elsewhere = TempDir::new("elsewhere").unwrap();
+        File::create(elsewhere.path().join("chapter.md")).unwrap();
+        symlink(elsewhere.path(), temp.path().join("linked")).unwrap();
+
+        assert!(load_book(temp.path(), &BuildConfig::default()).is_ok());
+
+        let cfg = BuildConfig {
+            allow_symlinks: false,
+            ..Default::default()
+        };
+        assert!(load_book(temp.path(), &cfg).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_includes_can_be_forbidden() {
+        use std::os::unix::fs::symlink;
+
+        let temp = TempDir::new("book").unwrap();
+        let elsewhere = TempDir::new("elsewhere").unwrap();
+        File::create(elsewhere.path().join("secret.rs")).unwrap();
+        File::create(elsewhere.path().join("more.md"))
+            .unwrap()
+            .write_all(b"- [More](more.md)\n")
+            .unwrap();
+        symlink(elsewhere.path(), temp.path().join("linked")).unwrap();
+        File::create(temp.path().join("chapter.md"))
+            .unwrap()
+            .write_all(b"{{#include linked/secret.rs}}\n")
+            .unwrap();
+        File::create(temp.path().join("more.md")).unwrap();
+        let cfg = BuildConfig {
+            allow_symlinks: false,
+            ..Default::default()
+        };
+
+        let summary_path = temp.path().join("SUMMARY.md");
+        File::create(&summary_path)
+            .unwrap()
+            .write_all(b"- [Chapter](chapter.md)\n")
+            .unwrap();
+        let err = load_book(temp.path(), &cfg).unwrap_err();
+        assert!(err.to_string().contains("included by \"Chapter\""));
+
+        File::create(&summary_path)
+            .unwrap()
+            .write_all(b"{{#include linked/more.md}}\n")
+            .unwrap();
+        let err = load_book(temp.path(), &cfg).unwrap_err();
+        assert!(err.to_string().starts_with("SUMMARY.md"));
+    }
+
+    #[test]
+    fn orphaned_files_can_be_an_error() {
+        let (link, temp) = dummy_link();
+        File::create(temp.path().join("orphan.md")).unwrap();
+        let mut summary = Summary {
+            numbered_chapters: vec![SummaryItem::Link(link)],
+            ..Default::default()
+        };
+
+        assert!(check_orphans(temp.path(), &mut summary, OrphanPolicy::Error, &[]).is_err());
+    }
+
+    #[test]
+    fn sub_books_are_added_under_a_part_title() {
+        let temp = TempDir::new("book").unwrap();
+        let guide = temp.path().join("guide");
+        fs::create_dir(&guide).unwrap();
+        File::create(temp.path().join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [Intro](intro.md)")
+            .unwrap();
+        File::create(temp.path().join("intro.md")).unwrap();
+        File::create(guide.join("book.toml"))
+            .unwrap()
+            .write_all(b"[book]\ntitle = \"User Guide\"")
+            .unwrap();
+        File::create(guide.join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [Setup](setup.md)")
+            .unwrap();
+        File::create(guide.join("setup.md")).unwrap();
+        let cfg = BuildConfig {
+            orphans: OrphanPolicy::Error,
+            ..Default::default()
+        };
+
+        let mut book = load_book(temp.path(), &cfg).unwrap();
+        add_sub_book(&mut book, temp.path(), "guide").unwrap();
+
+        assert_eq!(book.sections.len(), 3);
+        assert_eq!(book.sections[1], BookItem::PartTitle(String::from("User Guide")));
+        match book.sections[2] {
+            BookItem::Chapter(ref ch) => {
+                assert_eq!(ch.path, PathBuf::from("guide/setup.md"));
+                assert_eq!(ch.previous, Some(PathBuf::from("intro.md")));
+            }
+            ref other => panic!("Expected a chapter, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resolved_sub_book_paths_keep_chapter_paths_relative() {
+        let temp = TempDir::new("book").unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let guide = root.join("guide");
+        fs::create_dir(&guide).unwrap();
+        File::create(root.join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [Intro](intro.md)")
+            .unwrap();
+        File::create(root.join("intro.md")).unwrap();
+        File::create(guide.join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [Setup](setup.md)")
+            .unwrap();
+        File::create(guide.join("setup.md")).unwrap();
+
+        let book = load_book_with_sub_books(&root, &BuildConfig::default(), &[guide], false)
+            .unwrap();
+
+        assert_eq!(book.sections[1], BookItem::PartTitle(String::from("guide")));
+        match book.sections[2] {
+            BookItem::Chapter(ref ch) => assert_eq!(ch.path, PathBuf::from("guide/setup.md")),
+            ref other => panic!("Expected a chapter, found {:?}", other),
+        }
+
+        let outside = vec![TempDir::new("elsewhere").unwrap().path().to_path_buf()];
+        assert!(load_book_with_sub_books(&root, &BuildConfig::default(), &outside, false).is_err());
+    }
+
+    #[test]
+    fn iterate_over_chapters_with_numbers_and_depths() {
+        let mut nested = Chapter::new("Nested", String::new(), "nested.md");
+        nested.number = Some(SectionNumber(vec![1, 1]));
+        nested.depth = 1;
+        let mut chapter = Chapter::new("Chapter", String::new(), "chapter.md");
+        chapter.number = Some(SectionNumber(vec![1]));
+        chapter.sub_items = vec![BookItem::Chapter(nested)];
+        let mut book = Book {
+            sections: vec![
+                BookItem::Chapter(Chapter::new("Intro", String::new(), "intro.md")),
+                BookItem::Separator,
+                BookItem::Chapter(chapter),
+            ],
+        };
+
+        book.for_each_mut(|ch| ch.content = ch.name.to_uppercase());
+
+        let got: Vec<_> = book.chapters()
+            .map(|(number, depth, ch)| (number.0, depth, ch.content.as_str()))
+            .collect();
+        let should_be = vec![
+            (vec![], 0, "INTRO"),
+            (vec![1], 0, "CHAPTER"),
+            (vec![1, 1], 1, "NESTED"),
+        ];
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn draft_chapters_can_be_generated() {
+        let mut changelog = Chapter::new("Changelog", String::new(), "");
+        changelog
+            .attributes
+            .insert(String::from("generator"), String::from("changelog"));
+        let mut book = Book {
+            sections: vec![
+                BookItem::Chapter(Chapter::new("Intro", String::new(), "intro.md")),
+                BookItem::Chapter(changelog),
+            ],
+        };
+        let generator = |_: &Chapter, book: &Book| -> Result<String> {
+            Ok(format!("{} chapters", book.sections.len()))
+        };
+
+        book.generate("changelog", &generator).unwrap();
+
+        match book.sections[1] {
+            BookItem::Chapter(ref ch) => {
+                assert_eq!(ch.content, "2 chapters");
+                assert_eq!(ch.path, PathBuf::from("changelog.md"));
+                assert_eq!(ch.previous, Some(PathBuf::from("intro.md")));
+            }
+            ref other => panic!("Expected a chapter, found {:?}", other),
+        }
+    }
+
+    fn generated_draft(name: &str, generator: &str) -> BookItem {
+        let mut ch = Chapter::new(name, String::new(), "");
+        ch.attributes
+            .insert(String::from("generator"), String::from(generator));
+        BookItem::Chapter(ch)
+    }
+
+    #[test]
+    fn several_chapters_can_use_the_same_generator() {
+        let mut book = Book {
+            sections: vec![
+                generated_draft("Index", "index"),
+                BookItem::Chapter(Chapter::new("Intro", String::new(), "intro.md")),
+                generated_draft("Another Index", "index"),
+            ],
+        };
+        let generator = |ch: &Chapter, _: &Book| -> Result<String> { Ok(ch.name.clone()) };
+
+        book.generate("index", &generator).unwrap();
+        book.check_output_paths().unwrap();
+
+        let got: Vec<_> = chapters(&book)
+            .into_iter()
+            .map(|ch| (ch.content.as_str(), ch.path.clone()))
+            .collect();
+        let should_be = vec![
+            ("Index", PathBuf::from("index-1.md")),
+            ("", PathBuf::from("intro.md")),
+            ("Another Index", PathBuf::from("index-2.md")),
+        ];
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn generated_chapters_cant_replace_existing_ones() {
+        let mut book = Book {
+            sections: vec![
+                BookItem::Chapter(Chapter::new("Old Changelog", String::new(), "Changelog.md")),
+                generated_draft("Changelog", "changelog"),
+            ],
+        };
+        let generator = |_: &Chapter, _: &Book| -> Result<String> { Ok(String::new()) };
+
+        let err = book.generate("changelog", &generator).unwrap_err();
+
+        assert!(err.to_string().contains("Old Changelog"));
+    }
+
+    #[test]
+    fn output_paths_must_be_usable_everywhere() {
+        let bad_paths = vec!["../outside.md", "/etc/passwd.md", "what?.md", "aux.md", "dir./a.md"];
+        for path in bad_paths {
+            let book = Book {
+                sections: vec![BookItem::Chapter(Chapter::new("Bad", String::new(), path))],
+            };
+            assert!(book.check_output_paths().is_err(), "{} should be rejected", path);
+        }
+
+        let mut book = Book {
+            sections: vec![
+                BookItem::Chapter(Chapter::new("Intro", String::new(), "intro.md")),
+                BookItem::Chapter(Chapter::new("Escapes", String::new(), "nested/../intro.md")),
+                BookItem::Chapter(Chapter::new("Again", String::new(), "intro.md")),
+            ],
+        };
+        assert!(book.check_output_paths().is_err());
+
+        book.sections.remove(1);
+        assert!(book.check_output_paths().is_ok());
+
+        book.sections
+            .push(BookItem::Chapter(Chapter::new("Shouty", String::new(), "INTRO.md")));
+        assert!(book.check_output_paths().is_err());
+    }
+
+    #[test]
+    fn files_rendered_to_the_same_page_are_an_error() {
+        let mut book = Book {
+            sections: vec![
+                BookItem::Chapter(Chapter::new("Markdown", String::new(), "a.md")),
+                BookItem::Chapter(Chapter::new("Also Markdown", String::new(), "a.markdown")),
+            ],
+        };
+        assert!(book.check_output_paths().is_err());
+
+        // chapters pointing at anchors in the same file share its page
+        let mut anchor = Chapter::new("Section", String::new(), "a.md");
+        anchor.fragment = Some(String::from("section"));
+        book.sections[1] = BookItem::Chapter(anchor);
+        assert!(book.check_output_paths().is_ok());
+    }
+
+    #[test]
+    fn merge_another_book_under_a_part_title() {
+        let mut guide = Chapter::new("Setup", String::new(), "setup.md");
+        guide.dependencies = vec![PathBuf::from("listings/main.rs")];
+        guide.assets = vec![PathBuf::from("images/setup.png")];
+        let other = Book {
+            sections: vec![
+                BookItem::Chapter(guide),
+                BookItem::Chapter(Chapter::new("Draft", String::new(), "")),
+            ],
+        };
+        let mut book = Book {
+            sections: vec![BookItem::Chapter(Chapter::new("Intro", String::new(), "intro.md"))],
+        };
+
+        book.merge(other, "guide", "User Guide");
+
+        assert_eq!(book.sections[1], BookItem::PartTitle(String::from("User Guide")));
+        match book.sections[2] {
+            BookItem::Chapter(ref ch) => {
+                assert_eq!(ch.path, PathBuf::from("guide/setup.md"));
+                assert_eq!(ch.dependencies, vec![PathBuf::from("guide/listings/main.rs")]);
+                assert_eq!(ch.assets, vec![PathBuf::from("guide/images/setup.png")]);
+                assert_eq!(ch.previous, Some(PathBuf::from("intro.md")));
+            }
+            ref other => panic!("Expected a chapter, found {:?}", other),
+        }
+        match book.sections[3] {
+            BookItem::Chapter(ref ch) => assert!(ch.is_draft()),
+            ref other => panic!("Expected a chapter, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unpublished_chapters_can_be_removed() {
+        let mut hidden = Chapter::new("Hidden", String::new(), "hidden.md");
+        hidden
+            .attributes
+            .insert(String::from("unpublished"), String::from("true"));
+        let mut wip = Chapter::new("WIP", String::new(), "wip.md");
+        wip.metadata.unpublished = true;
+        let mut intro = Chapter::new("Intro", String::new(), "intro.md");
+        intro.sub_items = vec![BookItem::Chapter(wip)];
+        let mut book = Book {
+            sections: vec![
+                BookItem::Chapter(intro),
+                BookItem::Chapter(hidden),
+                BookItem::Chapter(Chapter::new("Outro", String::new(), "outro.md")),
+            ],
+        };
+
+        book.remove_unpublished();
+
+        let got: Vec<_> = book.chapters().map(|(_, _, ch)| ch.name.as_str()).collect();
+        assert_eq!(got, vec!["Intro", "Outro"]);
+    }
+
     #[test]
     fn book_iter_iterates_over_sequential_items() {
         let book = Book {
@@ -370,9 +2150,23 @@ And here is some \
             sections: vec![
                 BookItem::Chapter(Chapter {
                     name: String::from("Chapter 1"),
+                    markdown_name: None,
                     content: String::from(DUMMY_SRC),
                     number: None,
                     path: PathBuf::from("Chapter_1/index.md"),
+                    title: None,
+                    fragment: None,
+                    attributes: HashMap::new(),
+                    parent_names: Vec::new(),
+                    depth: 0,
+                    previous: None,
+                    next: None,
+                    metadata: ChapterMetadata::default(),
+                    source: None,
+                    word_count: 0,
+                    dependencies: Vec::new(),
+                    assets: Vec::new(),
+                    content_hash: 0,
                     sub_items: vec![
                         BookItem::Chapter(Chapter::new(
                             "Hello World",
@@ -1,10 +1,9 @@
 use std::fs::{self, File};
 use std::path::PathBuf;
 use std::io::Write;
-use toml;
 
 use config::Config;
-use super::MDBook;
+use super::{MDBook, Summary};
 use theme;
 use errors::*;
 
@@ -99,11 +98,11 @@ impl BookBuilder {
     fn write_book_toml(&self) -> Result<()> {
         debug!("[*] Writing book.toml");
         let book_toml = self.root.join("book.toml");
-        let cfg = toml::to_vec(&self.config).chain_err(|| "Unable to serialize the config")?;
+        let cfg = self.config.to_commented_toml()?;
 
         File::create(book_toml)
             .chain_err(|| "Couldn't create book.toml")?
-            .write_all(&cfg)
+            .write_all(cfg.as_bytes())
             .chain_err(|| "Unable to write config to book.toml")?;
         Ok(())
     }
@@ -149,6 +148,7 @@ impl BookBuilder {
         let mut f = File::create(self.root.join(".gitignore"))?;
 
         writeln!(f, "{}", self.config.build.build_dir.display())?;
+        writeln!(f, ".mdbook")?;
 
         Ok(())
     }
@@ -158,6 +158,23 @@ impl BookBuilder {
         let src_dir = self.root.join(&self.config.book.src);
 
         let summary = src_dir.join("SUMMARY.md");
+        if summary.exists() {
+            // loading the book will create any chapters it's missing
+            debug!("[*] Keeping the existing SUMMARY.md");
+            return Ok(());
+        }
+
+        let sub_books = &self.config.book.sub_books;
+        let mut generated = Summary::generate_from_dir_excluding(&src_dir, sub_books)
+            .chain_err(|| "Unable to generate a SUMMARY.md from the existing files")?;
+        if !generated.numbered_chapters.is_empty() {
+            debug!("[*] Writing a SUMMARY.md for the existing chapters");
+            generated.title = Some(String::from("Summary"));
+            let mut f = File::create(&summary).chain_err(|| "Unable to create SUMMARY.md")?;
+            write!(f, "{}", generated)?;
+            return Ok(());
+        }
+
         let mut f = File::create(&summary).chain_err(|| "Unable to create SUMMARY.md")?;
         writeln!(f, "# Summary")?;
         writeln!(f, "")?;
@@ -0,0 +1,554 @@
+//! Lints for catching sloppy (but otherwise valid) `SUMMARY.md` files.
+
+use std::path::Path;
+
+use super::summary::{literal_lines, ConcreteSummary, Diagnostic, Level, LineContent, Link, Summary,
+                     SummaryItem};
+use config::{Indentation, LintConfig, LintLevel};
+use errors::*;
+
+
+/// Run every lint which isn't set to `LintLevel::Allow` over a parsed
+/// `Summary` and the `src` it was parsed from, returning a `Diagnostic` for
+/// each problem found.
+///
+/// Denied lints are reported with `Level::Error`, everything else is a
+/// `Level::Warning`.
+pub fn lint_summary(
+    summary: &Summary,
+    src: &str,
+    filename: &str,
+    config: &LintConfig,
+) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        src: src,
+        filename: filename,
+        diagnostics: Vec::new(),
+    };
+
+    let links: Vec<&Link> = summary.iter().map(|(link, _)| link).collect();
+
+    if config.empty_title != LintLevel::Allow {
+        linter.empty_titles(&links, config.empty_title);
+    }
+    if config.trailing_whitespace != LintLevel::Allow {
+        linter.trailing_whitespace(config.trailing_whitespace);
+    }
+    if config.non_markdown_destination != LintLevel::Allow {
+        linter.non_markdown_destinations(&links, config.non_markdown_destination);
+    }
+    if config.inconsistent_list_markers != LintLevel::Allow {
+        linter.inconsistent_list_markers(&links, config.inconsistent_list_markers);
+    }
+    if config.inconsistent_title_case != LintLevel::Allow {
+        linter.inconsistent_title_case(&links, config.inconsistent_title_case);
+    }
+    if config.duplicate_title != LintLevel::Allow {
+        linter.duplicate_titles(&summary.prefix_chapters, config.duplicate_title);
+        linter.duplicate_titles(&summary.numbered_chapters, config.duplicate_title);
+        linter.duplicate_titles(&summary.suffix_chapters, config.duplicate_title);
+    }
+
+    linter.diagnostics
+}
+
+struct Linter<'a> {
+    src: &'a str,
+    filename: &'a str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    fn empty_titles(&mut self, links: &[&Link], level: LintLevel) {
+        for link in links {
+            if link.name.trim().is_empty() {
+                let msg = format!("The chapter for {} has an empty name", link.location.display());
+                self.report_link(link, "empty-title", msg, level);
+            }
+        }
+    }
+
+    fn trailing_whitespace(&mut self, level: LintLevel) {
+        let src = self.src;
+        let literal = literal_lines(src);
+
+        for (i, line) in src.lines().enumerate() {
+            if literal[i] {
+                continue;
+            }
+
+            let line = line.trim_right_matches('\r');
+            let trimmed = line.trim_right();
+
+            if !trimmed.is_empty() && trimmed.len() < line.len() {
+                let position = Some((i + 1, trimmed.chars().count() + 1));
+                self.report(position, "trailing-whitespace", "Trailing whitespace", level);
+            }
+        }
+    }
+
+    fn non_markdown_destinations(&mut self, links: &[&Link], level: LintLevel) {
+        for link in links {
+            if link.is_draft() {
+                continue;
+            }
+
+            if link.location.extension().map(|ext| ext != "md").unwrap_or(true) {
+                let msg = format!("\"{}\" doesn't point to a markdown file", link.name);
+                self.report_link(link, "non-markdown-destination", msg, level);
+            }
+        }
+    }
+
+    fn inconsistent_list_markers(&mut self, links: &[&Link], level: LintLevel) {
+        let mut expected = None;
+
+        for link in links {
+            let marker = match self.list_marker(link) {
+                Some(marker) => marker,
+                None => continue,
+            };
+
+            match expected {
+                None => expected = Some(marker),
+                Some(expected) if expected != marker => {
+                    let msg = format!(
+                        "\"{}\" uses `{}` for its list item, but earlier items use `{}`",
+                        link.name,
+                        marker,
+                        expected
+                    );
+                    self.report_link(link, "inconsistent-list-markers", msg, level);
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn inconsistent_title_case(&mut self, links: &[&Link], level: LintLevel) {
+        let mut expected = None;
+
+        for link in links {
+            let case = match TitleCase::of(&link.name) {
+                Some(case) => case,
+                None => continue,
+            };
+
+            match expected {
+                None => expected = Some(case),
+                Some(expected) if expected != case => {
+                    let msg = format!(
+                        "\"{}\" is in {}, but earlier chapters are in {}",
+                        link.name,
+                        case.description(),
+                        expected.description()
+                    );
+                    self.report_link(link, "inconsistent-title-case", msg, level);
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Check a group of sibling items (and, recursively, their children) for
+    /// chapters with the same name.
+    fn duplicate_titles(&mut self, items: &[SummaryItem], level: LintLevel) {
+        let mut seen: Vec<&Link> = Vec::new();
+
+        for item in items {
+            let link = match *item {
+                SummaryItem::Link(ref link) => link,
+                _ => continue,
+            };
+
+            if !link.name.trim().is_empty() {
+                let previous = seen.iter().find(|other| other.name == link.name).cloned();
+
+                match previous {
+                    Some(previous) => {
+                        let msg = match previous.span {
+                            Some(span) => format!(
+                                "There is already a chapter called \"{}\" on line {}",
+                                link.name,
+                                span.line_col(self.src).0
+                            ),
+                            None => format!("There is already a chapter called \"{}\"", link.name),
+                        };
+                        self.report_link(link, "duplicate-title", msg, level);
+
+                        if let Some(diagnostic) = self.diagnostics.last_mut() {
+                            diagnostic.related_span = previous.span;
+                        }
+                    }
+                    None => seen.push(link),
+                }
+            }
+
+            self.duplicate_titles(&link.nested_items, level);
+        }
+    }
+
+    /// Find the `-`, `*` or `+` starting the list item a link is in, if it's
+    /// in an unordered list.
+    fn list_marker(&self, link: &Link) -> Option<char> {
+        let span = match link.span {
+            Some(span) => span,
+            None => return None,
+        };
+        let before = &self.src[..span.start];
+        let line_start = before.rfind('\n').map(|ix| ix + 1).unwrap_or(0);
+
+        match before[line_start..].trim_left().chars().next() {
+            Some(c) if c == '-' || c == '*' || c == '+' => Some(c),
+            _ => None,
+        }
+    }
+
+    fn report_link<S>(&mut self, link: &Link, kind: &str, msg: S, level: LintLevel)
+    where
+        S: Into<String>,
+    {
+        let position = link.span.map(|span| span.line_col(self.src));
+        self.report(position, kind, msg, level);
+
+        if let Some(diagnostic) = self.diagnostics.last_mut() {
+            diagnostic.span = link.span;
+        }
+    }
+
+    fn report<S: Into<String>>(
+        &mut self,
+        position: Option<(usize, usize)>,
+        kind: &str,
+        msg: S,
+        level: LintLevel,
+    ) {
+        let level = match level {
+            LintLevel::Deny => Level::Error,
+            _ => Level::Warning,
+        };
+
+        self.diagnostics.push(Diagnostic {
+            file: self.filename.to_string(),
+            level: level,
+            kind: kind.to_string(),
+            message: msg.into(),
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            utf16_column: None,
+            span: None,
+            related_span: None,
+            suggestion: None,
+        });
+    }
+}
+
+/// The capitalisation style of a chapter's name.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TitleCase {
+    /// Every significant word is capitalised, "Like This One".
+    Title,
+    /// Only the first word is capitalised, "Like this one".
+    Sentence,
+}
+
+impl TitleCase {
+    /// Work out which style a name uses, ignoring the first word and any
+    /// short words (like "of" or "the") because they don't tell the two
+    /// apart. Returns `None` when it's ambiguous.
+    fn of(name: &str) -> Option<TitleCase> {
+        let mut capitalised = 0;
+        let mut lowercase = 0;
+
+        for word in name.split_whitespace().skip(1) {
+            if word.chars().count() <= 3 {
+                continue;
+            }
+
+            match word.chars().next() {
+                Some(c) if c.is_uppercase() => capitalised += 1,
+                Some(c) if c.is_lowercase() => lowercase += 1,
+                _ => {}
+            }
+        }
+
+        match (capitalised, lowercase) {
+            (0, 0) => None,
+            (_, 0) => Some(TitleCase::Title),
+            (0, _) => Some(TitleCase::Sentence),
+            _ => None,
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match *self {
+            TitleCase::Title => "title case",
+            TitleCase::Sentence => "sentence case",
+        }
+    }
+}
+
+/// Apply every safe, mechanical fix to the text of a `SUMMARY.md`:
+///
+/// - unordered list items all use the first list marker in the file
+/// - list items are indented with spaces instead of tabs, 4 to a tab
+/// - spaces in chapter destinations are percent-encoded
+/// - trailing whitespace is removed
+///
+/// The fixes are made to a `ConcreteSummary`, so lines which don't need
+/// fixing (including their line endings) are left exactly as they were, as
+/// is anything inside a fenced code block or an HTML comment.
+pub fn fix_summary(src: &str) -> String {
+    fix_summary_with(src, None)
+}
+
+/// Fix a `SUMMARY.md` like `fix_summary()`, for a book which wants nested
+/// chapters indented a particular way. Tabs become one level's worth of
+/// spaces, unless the book is meant to be indented with tabs, in which case
+/// they're left alone.
+pub fn fix_summary_with(src: &str, indentation: Option<Indentation>) -> String {
+    let mut summary = ConcreteSummary::parse(src);
+
+    let mut marker = None;
+    for item in summary.items_mut() {
+        item.marker = *marker.get_or_insert(item.marker);
+        item.indent = match indentation {
+            Some(Indentation::Tabs) => item.indent.clone(),
+            Some(Indentation::Spaces(width)) => expand_tabs(&item.indent, width),
+            None => expand_tabs(&item.indent, 4),
+        };
+        item.link = encode_destination(&item.link);
+
+        if item.rest.is_empty() {
+            item.link = item.link.trim_right().to_string();
+        } else {
+            item.rest = item.rest.trim_right().to_string();
+        }
+    }
+
+    for line in &mut summary.lines {
+        if let LineContent::Text(ref mut text) = line.content {
+            *text = text.trim_right().to_string();
+        }
+    }
+
+    summary.to_string()
+}
+
+/// Replace tabs in an indent with the equivalent number of spaces, with tab
+/// stops every `tab_width` columns.
+fn expand_tabs(indent: &str, tab_width: usize) -> String {
+    let mut expanded = String::new();
+
+    for c in indent.chars() {
+        if c == '\t' {
+            let width = tab_width - expanded.len() % tab_width;
+            expanded.extend((0..width).map(|_| ' '));
+        } else {
+            expanded.push(c);
+        }
+    }
+
+    expanded
+}
+
+/// Percent-encode any spaces in a link's destination, as long as it doesn't
+/// have a title (where the spaces would be meaningful).
+fn encode_destination(link: &str) -> String {
+    let start = match link.find("](") {
+        Some(ix) => ix + 2,
+        None => return link.to_string(),
+    };
+    let end = match link[start..].find(')') {
+        Some(ix) => start + ix,
+        None => return link.to_string(),
+    };
+
+    let destination = link[start..end].trim();
+    if !destination.contains(' ') || destination.contains('"') || destination.contains('\'') {
+        return link.to_string();
+    }
+
+    format!("{}{}{}", &link[..start], destination.replace(' ', "%20"), &link[end..])
+}
+
+/// Log every lint `Diagnostic`, failing if any of them were denied.
+pub fn report_lints(diagnostics: &[Diagnostic], summary_path: &Path) -> Result<()> {
+    let mut denied = 0;
+
+    for diagnostic in diagnostics {
+        let position = match (diagnostic.line, diagnostic.column) {
+            (Some(line), Some(column)) => format!(":{}:{}", line, column),
+            _ => String::new(),
+        };
+        let msg = format!(
+            "{}{}: {} [{}]",
+            diagnostic.file,
+            position,
+            diagnostic.message,
+            diagnostic.kind
+        );
+
+        if diagnostic.level == Level::Error {
+            error!("{}", msg);
+            denied += 1;
+        } else {
+            warn!("{}", msg);
+        }
+    }
+
+    if denied > 0 {
+        bail!("{} failed {} denied lint(s)", summary_path.display(), denied);
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use book::parse_summary;
+
+    fn lint(src: &str, config: &LintConfig) -> Vec<Diagnostic> {
+        let summary = parse_summary(src).unwrap();
+        lint_summary(&summary, src, "SUMMARY.md", config)
+    }
+
+    fn kinds(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.kind.as_str()).collect()
+    }
+
+    #[test]
+    fn a_tidy_summary_has_no_lints() {
+        let src = "# Summary\n\n- [First Chapter](first.md)\n    - [Nested Thing](nested.md)\n";
+
+        assert!(lint(src, &LintConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn detect_each_kind_of_lint() {
+        let src = "- [](empty.md)\n- [Second](second.txt) \n* [Third](third.md)\n";
+        let config = LintConfig {
+            trailing_whitespace: LintLevel::Warn,
+            ..Default::default()
+        };
+
+        let got = lint(src, &config);
+
+        assert_eq!(
+            kinds(&got),
+            vec![
+                "empty-title",
+                "trailing-whitespace",
+                "non-markdown-destination",
+                "inconsistent-list-markers",
+            ]
+        );
+        assert_eq!(got[3].line, Some(3));
+        assert_eq!(got[3].column, Some(3));
+    }
+
+    #[test]
+    fn denied_lints_are_errors_and_allowed_lints_are_skipped() {
+        let src = "- [Second](second.txt)\n";
+        let config = LintConfig {
+            non_markdown_destination: LintLevel::Deny,
+            ..Default::default()
+        };
+        let got = lint(src, &config);
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].level, Level::Error);
+
+        let config = LintConfig {
+            non_markdown_destination: LintLevel::Allow,
+            ..Default::default()
+        };
+        assert!(lint(src, &config).is_empty());
+    }
+
+    #[test]
+    fn trailing_whitespace_is_allowed_by_default() {
+        let src = "- [First](first.md)  \n";
+
+        assert!(lint(src, &LintConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn detect_mixed_title_case() {
+        let src = "- [Getting Started](start.md)\n- [Installing the tools](install.md)\n\
+                   - [Next Steps](next.md)\n";
+        let config = LintConfig {
+            inconsistent_title_case: LintLevel::Warn,
+            ..Default::default()
+        };
+
+        let got = lint(src, &config);
+
+        assert_eq!(kinds(&got), vec!["inconsistent-title-case"]);
+        assert_eq!(got[0].line, Some(2));
+    }
+
+    #[test]
+    fn detect_duplicate_titles_at_the_same_level() {
+        let src = "- [Intro](a.md)\n    - [Setup](b.md)\n- [Usage](c.md)\n    - [Setup](d.md)\n\
+                   - [Intro](e.md)\n";
+        let config = LintConfig {
+            duplicate_title: LintLevel::Warn,
+            ..Default::default()
+        };
+
+        let got = lint(src, &config);
+
+        assert_eq!(kinds(&got), vec!["duplicate-title"]);
+        assert_eq!(got[0].line, Some(5));
+        let first = got[0].related_span.unwrap();
+        assert_eq!(&src[first.start..first.end], "[Intro](a.md)");
+    }
+
+    #[test]
+    fn fix_a_messy_summary() {
+        let src = "# Summary\r\n\n- [First](first.md)  \n\t* [Nested](my nested.md)\n\
+                   * * *\n+ [Titled](a b.md \"Some Title\")\n";
+        let should_be = "# Summary\r\n\n- [First](first.md)\n    - [Nested](my%20nested.md)\n\
+                         * * *\n- [Titled](a b.md \"Some Title\")\n";
+
+        let got = fix_summary(src);
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn fixing_uses_the_books_indentation() {
+        let src = "- [First](first.md)\n\t- [Nested](nested.md)\n";
+
+        let got = fix_summary_with(src, Some(Indentation::Spaces(2)));
+        assert_eq!(got, "- [First](first.md)\n  - [Nested](nested.md)\n");
+        assert_eq!(fix_summary_with(src, Some(Indentation::Tabs)), src);
+    }
+
+    #[test]
+    fn fixing_a_tidy_summary_changes_nothing() {
+        let src = "# Summary\r\n\r\n[Intro](intro.md)\r\n\r\n- [First](first.md)\r\n";
+
+        assert_eq!(fix_summary(src), src);
+    }
+
+    #[test]
+    fn fixing_keeps_the_spacing_after_list_markers() {
+        let src = "-   [First](first.md)\n*\t[Second](my second.md)\n";
+        let should_be = "-   [First](first.md)\n-\t[Second](my%20second.md)\n";
+
+        assert_eq!(fix_summary(src), should_be);
+    }
+
+    #[test]
+    fn code_blocks_and_comments_are_left_alone_when_fixing() {
+        let src = "- [First](first.md)\n\n\
+                   ```\n* [Example](an example.md)  \n\t+ [Nested](x.md)\n```\n\n\
+                   <!--\n* [Hidden](hidden chapter.md)  \n-->\n\n\
+                   ~~~\n+  [Tilde](tilde.md)\t\n~~~\n";
+
+        assert_eq!(fix_summary(src), src);
+    }
+}
@@ -10,32 +10,57 @@
 mod summary;
 mod book;
 mod init;
-
-pub use self::book::{load_book, Book, BookItem, BookItems, Chapter};
-pub use self::summary::{parse_summary, Link, SectionNumber, Summary, SummaryItem};
+mod lint;
+mod stats;
+mod translations;
+
+pub use self::book::{add_sub_book, append_orphans, load_book, load_book_lazily,
+                     load_book_with_sub_books, Book,
+                     BookItem, BookItems, Chapter, ChapterMetadata, Chapters, ContentHashes,
+                     Generator};
+pub use self::summary::{diagnose_summary, format_diagnostic, parse_summary, parse_summary_partial,
+                        parse_summary_with, parse_summary_with_warnings, read_summary,
+                        read_summary_with_map, ConcreteSummary, Diagnostic, FrontMatter, Level,
+                        LineContent, Link, Links, ListItem, ParseErrorKind, ParseOptions,
+                        ParsedSummary, SectionNumber, SourceMap, Span, Summary, SummaryBuilder,
+                        SummaryItem, SummaryLine};
 pub use self::init::BookBuilder;
+pub use self::lint::{fix_summary, fix_summary_with, lint_summary};
+pub use self::stats::BookStats;
+pub use self::translations::{LanguageLink, TranslationReport, Translations};
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::io::Write;
 use std::process::Command;
 use tempdir::TempDir;
 
+use serde_json;
 use utils;
 use renderer::{HtmlHandlebars, Renderer};
-use preprocess;
+use preprocess::{self, Preprocessor, PreprocessorContext};
+use preprocess::links::LinkPreprocessor;
 use errors::*;
 
-use config::Config;
+use config::{Config, ConfigBuilder};
+
+/// The file (relative to the book's root) where a build saves the chapters'
+/// content hashes, for the next build to compare against. It's kept out of
+/// the build directory so it doesn't get published with the book.
+const CONTENT_HASHES_FILE: &'static str = ".mdbook/content-hashes.json";
 
 /// The object used to manage and build a book.
 pub struct MDBook {
     /// The book's root directory.
     pub root: PathBuf,
-    /// The configuration used to tweak now a book is built.
+    /// The configuration used to tweak how a book is built. Its paths have
+    /// been made absolute with `Config::resolve_paths()`, so they don't
+    /// depend on the current directory.
     pub config: Config,
 
     book: Book,
     renderer: Box<Renderer>,
+    preprocessors: Vec<Box<Preprocessor>>,
 
     /// The URL used for live reloading when serving up the book.
     pub livereload: Option<String>,
@@ -44,25 +69,22 @@ pub struct MDBook {
 impl MDBook {
     /// Load a book from its root directory on disk.
     pub fn load<P: Into<PathBuf>>(book_root: P) -> Result<MDBook> {
-        let book_root = book_root.into();
-        let config_location = book_root.join("book.toml");
-
-        // the book.json file is no longer used, so we should emit a warning to
-        // let people know to migrate to book.toml
-        if book_root.join("book.json").exists() {
-            warn!("It appears you are still using book.json for configuration.");
-            warn!("This format is no longer used, so you should migrate to the");
-            warn!("book.toml format.");
-            warn!("Check the user guide for migration information:");
-            warn!("\thttps://rust-lang-nursery.github.io/mdBook/format/config.html");
-        }
+        MDBook::load_profile(book_root.into(), None)
+    }
 
-        let config = if config_location.exists() {
-            debug!("[*] Loading config from {}", config_location.display());
-            Config::from_disk(&config_location)?
-        } else {
-            Config::default()
-        };
+    /// Load a book like `load()`, with the settings from the
+    /// `[profile.<name>]` table of its config laid over the rest of the file.
+    pub fn load_with_profile<P: Into<PathBuf>>(book_root: P, profile: &str) -> Result<MDBook> {
+        MDBook::load_profile(book_root.into(), Some(profile))
+    }
+
+    fn load_profile(book_root: PathBuf, profile: Option<&str>) -> Result<MDBook> {
+        let mut builder = ConfigBuilder::new();
+        builder.with_book_dir(&book_root)?;
+        if let Some(profile) = profile {
+            builder.with_profile(profile);
+        }
+        let config = builder.with_env()?.build()?;
 
         if log_enabled!(::log::LogLevel::Trace) {
             for line in format!("Config: {:#?}", config).lines() {
@@ -74,17 +96,48 @@ impl MDBook {
     }
 
     /// Load a book from its root directory using a custom config.
+    ///
+    /// Relative paths in the config are resolved against `book_root`.
     pub fn load_with_config<P: Into<PathBuf>>(book_root: P, config: Config) -> Result<MDBook> {
-        let book_root = book_root.into();
+        MDBook::load_book_root(book_root.into(), config, false)
+    }
 
-        let src_dir = book_root.join(&config.book.src);
-        let book = book::load_book(&src_dir, &config.build)?;
+    /// Load a book like `load_with_config()`, but leave reading each
+    /// chapter's file until its content is needed (see
+    /// `book::load_book_lazily()`). This keeps reloading a big book cheap,
+    /// e.g. for every rebuild in `mdbook serve`, and `build()` reads whatever
+    /// it needs.
+    pub fn load_lazily_with_config<P: Into<PathBuf>>(
+        book_root: P,
+        config: Config,
+    ) -> Result<MDBook> {
+        MDBook::load_book_root(book_root.into(), config, true)
+    }
+
+    fn load_book_root(book_root: PathBuf, mut config: Config, lazy: bool) -> Result<MDBook> {
+        config.resolve_paths(&book_root)?;
+        let book_root = book_root.canonicalize()?;
+
+        let src_dir = config.book.src.clone();
+
+        let mut book = if config.book.multilingual {
+            load_translations(&src_dir, &config)?
+        } else {
+            book::load_book_with_sub_books(
+                &src_dir,
+                &config.build,
+                &config.book.sub_books,
+                lazy,
+            )?
+        };
+        book.remove_unpublished();
 
         Ok(MDBook {
             root: book_root,
             config: config,
             book: book,
             renderer: Box::new(HtmlHandlebars::new()),
+            preprocessors: vec![Box::new(LinkPreprocessor::new())],
             livereload: None,
         })
     }
@@ -104,6 +157,7 @@ impl MDBook {
     ///     match *item {
     ///         BookItem::Chapter(ref chapter) => {},
     ///         BookItem::Separator => {},
+    ///         BookItem::PartTitle(ref title) => {},
     ///     }
     /// }
     ///
@@ -145,12 +199,77 @@ impl MDBook {
     pub fn build(&mut self) -> Result<()> {
         debug!("[fn]: build");
 
+        // books loaded lazily still need every chapter's content to render
+        self.book.load_content()?;
+        self.book.remove_unpublished();
+        if !self.config.build.allow_symlinks {
+            self.book.check_for_symlinks(self.source_dir())?;
+        }
+        self.book.check_output_paths()?;
+
+        if let Some(previous) = self.previous_content_hashes() {
+            let changed = self.book.changed_since(&previous);
+            debug!("[*] {} chapter(s) changed since the last build", changed.len());
+        }
+
         let dest = self.get_destination();
         if dest.exists() {
             utils::fs::remove_dir_content(&dest).chain_err(|| "Unable to clear output directory")?;
         }
 
-        self.renderer.render(self)
+        // preprocessors only modify the copy of the book being rendered
+        let original = self.book.clone();
+        let ctx = self.preprocessor_context(self.renderer.name());
+        let rendered = preprocess::run_all(&self.preprocessors, &ctx, &mut self.book)
+            .and_then(|_| self.renderer.render(self));
+        self.book = original;
+        rendered?;
+
+        self.save_content_hashes()
+    }
+
+    fn preprocessor_context(&self, renderer: &str) -> PreprocessorContext {
+        PreprocessorContext {
+            root: self.root.clone(),
+            config: self.config.clone(),
+            renderer: renderer.to_string(),
+        }
+    }
+
+    /// Save the chapters' content hashes for the next build. Chapters whose
+    /// paths aren't valid UTF-8 can't be saved, so they'll always look like
+    /// they changed.
+    fn save_content_hashes(&self) -> Result<()> {
+        let hashes: BTreeMap<String, u64> = self.book
+            .content_hashes()
+            .into_iter()
+            .filter_map(|(path, hash)| path.to_str().map(|path| (path.to_string(), hash)))
+            .collect();
+        let hashes = serde_json::to_vec(&hashes)
+            .chain_err(|| "Unable to serialize the chapters' content hashes")?;
+
+        utils::fs::create_file(&self.root.join(CONTENT_HASHES_FILE))?
+            .write_all(&hashes)
+            .chain_err(|| "Unable to save the chapters' content hashes")
+    }
+
+    /// Get the chapters' content hashes saved by the last build, if there
+    /// was one.
+    pub fn previous_content_hashes(&self) -> Option<ContentHashes> {
+        let location = self.root.join(CONTENT_HASHES_FILE);
+        let content = match utils::fs::file_to_string(&location) {
+            Ok(content) => content,
+            Err(_) => return None,
+        };
+
+        serde_json::from_str::<BTreeMap<String, u64>>(&content)
+            .ok()
+            .map(|hashes| {
+                hashes
+                    .into_iter()
+                    .map(|(path, hash)| (PathBuf::from(path), hash))
+                    .collect()
+            })
     }
 
     // FIXME: This doesn't belong as part of `MDBook`. It is only used by the HTML renderer
@@ -171,7 +290,20 @@ impl MDBook {
         self
     }
 
+    /// Add a preprocessor to run on the book's chapters before it gets
+    /// rendered. The order preprocessors run in can be tweaked with the
+    /// `before` and `after` keys of their `[preprocessor.<name>]` tables,
+    /// otherwise they run in the order they were added, after the built-in
+    /// `links` preprocessor.
+    pub fn with_preprocessor<P: Preprocessor + 'static>(mut self, preprocessor: P) -> Self {
+        self.preprocessors.push(Box::new(preprocessor));
+        self
+    }
+
     /// Run `rustdoc` tests on the book, linking against the provided libraries.
+    ///
+    /// The chapters are preprocessed first (so `{{#include}}` links get
+    /// expanded), with `"test"` as the renderer's name.
     pub fn test(&mut self, library_paths: Vec<&str>) -> Result<()> {
         let library_args: Vec<&str> = (0..library_paths.len())
             .map(|_| "-L")
@@ -181,21 +313,21 @@ impl MDBook {
 
         let temp_dir = TempDir::new("mdbook")?;
 
-        for item in self.iter() {
+        let mut book = self.book.clone();
+        book.load_content()?;
+        let ctx = self.preprocessor_context("test");
+        preprocess::run_all(&self.preprocessors, &ctx, &mut book)?;
+
+        for item in book.iter() {
             if let BookItem::Chapter(ref ch) = *item {
-                if !ch.path.as_os_str().is_empty() {
+                if !ch.is_draft() {
                     let path = self.source_dir().join(&ch.path);
-                    let base = path.parent()
-                        .ok_or_else(|| String::from("Invalid bookitem path!"))?;
-                    let content = utils::fs::file_to_string(&path)?;
-                    // Parse and expand links
-                    let content = preprocess::links::replace_all(&content, base)?;
                     println!("[*]: Testing file: {:?}", path);
 
                     // write preprocessed file to tempdir
                     let path = temp_dir.path().join(&ch.path);
                     let mut tmpf = utils::fs::create_file(&path)?;
-                    tmpf.write_all(content.as_bytes())?;
+                    tmpf.write_all(ch.content.as_bytes())?;
 
                     let output = Command::new("rustdoc")
                         .arg(&path)
@@ -226,6 +358,38 @@ impl MDBook {
         self.root.join(&self.config.book.src)
     }
 
+    /// Gather statistics about the book's contents.
+    pub fn stats(&self) -> BookStats {
+        self.book.stats()
+    }
+
+    /// Find every chapter which includes the file at `path`, which can be
+    /// absolute or relative to the book's `src` directory. A lazily loaded
+    /// book only knows what its chapters include once it has been built.
+    pub fn chapters_depending_on<P: AsRef<Path>>(&self, path: P) -> Vec<&Chapter> {
+        let path = path.as_ref();
+        let src_dir = self.source_dir();
+        let relative = path.strip_prefix(&src_dir).unwrap_or(path);
+
+        self.book.chapters_depending_on(relative)
+    }
+
+    /// Fill in the draft chapters in `SUMMARY.md` whose `generator` attribute
+    /// is `name` with content produced by `generator`.
+    pub fn generate_chapters<G: Generator>(&mut self, name: &str, generator: G) -> Result<()> {
+        self.book.generate(name, &generator)
+    }
+
+    /// Read and parse this book's `SUMMARY.md` again, e.g. to inspect its
+    /// structure without the chapter contents.
+    pub fn parse_summary(&self) -> Result<Summary> {
+        let options = ParseOptions {
+            strict: self.config.build.deny_warnings,
+            indentation: self.config.build.summary_indent,
+        };
+        Summary::from_path_with(self.source_dir().join("SUMMARY.md"), &options)
+    }
+
     // FIXME: This belongs as part of the `HtmlConfig`.
     #[doc(hidden)]
     pub fn theme_dir(&self) -> PathBuf {
@@ -235,3 +399,40 @@ impl MDBook {
         }
     }
 }
+
+/// Load every translation of a multilingual book (see `Translations`) into a
+/// single book. Each language goes under a part title with its pages in its
+/// own directory (e.g. `fr/intro.html`), starting with the default language
+/// from `book.language`.
+fn load_translations(src_dir: &Path, config: &Config) -> Result<Book> {
+    let default_language = match config.book.language {
+        Some(ref language) => language.clone(),
+        None => bail!(
+            "A multilingual book needs `book.language` to say which translation is the \
+             default"
+        ),
+    };
+    if !config.book.sub_books.is_empty() {
+        warn!("Ignoring `book.sub-books`, they can't be used in a multilingual book");
+    }
+
+    let translations = Translations::load(src_dir, &default_language, &config.build)?;
+    for (language, missing) in &translations.report().untranslated {
+        if !missing.is_empty() {
+            info!("The {} translation is missing {} chapter(s)", language, missing.len());
+        }
+    }
+
+    let mut books = translations.books;
+    let default_book = books
+        .remove(&default_language)
+        .expect("Translations::load() checks the default language exists");
+
+    let mut book = Book::new();
+    book.merge(default_book, &default_language, &default_language);
+    for (language, translation) in books {
+        book.merge(translation, &language, &language);
+    }
+
+    Ok(book)
+}
@@ -0,0 +1,145 @@
+//! Statistics about a book's contents.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Event, Parser, Tag};
+
+use super::book::{Book, Chapter};
+use utils;
+
+
+/// A summary of how big a book is and what's in it, as returned by
+/// `Book::stats()`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BookStats {
+    /// The number of chapters, not counting drafts.
+    pub chapters: usize,
+    /// The number of words of prose in every chapter.
+    pub words: usize,
+    /// The number of code blocks for each language, where code blocks
+    /// without a language are counted under an empty string.
+    pub code_blocks: BTreeMap<String, usize>,
+    /// The number of images.
+    pub images: usize,
+    /// The number of links to `.md` or `.html` files which aren't chapters
+    /// in the book.
+    pub broken_links: usize,
+}
+
+impl Book {
+    /// Gather statistics about the book's chapters.
+    pub fn stats(&self) -> BookStats {
+        let chapters: Vec<&Chapter> = self.chapters()
+            .map(|(_, _, ch)| ch)
+            .filter(|ch| !ch.is_draft())
+            .collect();
+        let paths: Vec<&Path> = chapters.iter().map(|ch| ch.path.as_path()).collect();
+
+        let mut stats = BookStats {
+            chapters: chapters.len(),
+            words: self.word_count(),
+            ..Default::default()
+        };
+
+        for ch in &chapters {
+            for event in Parser::new(&ch.content) {
+                match event {
+                    Event::Start(Tag::CodeBlock(info)) => {
+                        let language = info.split(|c: char| c == ',' || c.is_whitespace())
+                            .next()
+                            .unwrap_or("")
+                            .to_string();
+                        *stats.code_blocks.entry(language).or_insert(0) += 1;
+                    }
+                    Event::Start(Tag::Image(..)) => stats.images += 1,
+                    Event::Start(Tag::Link(dest, _)) => {
+                        if let Some(target) = chapter_link_target(&ch.path, &dest) {
+                            if !paths.contains(&target.as_path()) {
+                                stats.broken_links += 1;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        stats
+    }
+}
+
+/// If `dest` is a relative link to another chapter, get the path (relative
+/// to the `src` directory) of that chapter's markdown file.
+fn chapter_link_target(chapter: &Path, dest: &str) -> Option<PathBuf> {
+    if dest.contains("://") || dest.starts_with("mailto:") || dest.starts_with('/') {
+        return None;
+    }
+
+    let file = dest.split('#').next().unwrap_or("");
+    let file = Path::new(file);
+    let is_chapter = match file.extension() {
+        Some(ext) => ext == "md" || ext == "html",
+        None => false,
+    };
+    if !is_chapter {
+        return None;
+    }
+
+    let dir = chapter.parent().unwrap_or_else(|| Path::new(""));
+    Some(utils::fs::collapse_path(dir.join(file)).with_extension("md"))
+}
+
+impl Display for BookStats {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Chapters:     {}", self.chapters)?;
+        writeln!(f, "Words:        {}", self.words)?;
+        writeln!(f, "Images:       {}", self.images)?;
+        writeln!(f, "Broken links: {}", self.broken_links)?;
+
+        let total: usize = self.code_blocks.values().sum();
+        writeln!(f, "Code blocks:  {}", total)?;
+        for (language, count) in &self.code_blocks {
+            let language = if language.is_empty() {
+                "(none)"
+            } else {
+                language
+            };
+            writeln!(f, "    {}: {}", language, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use book::BookItem;
+
+    #[test]
+    fn gather_statistics_about_a_book() {
+        let first = "# First\n\n![logo](logo.png)\n\n```rust\nfn main() {}\n```\n\n\
+                     See [the second chapter](nested/second.html#top) and [a missing \
+                     one](missing.md).";
+        let second = "```\nplain\n```\n\n```rust,ignore\nunsafe {}\n```\n\n\
+                      Back to [the start](../first.md) or [the web](https://example.com).";
+        let book = Book {
+            sections: vec![
+                BookItem::Chapter(Chapter::new("First", first.to_string(), "first.md")),
+                BookItem::Chapter(Chapter::new("Second", second.to_string(), "nested/second.md")),
+                BookItem::Chapter(Chapter::new("Draft", String::new(), "")),
+            ],
+        };
+
+        let got = book.stats();
+
+        assert_eq!(got.chapters, 2);
+        assert_eq!(got.words, book.word_count());
+        assert_eq!(got.images, 1);
+        assert_eq!(got.broken_links, 1);
+        assert_eq!(got.code_blocks[""], 1);
+        assert_eq!(got.code_blocks["rust"], 2);
+    }
+}
@@ -0,0 +1,364 @@
+//! A lossless view of a `SUMMARY.md`, for tools which edit the file and
+//! don't want to reformat the lines they didn't touch.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use super::{literal_lines, parse_summary_with_warnings, Link};
+
+
+/// A `SUMMARY.md` split into lines which remember exactly how they were
+/// written, down to their whitespace, list markers, comments and line
+/// endings.
+///
+/// Unlike a `Summary`, this can be written back out (with `to_string()`)
+/// byte-for-byte. Lines can be edited, inserted or removed, and everything
+/// else stays the way it was.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConcreteSummary {
+    /// Did the file start with a byte order mark?
+    pub bom: bool,
+    /// Every line in the file, in order.
+    pub lines: Vec<SummaryLine>,
+}
+
+impl ConcreteSummary {
+    /// Split the text of a `SUMMARY.md` into lines, picking out the list
+    /// items which start with a link.
+    pub fn parse(src: &str) -> ConcreteSummary {
+        let bom = src.starts_with('\u{feff}');
+        let body = if bom { &src['\u{feff}'.len_utf8()..] } else { src };
+
+        let links = link_columns(body);
+        let literal = literal_lines(body);
+        let mut pieces: Vec<&str> = body.split('\n').collect();
+        let last = pieces.pop().unwrap_or("");
+
+        let mut lines: Vec<SummaryLine> = pieces
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let is_literal = literal.get(i).cloned().unwrap_or(false);
+                if line.ends_with('\r') {
+                    let line = &line[..line.len() - 1];
+                    SummaryLine::parse(line, "\r\n", is_literal, links.get(&i))
+                } else {
+                    SummaryLine::parse(line, "\n", is_literal, links.get(&i))
+                }
+            })
+            .collect();
+
+        if !last.is_empty() {
+            let i = lines.len();
+            let is_literal = literal.get(i).cloned().unwrap_or(false);
+            lines.push(SummaryLine::parse(last, "", is_literal, links.get(&i)));
+        }
+
+        ConcreteSummary {
+            bom: bom,
+            lines: lines,
+        }
+    }
+
+    /// Get every list item which starts with a link, in order.
+    pub fn items(&self) -> Vec<&ListItem> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line.content {
+                LineContent::Item(ref item) => Some(item),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get every list item which starts with a link, so they can be edited.
+    pub fn items_mut(&mut self) -> Vec<&mut ListItem> {
+        self.lines
+            .iter_mut()
+            .filter_map(|line| match line.content {
+                LineContent::Item(ref mut item) => Some(item),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Insert a new line before the line at `index`, using the same line
+    /// endings as the rest of the file. An `index` of `lines.len()` adds the
+    /// line to the end.
+    pub fn insert(&mut self, index: usize, content: LineContent) {
+        let line_ending = self.line_ending();
+
+        if index == self.lines.len() {
+            if let Some(last) = self.lines.last_mut() {
+                if last.line_ending.is_empty() {
+                    last.line_ending = line_ending.clone();
+                }
+            }
+        }
+
+        self.lines.insert(
+            index,
+            SummaryLine {
+                content: content,
+                line_ending: line_ending,
+            },
+        );
+    }
+
+    /// The line ending used by the first line, or `"\n"` for a file with
+    /// only one line.
+    fn line_ending(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.line_ending.clone())
+            .find(|ending| !ending.is_empty())
+            .unwrap_or_else(|| String::from("\n"))
+    }
+}
+
+impl Display for ConcreteSummary {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.bom {
+            write!(f, "\u{feff}")?;
+        }
+
+        for line in &self.lines {
+            write!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One line of a `ConcreteSummary`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryLine {
+    /// What's on the line.
+    pub content: LineContent,
+    /// `"\n"`, `"\r\n"`, or nothing at all for the last line of a file which
+    /// doesn't end in a newline.
+    pub line_ending: String,
+}
+
+impl SummaryLine {
+    fn parse(
+        line: &str,
+        line_ending: &str,
+        is_literal: bool,
+        link: Option<&(usize, usize)>,
+    ) -> SummaryLine {
+        let content = if is_literal {
+            LineContent::Literal(line.to_string())
+        } else {
+            match ListItem::parse(line, link) {
+                Some(item) => LineContent::Item(item),
+                None => LineContent::Text(line.to_string()),
+            }
+        };
+
+        SummaryLine {
+            content: content,
+            line_ending: line_ending.to_string(),
+        }
+    }
+}
+
+impl Display for SummaryLine {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.content {
+            LineContent::Item(ref item) => write!(f, "{}", item)?,
+            LineContent::Literal(ref text) | LineContent::Text(ref text) => {
+                write!(f, "{}", text)?
+            }
+        }
+
+        write!(f, "{}", self.line_ending)
+    }
+}
+
+/// The different kinds of line in a `ConcreteSummary`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineContent {
+    /// A list item which starts with a link.
+    Item(ListItem),
+    /// A line inside a fenced code block or an HTML comment, which is just
+    /// text as far as the summary is concerned.
+    Literal(String),
+    /// Anything else, such as a title, a prefix chapter, a separator or a
+    /// blank line.
+    Text(String),
+}
+
+/// A list item starting with a link, split into the pieces it was written
+/// with. Putting the pieces back together gives the original line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListItem {
+    /// The whitespace before the list marker.
+    pub indent: String,
+    /// The `-`, `*` or `+` starting the item.
+    pub marker: char,
+    /// The whitespace between the list marker and the link.
+    pub spacing: String,
+    /// The link, exactly as it was written (e.g. `[Intro](intro.md)`).
+    pub link: String,
+    /// Anything after the link, such as a comment or trailing whitespace.
+    pub rest: String,
+}
+
+impl ListItem {
+    /// Create a list item for `link`, indented by `indent`.
+    pub fn new<S: Into<String>>(indent: S, marker: char, link: &Link) -> ListItem {
+        ListItem {
+            indent: indent.into(),
+            marker: marker,
+            spacing: String::from(" "),
+            link: link.to_string(),
+            rest: String::new(),
+        }
+    }
+
+    /// Split a line into a list item, if it's a list marker followed by a
+    /// link. `link` is where the parser found the link in the line, if it
+    /// found one.
+    fn parse(line: &str, link: Option<&(usize, usize)>) -> Option<ListItem> {
+        let trimmed = line.trim_left_matches(|c: char| c == ' ' || c == '\t');
+        let indent = &line[..line.len() - trimmed.len()];
+
+        let mut chars = trimmed.chars();
+        let marker = match chars.next() {
+            Some(c) if c == '-' || c == '*' || c == '+' => c,
+            _ => return None,
+        };
+
+        let after_marker = chars.as_str();
+        let link_text = after_marker.trim_left();
+        if link_text.len() == after_marker.len() || !link_text.starts_with('[') {
+            return None;
+        }
+
+        let link_start = line.len() - link_text.len();
+        let link_end = match link {
+            Some(&(start, end)) if start == link_start => end,
+            _ => line.len(),
+        };
+
+        Some(ListItem {
+            indent: indent.to_string(),
+            marker: marker,
+            spacing: after_marker[..after_marker.len() - link_text.len()].to_string(),
+            link: line[link_start..link_end].to_string(),
+            rest: line[link_end..].to_string(),
+        })
+    }
+}
+
+impl Display for ListItem {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}{}{}",
+            self.indent,
+            self.marker,
+            self.spacing,
+            self.link,
+            self.rest
+        )
+    }
+}
+
+/// Find where each link the parser found starts and ends, as columns in
+/// the (0-based) line it starts on. Links which run onto the next line are
+/// left out.
+fn link_columns(src: &str) -> BTreeMap<usize, (usize, usize)> {
+    let line_starts: Vec<usize> = ::std::iter::once(0)
+        .chain(src.match_indices('\n').map(|(ix, _)| ix + 1))
+        .collect();
+    let summary = parse_summary_with_warnings(src).summary;
+
+    let mut columns = BTreeMap::new();
+    for (link, _) in summary.iter() {
+        let span = match link.span {
+            Some(span) => span,
+            None => continue,
+        };
+        let line = match line_starts.binary_search(&span.start) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let line_start = line_starts[line];
+        let line_end = src[line_start..]
+            .find('\n')
+            .map(|ix| line_start + ix)
+            .unwrap_or_else(|| src.len());
+
+        if span.end <= line_end {
+            columns
+                .entry(line)
+                .or_insert((span.start - line_start, span.end - line_start));
+        }
+    }
+
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_and_writing_a_summary_changes_nothing() {
+        let src = "\u{feff}# Summary\r\n\r\n[Intro](intro.md)\r\n\r\n\
+                   -   [First](first.md) <!-- a comment -->  \r\n\
+                   \t* [Nested](nested.md)\r\n\
+                   ```\r\n+ [Example](example.md)\r\n```\r\n\
+                   ---\r\n+ [Last](last.md)";
+
+        let got = ConcreteSummary::parse(src);
+
+        assert!(got.bom);
+        assert_eq!(got.items().len(), 3);
+        assert_eq!(got.to_string(), src);
+    }
+
+    #[test]
+    fn list_items_keep_their_pieces() {
+        let src = "- [Parent](parent.md)\n\t*  [First](first.md) <!-- x -->\n";
+        let got = ConcreteSummary::parse(src);
+
+        let should_be = ListItem {
+            indent: String::from("\t"),
+            marker: '*',
+            spacing: String::from("  "),
+            link: String::from("[First](first.md)"),
+            rest: String::from(" <!-- x -->"),
+        };
+        assert_eq!(got.items()[1], &should_be);
+    }
+
+    #[test]
+    fn items_in_code_blocks_are_literal() {
+        let got = ConcreteSummary::parse("~~~\n- [Example](example.md)\n~~~\n");
+
+        assert!(got.items().is_empty());
+        assert_eq!(
+            got.lines[1].content,
+            LineContent::Literal(String::from("- [Example](example.md)"))
+        );
+    }
+
+    #[test]
+    fn inserting_a_chapter_leaves_the_other_lines_alone() {
+        let src = "# Summary\r\n\r\n* [First](first.md)  \r\n  * [Third](third.md)";
+        let mut summary = ConcreteSummary::parse(src);
+
+        let item = ListItem::new("  ", '*', &Link::new("Second", "second.md"));
+        summary.insert(3, LineContent::Item(item));
+        let item = ListItem::new("", '*', &Link::new("Fourth", "fourth.md"));
+        let end = summary.lines.len();
+        summary.insert(end, LineContent::Item(item));
+
+        let should_be = "# Summary\r\n\r\n* [First](first.md)  \r\n  * [Second](second.md)\r\n\
+                         \x20 * [Third](third.md)\r\n* [Fourth](fourth.md)\r\n";
+        assert_eq!(summary.to_string(), should_be);
+    }
+}
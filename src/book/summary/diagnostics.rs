@@ -0,0 +1,392 @@
+//! Turning the problems found in a `SUMMARY.md` into diagnostics which can be
+//! shown to a person or handed to other tools.
+
+use std::cmp;
+use std::path::{Path, PathBuf};
+use serde_json;
+use config::BuildConfig;
+use book::lint::lint_summary;
+use super::{parse_summary_with_warnings, read_summary_with_map, ParseErrorKind, Span};
+use errors::*;
+
+
+/// Format an error from parsing the `src` text like a compiler diagnostic,
+/// showing the offending line with a caret under the column:
+///
+/// ```text
+/// error: Suffix chapters cannot be followed by a list
+///  --> src/SUMMARY.md:7:1
+///   |
+/// 7 | - [Oops](oops.md)
+///   | ^
+/// ```
+///
+/// When the parser skipped the rest of the line to recover from the error,
+/// everything it skipped is underlined. Errors which don't have a location
+/// just get the `error: ...` line.
+pub fn format_diagnostic(err: &Error, src: &str, filename: &str) -> String {
+    let (line, col, msg) = match *err.kind() {
+        ErrorKind::ParseError(line, col, ref kind) => (line, col, kind),
+        _ => return format!("error: {}", err),
+    };
+
+    let source_line = src.lines().nth(line.saturating_sub(1)).unwrap_or("");
+    let source_line = source_line.trim_right_matches('\r');
+    // keep any tabs so the caret lines up with the text above it
+    let padding: String = source_line
+        .chars()
+        .take(col.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let underline_len = if msg.skips_line() {
+        let remaining = source_line.trim_right().chars().count();
+        cmp::max(remaining.saturating_sub(col.saturating_sub(1)), 1)
+    } else {
+        1
+    };
+    let underline: String = (0..underline_len).map(|_| '^').collect();
+
+    let line_number = line.to_string();
+    let gutter: String = line_number.chars().map(|_| ' ').collect();
+
+    let mut diagnostic = format!("error: {}\n", msg);
+    diagnostic.push_str(&format!("{}--> {}:{}:{}\n", gutter, filename, line, col));
+    diagnostic.push_str(&format!("{} |\n", gutter));
+    diagnostic.push_str(&format!("{} | {}\n", line_number, source_line));
+    diagnostic.push_str(&format!("{} | {}{}", gutter, padding, underline));
+
+    diagnostic
+}
+
+/// Read and parse the `SUMMARY.md` at `path`, returning every error and
+/// warning found as a `Diagnostic` (errors first) instead of stopping at the
+/// first one.
+///
+/// Problems in a file pulled in with `{{#include}}` are reported against
+/// that file. Chapters whose files don't exist are errors, unless `cfg`
+/// says they'll be created by the build, and the lints configured in `cfg`
+/// are run as well.
+pub fn diagnose_summary<P: AsRef<Path>>(path: P, cfg: &BuildConfig) -> Result<Vec<Diagnostic>> {
+    let path = path.as_ref();
+    let (content, map) = read_summary_with_map(path)?;
+    let filename = path.display().to_string();
+
+    let parsed = parse_summary_with_warnings(&content);
+    let src_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let missing = parsed.summary.validate_destinations(src_dir).err().unwrap_or_default();
+    let missing_level = if cfg.create_missing {
+        Level::Warning
+    } else {
+        Level::Error
+    };
+
+    let errors = parsed
+        .errors
+        .iter()
+        .map(|e| Diagnostic::new(e, Level::Error, &content, &filename));
+    let missing = missing
+        .iter()
+        .map(|e| Diagnostic::new(e, missing_level, &content, &filename));
+    let warnings = parsed
+        .warnings
+        .iter()
+        .map(|e| Diagnostic::new(e, Level::Warning, &content, &filename));
+    let lint_config = if cfg.deny_warnings {
+        cfg.lints.deny_warnings()
+    } else {
+        cfg.lints.clone()
+    };
+    let lints = lint_summary(&parsed.summary, &content, &filename, &lint_config);
+
+    let mut diagnostics: Vec<Diagnostic> = errors
+        .chain(missing)
+        .chain(warnings)
+        .chain(lints)
+        .collect();
+    for diagnostic in &mut diagnostics {
+        map.remap(diagnostic);
+
+        let text = map.text_of(Path::new(&diagnostic.file)).unwrap_or(&content);
+        diagnostic.utf16_column = match (diagnostic.line, diagnostic.column) {
+            (Some(line), Some(column)) => utf16_column(text, line, column),
+            _ => None,
+        };
+    }
+
+    Ok(diagnostics)
+}
+
+/// How serious a `Diagnostic` is.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// Something which stops the book from being built.
+    Error,
+    /// Something which is probably a mistake, but doesn't stop the build.
+    Warning,
+}
+
+/// A machine-readable version of an error found in a `SUMMARY.md`, for
+/// editor plugins and CI tools.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// The file the problem was found in.
+    pub file: String,
+    /// Whether this is an error or a warning.
+    pub level: Level,
+    /// A short, stable identifier for the kind of problem (e.g.
+    /// `"mixed-indentation"`).
+    pub kind: String,
+    /// A human readable description of the problem.
+    pub message: String,
+    /// The (1-based) line the problem is on, if known.
+    pub line: Option<usize>,
+    /// The (1-based) column the problem starts at, if known.
+    pub column: Option<usize>,
+    /// The same column counted in UTF-16 code units instead of characters,
+    /// for editors (and the Language Server Protocol) which count that way.
+    pub utf16_column: Option<usize>,
+    /// The bytes of the source this problem refers to, if known.
+    pub span: Option<Span>,
+    /// Another part of the source involved in the problem, such as the first
+    /// use of a duplicated name.
+    pub related_span: Option<Span>,
+    /// A possible fix, such as the chapter file which was probably meant.
+    pub suggestion: Option<PathBuf>,
+}
+
+impl Diagnostic {
+    /// Describe an error from parsing the `src` text.
+    pub fn new(err: &Error, level: Level, src: &str, filename: &str) -> Diagnostic {
+        let mut diagnostic = Diagnostic {
+            file: filename.to_string(),
+            level: level,
+            kind: String::from("other"),
+            message: err.to_string(),
+            line: None,
+            column: None,
+            utf16_column: None,
+            span: None,
+            related_span: None,
+            suggestion: None,
+        };
+
+        match *err.kind() {
+            ErrorKind::ParseError(line, col, ref kind) => {
+                diagnostic.kind = kind.code().to_string();
+                diagnostic.message = kind.to_string();
+                diagnostic.line = Some(line);
+                diagnostic.column = Some(col);
+
+                diagnostic.span = if let ParseErrorKind::UnterminatedLink(span) = *kind {
+                    Some(span)
+                } else if kind.skips_line() {
+                    rest_of_line(src, line, col)
+                } else {
+                    word_at(src, line, col)
+                };
+            }
+            ErrorKind::MissingChapter(_, _, span, ref suggestion) => {
+                diagnostic.kind = String::from("missing-chapter");
+                diagnostic.span = span;
+                diagnostic.suggestion = suggestion.clone();
+
+                if let Some(span) = span {
+                    let (line, column) = span.line_col(src);
+                    diagnostic.line = Some(line);
+                    diagnostic.column = Some(column);
+                }
+            }
+            _ => {}
+        }
+
+        diagnostic
+    }
+
+    /// Serialize this diagnostic as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("A Diagnostic can always be serialized")
+    }
+}
+
+/// Convert a (1-based) column on a (1-based) line of `src` from characters
+/// into UTF-16 code units.
+fn utf16_column(src: &str, line: usize, column: usize) -> Option<usize> {
+    src.split('\n').nth(line.saturating_sub(1)).map(|text| {
+        let before: usize = text.chars().take(column.saturating_sub(1)).map(char::len_utf16).sum();
+        before + 1
+    })
+}
+
+/// Find the span of the word (or run of whitespace, such as an indent)
+/// starting at a (1-based) line and column.
+fn word_at(src: &str, line: usize, col: usize) -> Option<Span> {
+    rest_of_line(src, line, col).map(|span| {
+        let text = &src[span.start..span.end];
+        let len = if text.starts_with(char::is_whitespace) {
+            text.find(|c: char| !c.is_whitespace())
+        } else {
+            text.find(char::is_whitespace)
+        };
+
+        Span {
+            start: span.start,
+            end: span.start + len.unwrap_or_else(|| text.len()),
+        }
+    })
+}
+
+/// Find the span from a (1-based) line and column to the end of that line,
+/// ignoring any trailing whitespace.
+fn rest_of_line(src: &str, line: usize, col: usize) -> Option<Span> {
+    let line_start = if line <= 1 {
+        0
+    } else {
+        match src.match_indices('\n').nth(line - 2) {
+            Some((ix, _)) => ix + 1,
+            None => return None,
+        }
+    };
+    let line_end = src[line_start..]
+        .find('\n')
+        .map(|ix| line_start + ix)
+        .unwrap_or_else(|| src.len());
+    let text = src[line_start..line_end].trim_right();
+
+    text.char_indices()
+        .nth(col.saturating_sub(1))
+        .map(|(ix, _)| Span {
+            start: line_start + ix,
+            end: line_start + text.len(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+
+    #[test]
+    fn diagnostics_include_missing_chapters() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        let path = temp.path().join("SUMMARY.md");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"- [First](first.md)\n- [Second](second.md)\n")
+            .unwrap();
+        File::create(temp.path().join("first.md")).unwrap();
+
+        let cfg = BuildConfig {
+            create_missing: false,
+            ..Default::default()
+        };
+        let got = diagnose_summary(&path, &cfg).unwrap();
+
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].kind, "missing-chapter");
+        assert_eq!(got[0].level, Level::Error);
+        assert_eq!(got[0].line, Some(2));
+
+        let got = diagnose_summary(&path, &BuildConfig::default()).unwrap();
+        assert_eq!(got[0].level, Level::Warning);
+    }
+
+    #[test]
+    fn diagnostics_include_lints() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        let path = temp.path().join("SUMMARY.md");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"- [First](first.txt)\n")
+            .unwrap();
+        File::create(temp.path().join("first.txt")).unwrap();
+
+        let got = diagnose_summary(&path, &BuildConfig::default()).unwrap();
+
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].kind, "non-markdown-destination");
+        assert_eq!(got[0].level, Level::Warning);
+        assert_eq!(got[0].utf16_column, Some(3));
+    }
+
+    #[test]
+    fn columns_can_be_counted_in_utf16() {
+        let src = "- [First](first.md)\n- [\u{1f600} \u{e9}](x.md)";
+
+        assert_eq!(utf16_column(src, 1, 3), Some(3));
+        assert_eq!(utf16_column(src, 2, 7), Some(8));
+        assert_eq!(utf16_column(src, 3, 1), None);
+    }
+
+    #[test]
+    fn skipped_text_is_included_in_diagnostics() {
+        let src = "- [First](first.md)\n- Not a link at all  \n";
+        let err: Error = ErrorKind::ParseError(2, 3, ParseErrorKind::NestedItemNotALink).into();
+
+        let got = Diagnostic::new(&err, Level::Error, src, "SUMMARY.md");
+
+        let span = got.span.unwrap();
+        assert_eq!(&src[span.start..span.end], "Not a link at all");
+        let formatted = format_diagnostic(&err, src, "SUMMARY.md");
+        assert!(formatted.ends_with("2 | - Not a link at all  \n  |   ^^^^^^^^^^^^^^^^^"));
+    }
+
+    #[test]
+    fn parse_errors_become_diagnostics() {
+        let src = "- [First](first.md)\n\t - [Second](second.md)\n";
+        let err: Error = ErrorKind::ParseError(2, 1, ParseErrorKind::MixedIndentation).into();
+
+        let got = Diagnostic::new(&err, Level::Warning, src, "SUMMARY.md");
+
+        assert_eq!(got.kind, "mixed-indentation");
+        assert_eq!(got.level, Level::Warning);
+        assert_eq!(got.line, Some(2));
+        assert_eq!(got.column, Some(1));
+        assert_eq!(got.message, ParseErrorKind::MixedIndentation.to_string());
+        let span = got.span.unwrap();
+        assert_eq!(&src[span.start..span.end], "\t ");
+
+        let json = got.to_json();
+        assert!(json.contains(r#""level":"warning""#));
+        assert!(json.contains(r#""file":"SUMMARY.md""#));
+    }
+
+    #[test]
+    fn missing_chapter_diagnostics_get_a_line_and_column() {
+        let src = "# Summary\n\n- [First](first.md)\n";
+        let span = Span { start: 13, end: 30 };
+        let err: Error = ErrorKind::MissingChapter(
+            String::from("First"),
+            PathBuf::from("first.md"),
+            Some(span),
+            Some(PathBuf::from("fist.md")),
+        ).into();
+
+        let got = Diagnostic::new(&err, Level::Error, src, "SUMMARY.md");
+
+        assert_eq!(got.kind, "missing-chapter");
+        assert_eq!(got.line, Some(3));
+        assert_eq!(got.column, Some(3));
+        assert_eq!(got.span, Some(span));
+        assert_eq!(got.suggestion, Some(PathBuf::from("fist.md")));
+    }
+
+    #[test]
+    fn diagnostics_point_at_the_offending_column() {
+        let src = "- [First](first.md)\n\tbad text";
+        let kind = ParseErrorKind::UnexpectedText(String::from("bad text"));
+        let err: Error = ErrorKind::ParseError(2, 3, kind).into();
+
+        let got = format_diagnostic(&err, src, "src/SUMMARY.md");
+
+        let should_be = "error: Unexpected text after a chapter's link, \"bad text\" \
+                         (check the indentation)\n \
+                         --> src/SUMMARY.md:2:3\n  \
+                         |\n\
+                         2 | \tbad text\n  \
+                         | \t ^^^^^^^";
+        assert_eq!(got, should_be);
+    }
+}
@@ -0,0 +1,240 @@
+//! Generating a summary from the layout of the source directory, for books
+//! without a `SUMMARY.md`.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use super::{assign_section_numbers, Link, SectionNumber, Summary, SummaryItem};
+use book::book::first_heading;
+use errors::*;
+
+
+impl Summary {
+    /// Generate a summary from the markdown files in `src_dir`, for books
+    /// which don't want to maintain a `SUMMARY.md` by hand.
+    ///
+    /// Every file becomes a numbered chapter named after its first heading,
+    /// and directories become chapters whose `README.md` (or `index.md`) is
+    /// their introduction. Entries are sorted by any number at the start of
+    /// their filename (e.g. `02-setup.md`), then alphabetically. Directories
+    /// with their own `book.toml` are left out, since they're sub-books.
+    pub fn generate_from_dir<P: AsRef<Path>>(src_dir: P) -> Result<Summary> {
+        Summary::generate_from_dir_excluding(src_dir, &[])
+    }
+
+    /// Generate a summary like `generate_from_dir()`, skipping the `excluded`
+    /// directories (relative to `src_dir`), such as the book's configured
+    /// sub-books.
+    pub fn generate_from_dir_excluding<P: AsRef<Path>>(
+        src_dir: P,
+        excluded: &[PathBuf],
+    ) -> Result<Summary> {
+        let src_dir = src_dir.as_ref();
+        let excluded: Vec<PathBuf> = excluded.iter().map(|dir| src_dir.join(dir)).collect();
+
+        let canonical = src_dir.canonicalize()
+            .chain_err(|| format!("Unable to find {}", src_dir.display()))?;
+        let mut visited = vec![canonical];
+        let mut numbered_chapters = generate_items(src_dir, src_dir, &excluded, &mut visited)?;
+        assign_section_numbers(&mut numbered_chapters, &SectionNumber::default());
+
+        Ok(Summary {
+            numbered_chapters: numbered_chapters,
+            ..Default::default()
+        })
+    }
+}
+
+/// The files which are used as the introduction for their directory when
+/// generating a summary.
+const DIRECTORY_INDICES: &'static [&'static str] = &["README.md", "index.md"];
+
+/// Generate the items for everything in `dir`. Symlinked directories are
+/// followed, but `visited` remembers where we've been so a symlink pointing
+/// back up the tree can't send us round in circles.
+fn generate_items(
+    src_dir: &Path,
+    dir: &Path,
+    excluded: &[PathBuf],
+    visited: &mut Vec<PathBuf>,
+) -> Result<Vec<SummaryItem>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir).chain_err(|| format!("Unable to read {}", dir.display()))? {
+        entries.push(entry?.path());
+    }
+    entries.sort_by_key(|path| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        (numeric_prefix(&name).unwrap_or(u64::max_value()), name)
+    });
+
+    let mut items = Vec::new();
+
+    for path in entries {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            if path.join("book.toml").exists() || excluded.contains(&path) {
+                continue;
+            }
+            let canonical = path.canonicalize()
+                .chain_err(|| format!("Unable to find {}", path.display()))?;
+            if visited.contains(&canonical) {
+                debug!("[*] Already visited {}, skipping it", path.display());
+                continue;
+            }
+            visited.push(canonical);
+
+            let nested_items = generate_items(src_dir, &path, excluded, visited)?;
+            let index = DIRECTORY_INDICES
+                .iter()
+                .map(|index| path.join(index))
+                .find(|index| index.is_file());
+
+            let mut link = match index {
+                Some(index) => link_for_file(src_dir, &index)?,
+                None if nested_items.is_empty() => continue,
+                None => Link::new(name_from_filename(&name), ""),
+            };
+            link.nested_items = nested_items;
+            items.push(SummaryItem::Link(link));
+        } else {
+            let is_markdown = path.extension().map(|ext| ext == "md").unwrap_or(false);
+            let is_index = dir != src_dir && DIRECTORY_INDICES.iter().any(|index| *index == name);
+
+            if is_markdown && !is_index && name != "SUMMARY.md" {
+                items.push(SummaryItem::Link(link_for_file(src_dir, &path)?));
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+/// Create a `Link` for a markdown file, using its first heading as the name.
+fn link_for_file(src_dir: &Path, path: &Path) -> Result<Link> {
+    let mut content = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut content))
+        .chain_err(|| format!("Couldn't read {}", path.display()))?;
+
+    let name = first_heading(&content).unwrap_or_else(|| {
+        name_from_filename(&path.file_stem().unwrap_or_default().to_string_lossy())
+    });
+
+    let location = path.strip_prefix(src_dir)
+        .expect("Generated chapters are always inside the source directory");
+
+    Ok(Link::new(name, location))
+}
+
+/// The number at the start of a filename like `02-setup.md`, if it has one.
+fn numeric_prefix(name: &str) -> Option<u64> {
+    let digits: String = name.chars().take_while(|c| c.is_digit(10)).collect();
+    digits.parse().ok()
+}
+
+/// Turn a file stem or directory name like `02-getting_started` into
+/// `getting started`.
+fn name_from_filename(name: &str) -> String {
+    let name = name.trim_left_matches(|c: char| c.is_digit(10));
+    let name = name.trim_left_matches(|c: char| c == '-' || c == '_' || c == ' ');
+
+    name.replace(|c: char| c == '-' || c == '_', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use super::super::SummaryBuilder;
+
+    #[test]
+    fn generate_a_summary_from_the_source_directory() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        let files = vec![
+            ("SUMMARY.md", "# Summary\n"),
+            ("10-last.md", "# The Last Chapter\n"),
+            ("2-second.md", "Some text without a heading\n"),
+            ("1-first.md", "\n# First\n\n## Subheading\n"),
+            ("3-third/README.md", "# Third\n"),
+            ("3-third/nested.md", "# Nested\n"),
+            ("4-untitled/nested.md", "# Also Nested\n"),
+            ("notes.txt", "not markdown"),
+        ];
+        for (name, content) in files {
+            let path = temp.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            File::create(path).unwrap().write_all(content.as_bytes()).unwrap();
+        }
+
+        let got = Summary::generate_from_dir(temp.path()).unwrap();
+
+        let should_be = SummaryBuilder::new()
+            .numbered_chapter(Link::new("First", "1-first.md"))
+            .numbered_chapter(Link::new("second", "2-second.md"))
+            .numbered_chapter(Link::new("Third", "3-third/README.md")
+                .with_nested_item(Link::new("Nested", "3-third/nested.md")))
+            .numbered_chapter(Link::new("untitled", "")
+                .with_nested_item(Link::new("Also Nested", "4-untitled/nested.md")))
+            .numbered_chapter(Link::new("The Last Chapter", "10-last.md"))
+            .build();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn comments_in_code_blocks_arent_headings() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        File::create(temp.path().join("setup.md"))
+            .unwrap()
+            .write_all(b"```sh\n# install it\n```\n\n# Getting Set Up\n")
+            .unwrap();
+
+        let got = Summary::generate_from_dir(temp.path()).unwrap();
+
+        let should_be = SummaryBuilder::new()
+            .numbered_chapter(Link::new("Getting Set Up", "setup.md"))
+            .build();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn sub_books_are_left_out() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        for dir in &["configured", "own-config", "chapters"] {
+            fs::create_dir(temp.path().join(dir)).unwrap();
+            File::create(temp.path().join(dir).join("page.md")).unwrap();
+        }
+        File::create(temp.path().join("own-config").join("book.toml")).unwrap();
+
+        let excluded = vec![PathBuf::from("configured")];
+        let got = Summary::generate_from_dir_excluding(temp.path(), &excluded).unwrap();
+
+        let should_be = SummaryBuilder::new()
+            .numbered_chapter(Link::new("chapters", "")
+                .with_nested_item(Link::new("page", "chapters/page.md")))
+            .build();
+        assert_eq!(got, should_be);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_back_up_the_tree_are_only_followed_once() {
+        use std::os::unix::fs::symlink;
+
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        fs::create_dir(temp.path().join("nested")).unwrap();
+        File::create(temp.path().join("nested").join("page.md")).unwrap();
+        symlink(temp.path(), temp.path().join("nested").join("loop")).unwrap();
+
+        let got = Summary::generate_from_dir(temp.path()).unwrap();
+
+        let should_be = SummaryBuilder::new()
+            .numbered_chapter(Link::new("nested", "")
+                .with_nested_item(Link::new("page", "nested/page.md")))
+            .build();
+        assert_eq!(got, should_be);
+    }
+}
@@ -0,0 +1,3671 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::Read;
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use memchr::Memchr;
+use pulldown_cmark::{self, Event, Tag};
+use toml;
+use config::Indentation;
+use utils;
+use errors::*;
+
+mod concrete;
+mod diagnostics;
+mod generate;
+
+pub use self::concrete::{ConcreteSummary, LineContent, ListItem, SummaryLine};
+pub use self::diagnostics::{diagnose_summary, format_diagnostic, Diagnostic, Level};
+
+
+/// Parse the text from a `SUMMARY.md` file into a sort of "recipe" to be
+/// used when loading a book from disk.
+///
+/// # Summary Format
+///
+/// **Title:** It's common practice to begin with a title, generally
+/// "# Summary". It's not mandatory and the parser (currently) ignores it, so
+/// you can too if you feel like it.
+///
+/// **Prefix Chapter:** Before the main numbered chapters you can add a couple
+/// of elements that will not be numbered. This is useful for forewords,
+/// introductions, etc. There are however some constraints. You can not nest
+/// prefix chapters, they should all be on the root level. And you can not add
+/// prefix chapters once you have added numbered chapters.
+///
+/// ```markdown
+/// [Title of prefix element](relative/path/to/markdown.md)
+/// ```
+///
+/// **Numbered Chapter:** Numbered chapters are the main content of the book,
+/// they
+/// will be numbered and can be nested, resulting in a nice hierarchy (chapters,
+/// sub-chapters, etc.)
+///
+/// ```markdown
+/// - [Title of the Chapter](relative/path/to/markdown.md)
+/// ```
+///
+/// You can either use - or * to indicate a numbered chapter, the parser doesn't
+/// care but you'll probably want to stay consistent.
+///
+/// **Suffix Chapter:** After the numbered chapters you can add a couple of
+/// non-numbered chapters. They are the same as prefix chapters but come after
+/// the numbered chapters instead of before.
+///
+/// **Draft Chapter:** A link with an empty destination is a placeholder for a
+/// chapter which hasn't been written yet. It shows up in the table of
+/// contents but no page is generated for it.
+///
+/// ```markdown
+/// - [Coming soon]()
+/// ```
+///
+/// All other elements are unsupported and will be ignored at best or result in
+/// an error.
+pub fn parse_summary(summary: &str) -> Result<Summary> {
+    parse_summary_partial(summary).map_err(|(_, mut errors)| errors.remove(0))
+}
+
+/// Parse a `SUMMARY.md`, holding on to whatever could be parsed even when
+/// errors were encountered.
+///
+/// The parser will skip over anything it doesn't understand and keep going,
+/// so on failure you get back both the partial `Summary` and every error
+/// which was found (in the order they appear in the document).
+pub fn parse_summary_partial(
+    summary: &str,
+) -> ::std::result::Result<Summary, (Summary, Vec<Error>)> {
+    let parsed = parse_summary_with_warnings(summary);
+
+    if parsed.errors.is_empty() {
+        Ok(parsed.summary)
+    } else {
+        Err((parsed.summary, parsed.errors))
+    }
+}
+
+/// Parse a `SUMMARY.md`, keeping track of any warnings (things which are
+/// probably mistakes but don't stop the book from being built) as well as
+/// errors.
+pub fn parse_summary_with_warnings(summary: &str) -> ParsedSummary {
+    parse_summary_with(summary, &ParseOptions::default())
+}
+
+/// Parse a `SUMMARY.md` using the provided `ParseOptions`.
+///
+/// Malformed items are always skipped (and logged) so parsing can carry on,
+/// it's up to the caller whether any errors stop the build.
+pub fn parse_summary_with(summary: &str, options: &ParseOptions) -> ParsedSummary {
+    // a byte order mark would otherwise end up in the first item's text, so
+    // parse what comes after it and shift positions back afterwards
+    let bom = if summary.starts_with('\u{feff}') {
+        '\u{feff}'.len_utf8()
+    } else {
+        0
+    };
+    let summary = &summary[bom..];
+    let parse = |text: &str| {
+        let mut parser = SummaryParser::new(text);
+        parser.indentation = options.indentation;
+        parser.parse()
+    };
+
+    let mut parsed = match split_front_matter(summary) {
+        Some((front_matter, body)) => {
+            let mut parsed = parse(&body);
+            match front_matter {
+                Ok(front_matter) => parsed.summary.front_matter = Some(front_matter),
+                Err(e) => parsed.errors.insert(0, e),
+            }
+            parsed
+        }
+        None => parse(summary),
+    };
+
+    if bom > 0 {
+        skip_first_char(&mut parsed, bom);
+    }
+
+    if options.strict {
+        let warnings = mem::replace(&mut parsed.warnings, Vec::new());
+        parsed.errors.extend(warnings);
+    }
+
+    parsed
+}
+
+/// Move every span and position in `parsed` along to make room for a single
+/// character (`len` bytes long) which was skipped at the very start of the
+/// text.
+fn skip_first_char(parsed: &mut ParsedSummary, len: usize) {
+    let shift = |span: Span| Span {
+        start: span.start + len,
+        end: span.end + len,
+    };
+
+    parsed.summary.walk_mut(|link| link.span = link.span.map(&shift));
+
+    for err in parsed.errors.iter_mut().chain(parsed.warnings.iter_mut()) {
+        let shifted = match *err.kind() {
+            ErrorKind::ParseError(line, column, ref kind) => {
+                let column = if line == 1 { column + 1 } else { column };
+                let kind = match *kind {
+                    ParseErrorKind::UnterminatedLink(span) => {
+                        ParseErrorKind::UnterminatedLink(shift(span))
+                    }
+                    ref other => other.clone(),
+                };
+                Some(ErrorKind::ParseError(line, column, kind).into())
+            }
+            _ => None,
+        };
+
+        if let Some(shifted) = shifted {
+            *err = shifted;
+        }
+    }
+}
+
+/// Options for tweaking how a `SUMMARY.md` is parsed.
+///
+/// The parser accepts `-`, `*` and `+` list markers interchangeably. Making
+/// a book stick to one of them is the job of the `inconsistent-list-markers`
+/// lint (see `lint_summary()`), which knows how seriously to take it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseOptions {
+    /// Treat every warning as an error, useful for making CI builds fail on
+    /// sloppy summaries.
+    pub strict: bool,
+    /// How nested chapters have to be indented. A list item indented any
+    /// other way (including with a mix of tabs and spaces), or nested more
+    /// than one level below the item before it, is an error.
+    pub indentation: Option<Indentation>,
+}
+
+/// The different problems which may be found while parsing a `SUMMARY.md`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// The suffix chapters were followed by a list of numbered chapters.
+    ListAfterSuffixChapters,
+    /// A numbered chapter's list item contained something other than a link.
+    NestedItemNotALink,
+    /// A link which isn't in its own list item, usually because it was
+    /// indented too far.
+    LinkOutsideListItem,
+    /// An unindented link straight after the numbered chapters (without a
+    /// blank line), as if it were a prefix chapter.
+    UnnumberedLinkAfterList,
+    /// Text found after a chapter's link.
+    UnexpectedText(String),
+    /// A list item indented with both tabs and spaces.
+    MixedIndentation,
+    /// A list item indented with tabs when earlier items used spaces, or vice
+    /// versa.
+    InconsistentIndentation {
+        /// Whether this item was indented with tabs.
+        uses_tabs: bool,
+    },
+    /// A list item which isn't indented the way `ParseOptions::indentation`
+    /// says it should be.
+    WrongIndentation(Indentation),
+    /// A list item nested more than one level below the item before it.
+    IndentedTooFar,
+    /// An ordered list which doesn't start at the next chapter number.
+    OrderedListOutOfSequence {
+        /// The number the list starts at.
+        start: usize,
+        /// The number the first chapter in the list will actually get.
+        expected: usize,
+    },
+    /// A chapter link with no name (e.g. `[](chapter.md)`).
+    EmptyChapterName,
+    /// A link which was never closed, such as `[Title](path` or `[Title`.
+    UnterminatedLink(Span),
+}
+
+impl ParseErrorKind {
+    /// Does the parser skip the rest of the line after this error, so the
+    /// whole thing should be pointed out to the user?
+    pub fn skips_line(&self) -> bool {
+        match *self {
+            ParseErrorKind::NestedItemNotALink |
+            ParseErrorKind::LinkOutsideListItem |
+            ParseErrorKind::UnexpectedText(_) |
+            ParseErrorKind::UnnumberedLinkAfterList |
+            ParseErrorKind::UnterminatedLink(_) => true,
+            _ => false,
+        }
+    }
+
+    /// A short, stable identifier for this kind of problem, suitable for
+    /// tools to match on.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ParseErrorKind::ListAfterSuffixChapters => "list-after-suffix-chapters",
+            ParseErrorKind::NestedItemNotALink => "nested-item-not-a-link",
+            ParseErrorKind::LinkOutsideListItem => "link-outside-list-item",
+            ParseErrorKind::UnnumberedLinkAfterList => "unnumbered-link-after-list",
+            ParseErrorKind::UnexpectedText(_) => "unexpected-text",
+            ParseErrorKind::MixedIndentation => "mixed-indentation",
+            ParseErrorKind::InconsistentIndentation { .. } => "inconsistent-indentation",
+            ParseErrorKind::WrongIndentation(_) => "wrong-indentation",
+            ParseErrorKind::IndentedTooFar => "indented-too-far",
+            ParseErrorKind::OrderedListOutOfSequence { .. } => "ordered-list-out-of-sequence",
+            ParseErrorKind::EmptyChapterName => "empty-chapter-name",
+            ParseErrorKind::UnterminatedLink(_) => "unterminated-link",
+        }
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            ParseErrorKind::ListAfterSuffixChapters => {
+                write!(f, "Suffix chapters cannot be followed by a list")
+            }
+            ParseErrorKind::NestedItemNotALink => write!(
+                f,
+                "The link items for nested chapters must only contain a hyperlink"
+            ),
+            ParseErrorKind::LinkOutsideListItem => write!(
+                f,
+                "Found a link which isn't part of a list item, check the indentation"
+            ),
+            ParseErrorKind::UnnumberedLinkAfterList => write!(
+                f,
+                "Prefix chapters must come before the numbered chapters. Add a blank line \
+                 before this link to make it a suffix chapter (e.g. an appendix), or move it \
+                 above the list"
+            ),
+            ParseErrorKind::UnexpectedText(ref text) => write!(
+                f,
+                "Unexpected text after a chapter's link, {:?} (check the indentation)",
+                text
+            ),
+            ParseErrorKind::MixedIndentation => {
+                write!(f, "This list item is indented with a mix of tabs and spaces")
+            }
+            ParseErrorKind::InconsistentIndentation { uses_tabs: true } => write!(
+                f,
+                "This list item is indented with tabs, but earlier items use spaces"
+            ),
+            ParseErrorKind::InconsistentIndentation { uses_tabs: false } => write!(
+                f,
+                "This list item is indented with spaces, but earlier items use tabs"
+            ),
+            ParseErrorKind::WrongIndentation(indentation) => write!(
+                f,
+                "This list item should be indented by {} for each level of nesting",
+                indentation
+            ),
+            ParseErrorKind::IndentedTooFar => write!(
+                f,
+                "This list item is nested more than one level below the item before it"
+            ),
+            ParseErrorKind::OrderedListOutOfSequence { start, expected } => write!(
+                f,
+                "An ordered list of chapters starts at {} but will be numbered from {}",
+                start,
+                expected
+            ),
+            ParseErrorKind::EmptyChapterName => write!(f, "This chapter's link has an empty name"),
+            ParseErrorKind::UnterminatedLink(_) => {
+                write!(f, "This link is missing its closing `]` or `)`")
+            }
+        }
+    }
+}
+
+/// Book-level metadata given in a TOML front matter block at the very top of
+/// `SUMMARY.md`.
+///
+/// ```markdown
+/// +++
+/// title = "My Book"
+/// authors = ["Jane Doe"]
+/// language = "en"
+/// +++
+///
+/// # Summary
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrontMatter {
+    /// The book's title.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The book's authors.
+    pub authors: Vec<String>,
+    /// The language the book is written in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+/// If the summary starts with a `+++` delimited front matter block, parse it
+/// and return the rest of the text with the front matter blanked out (so
+/// line numbers and spans still match the original).
+fn split_front_matter(src: &str) -> Option<(Result<FrontMatter>, String)> {
+    let (toml_src, body_start) = match utils::front_matter(src) {
+        Some(block) => block,
+        None => return None,
+    };
+
+    let front_matter = toml::from_str(toml_src).chain_err(|| "Invalid front matter in SUMMARY.md");
+
+    let mut blanked = String::with_capacity(src.len());
+    for c in src[..body_start].chars() {
+        if c == '\n' {
+            blanked.push('\n');
+        } else {
+            blanked.extend((0..c.len_utf8()).map(|_| ' '));
+        }
+    }
+    blanked.push_str(&src[body_start..]);
+
+    Some((front_matter, blanked))
+}
+
+/// Everything found while parsing a `SUMMARY.md`.
+#[derive(Debug)]
+pub struct ParsedSummary {
+    /// As much of the `Summary` as could be parsed.
+    pub summary: Summary,
+    /// Problems which mean the `Summary` is incomplete or wrong.
+    pub errors: Vec<Error>,
+    /// Recoverable issues, like mixed indentation or an empty chapter name.
+    pub warnings: Vec<Error>,
+}
+
+/// The parsed `SUMMARY.md`, specifying how the book should be laid out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    /// An optional title for the `SUMMARY.md`, currently just ignored.
+    pub title: Option<String>,
+    /// Any book-level metadata from the top of the file.
+    pub front_matter: Option<FrontMatter>,
+    /// Chapters before the main text (e.g. an introduction).
+    pub prefix_chapters: Vec<SummaryItem>,
+    /// The main chapters in the document.
+    pub numbered_chapters: Vec<SummaryItem>,
+    /// Items which come after the main document (e.g. a conclusion).
+    pub suffix_chapters: Vec<SummaryItem>,
+}
+
+impl Summary {
+    /// Read and parse a `SUMMARY.md` file, mentioning the file's path in any
+    /// errors.
+    ///
+    /// A line containing just `{{#include part2/SUMMARY.md}}` is replaced by
+    /// the contents of that file (relative to the one including it),
+    /// indented to match the directive. Links in an included file are still
+    /// relative to the book's `src` directory.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Summary> {
+        Summary::from_path_with(path, &ParseOptions::default())
+    }
+
+    /// Read and parse a `SUMMARY.md` file like `from_path()`, using the
+    /// provided `ParseOptions`.
+    pub fn from_path_with<P: AsRef<Path>>(path: P, options: &ParseOptions) -> Result<Summary> {
+        let path = path.as_ref();
+        let (content, map) = read_summary_with_map(path)?;
+
+        Summary::from_source(path, &content, &map, options)
+    }
+
+    /// Parse a `SUMMARY.md` which has already been read (along with its
+    /// includes) by `read_summary_with_map()`, for callers which need the
+    /// text as well as the `Summary`. The `path` is only used when reporting
+    /// errors.
+    pub fn from_source<P: AsRef<Path>>(
+        path: P,
+        content: &str,
+        map: &SourceMap,
+        options: &ParseOptions,
+    ) -> Result<Summary> {
+        let path = path.as_ref();
+        let mut parsed = parse_summary_with(content, options);
+        if parsed.errors.is_empty() {
+            return Ok(parsed.summary);
+        }
+
+        let e = parsed.errors.remove(0);
+        let (e, diagnostic) = match map.remap_error(&e) {
+            Some((remapped, file, text)) => {
+                let diagnostic = format_diagnostic(&remapped, text, &file.display().to_string());
+                (remapped, diagnostic)
+            }
+            None => {
+                let diagnostic = format_diagnostic(&e, content, &path.display().to_string());
+                (e, diagnostic)
+            }
+        };
+        Err(Error::with_chain(e, diagnostic))
+    }
+
+    /// Get a depth-first iterator over every `Link` in the summary (prefix,
+    /// numbered, then suffix chapters), along with how deeply it is nested.
+    /// Top-level chapters have a depth of `0`.
+    pub fn iter(&self) -> Links {
+        let items = self.prefix_chapters
+            .iter()
+            .chain(self.numbered_chapters.iter())
+            .chain(self.suffix_chapters.iter())
+            .map(|item| (item, 0))
+            .collect();
+
+        Links { items: items }
+    }
+
+    /// Check the summary for mistakes the parser can't catch by itself, such
+    /// as two chapters pointing at the same file (which breaks the
+    /// previous/next chapter navigation).
+    ///
+    /// Links to an anchor within another chapter's file (`guide.md#setup`)
+    /// are fine, and `./guide.md` is treated as the same file as `guide.md`.
+    pub fn validate(&self) -> Result<()> {
+        let mut seen: Vec<(PathBuf, &Link)> = Vec::new();
+
+        for (link, _) in self.iter() {
+            if link.is_draft() || link.fragment.is_some() {
+                continue;
+            }
+
+            let location = utils::fs::collapse_path(&link.location);
+            if let Some(&(_, first)) = seen.iter().find(|&&(ref other, _)| *other == location) {
+                bail!(
+                    "\"{}\" and \"{}\" both point at the same file, {}",
+                    first.name,
+                    link.name,
+                    link.location.display()
+                );
+            }
+
+            seen.push((location, link));
+        }
+
+        Ok(())
+    }
+
+    /// Make sure no chapter is nested more than `max_depth` levels deep (a
+    /// `max_depth` of `2` allows chapter `1.2` but not `1.2.3`). Very deep
+    /// nesting is usually an indentation mistake.
+    pub fn check_depth(&self, max_depth: usize) -> Result<()> {
+        for (link, depth) in self.iter() {
+            if depth + 1 > max_depth {
+                bail!(
+                    "\"{}\" is nested {} levels deep, but only {} are allowed",
+                    link.name,
+                    depth + 1,
+                    max_depth
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Make sure every non-draft chapter's file exists in the `src_dir`,
+    /// returning an `ErrorKind::MissingChapter` for each one that doesn't.
+    pub fn validate_destinations<P: AsRef<Path>>(
+        &self,
+        src_dir: P,
+    ) -> ::std::result::Result<(), Vec<Error>> {
+        let src_dir = src_dir.as_ref();
+
+        let mut errors: Vec<Error> = Vec::new();
+        let mut existing_files = None;
+
+        for (link, _) in self.iter() {
+            if !link.is_draft() && !src_dir.join(&link.location).is_file() {
+                // only bother scanning the source directory if something's wrong
+                let existing_files = existing_files.get_or_insert_with(|| {
+                    let mut files = Vec::new();
+                    find_markdown_files(src_dir, src_dir, &mut files);
+                    files
+                });
+
+                let name = link.name.clone();
+                let location = link.location.clone();
+                let suggestion = closest_match(&link.location, existing_files);
+                let kind = ErrorKind::MissingChapter(name, location, link.span, suggestion);
+                errors.push(kind.into());
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Find every markdown file in `src_dir` which isn't used by any chapter,
+    /// sorted by path. Files called `SUMMARY.md` are never counted as
+    /// orphans, and neither is anything in a directory with its own
+    /// `book.toml`, since that belongs to a sub-book.
+    pub fn find_orphans<P: AsRef<Path>>(&self, src_dir: P) -> Vec<PathBuf> {
+        self.find_orphans_excluding(src_dir, &[])
+    }
+
+    /// Find orphaned files like `find_orphans()`, skipping the `excluded`
+    /// directories (relative to `src_dir`), such as the book's configured
+    /// sub-books.
+    pub fn find_orphans_excluding<P: AsRef<Path>>(
+        &self,
+        src_dir: P,
+        excluded: &[PathBuf],
+    ) -> Vec<PathBuf> {
+        let src_dir = src_dir.as_ref();
+        let excluded: Vec<PathBuf> = excluded.iter().map(|dir| src_dir.join(dir)).collect();
+
+        let mut files = Vec::new();
+        let mut visited = Vec::new();
+        walk_markdown_files(src_dir, src_dir, &excluded, &mut files, &mut visited);
+
+        let used: Vec<&Path> = self.iter()
+            .map(|(link, _)| {
+                let location = link.location.as_path();
+                location.strip_prefix("./").unwrap_or(location)
+            })
+            .collect();
+
+        let mut orphans: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|file| file.file_name().map(|name| name != "SUMMARY.md").unwrap_or(true))
+            .filter(|file| !used.contains(&file.as_path()))
+            .collect();
+        orphans.sort();
+
+        orphans
+    }
+
+    /// Find the chapter whose link in the `SUMMARY.md` source covers the byte
+    /// at `offset` (e.g. the chapter under an editor's cursor).
+    ///
+    /// Only links have spans, so this never finds separators or part titles.
+    pub fn item_at_offset(&self, offset: usize) -> Option<&Link> {
+        self.iter().map(|(link, _)| link).find(|link| match link.span {
+            Some(span) => span.start <= offset && offset < span.end,
+            None => false,
+        })
+    }
+
+    /// Find the chapter whose source file is at `location` (relative to the
+    /// book's `src` directory).
+    pub fn get_by_path<P: AsRef<Path>>(&self, location: P) -> Option<&Link> {
+        let location = location.as_ref();
+        self.iter()
+            .map(|(link, _)| link)
+            .find(|link| link.location == location)
+    }
+
+    /// Find the numbered chapter with this section number.
+    pub fn get_by_number(&self, number: &SectionNumber) -> Option<&Link> {
+        self.iter()
+            .map(|(link, _)| link)
+            .find(|link| link.number.as_ref() == Some(number))
+    }
+
+    /// Visit every `Link` in the summary, in the same order as `iter()`,
+    /// letting you modify it in place (e.g. to rewrite its location).
+    ///
+    /// A link is always visited before its nested items.
+    pub fn walk_mut<F: FnMut(&mut Link)>(&mut self, mut f: F) {
+        walk_items_mut(&mut self.prefix_chapters, &mut f);
+        walk_items_mut(&mut self.numbered_chapters, &mut f);
+        walk_items_mut(&mut self.suffix_chapters, &mut f);
+    }
+}
+
+/// Recursively find every markdown file in `dir`, relative to `src_dir`.
+fn find_markdown_files(src_dir: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut visited = Vec::new();
+    walk_markdown_files(src_dir, dir, &[], files, &mut visited);
+}
+
+/// The guts of `find_markdown_files()`. Symlinked directories are followed,
+/// but `visited` remembers where we've been so a symlink pointing back up
+/// the tree can't send us round in circles. Directories in `excluded` (or
+/// with their own `book.toml`) are skipped.
+fn walk_markdown_files(
+    src_dir: &Path,
+    dir: &Path,
+    excluded: &[PathBuf],
+    files: &mut Vec<PathBuf>,
+    visited: &mut Vec<PathBuf>,
+) {
+    if let Ok(canonical) = dir.canonicalize() {
+        if visited.contains(&canonical) {
+            debug!("[*] Already visited {}, skipping it", dir.display());
+            return;
+        }
+        visited.push(canonical);
+    }
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+
+        if path.is_dir() {
+            if !path.join("book.toml").exists() && !excluded.contains(&path) {
+                walk_markdown_files(src_dir, &path, excluded, files, visited);
+            }
+        } else if path.extension().map(|ext| ext == "md").unwrap_or(false) {
+            if let Ok(relative) = path.strip_prefix(src_dir) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+}
+
+/// Find the file whose path is closest to the `missing` one, as long as it's
+/// close enough to plausibly be a typo.
+fn closest_match(missing: &Path, candidates: &[PathBuf]) -> Option<PathBuf> {
+    let missing = missing.to_string_lossy();
+    let missing = missing.trim_left_matches("./");
+    let max_distance = cmp::max(missing.chars().count() / 3, 1);
+
+    candidates
+        .iter()
+        .map(|candidate| {
+            let distance = utils::edit_distance(missing, &candidate.to_string_lossy());
+            (distance, candidate)
+        })
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// Read the text of the `SUMMARY.md` at `path`, with every
+/// `{{#include ...}}` directive expanded.
+pub fn read_summary<P: AsRef<Path>>(path: P) -> Result<String> {
+    read_summary_with_map(path).map(|(text, _)| text)
+}
+
+/// Read a `SUMMARY.md` like `read_summary()`, along with a `SourceMap` for
+/// turning positions in the expanded text back into positions in the files
+/// it was put together from.
+pub fn read_summary_with_map<P: AsRef<Path>>(path: P) -> Result<(String, SourceMap)> {
+    read_with_includes(path.as_ref(), &mut Vec::new())
+}
+
+/// Where each line of an expanded summary came from, so problems found in
+/// an included file can be reported against that file instead of the
+/// expanded text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    /// The path and text of every file which went into the summary.
+    files: Vec<(PathBuf, String)>,
+    /// One entry for each line in the expanded text.
+    lines: Vec<MappedLine>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MappedLine {
+    /// Index into `SourceMap::files`.
+    file: usize,
+    /// The (1-based) line number in that file.
+    line: usize,
+    /// The byte offset the line starts at in that file.
+    start: usize,
+    /// The byte offset the line starts at in the expanded text.
+    expanded_start: usize,
+    /// How many bytes of indentation were added in front of the line when
+    /// its file was included.
+    indent: usize,
+}
+
+impl SourceMap {
+    /// The path of every file which went into the summary, starting with the
+    /// `SUMMARY.md` itself.
+    pub fn files(&self) -> Vec<&Path> {
+        self.files.iter().map(|&(ref path, _)| path.as_path()).collect()
+    }
+
+    /// A map for a file which was used as-is.
+    fn identity(path: &Path, text: &str) -> SourceMap {
+        let mut map = SourceMap {
+            files: vec![(path.to_path_buf(), text.to_string())],
+            lines: Vec::new(),
+        };
+        let mut start = 0;
+        for (i, line) in text.split('\n').enumerate() {
+            if start == text.len() && i > 0 {
+                break;
+            }
+            map.push_line(0, i + 1, start, start, 0);
+            start += line.len() + 1;
+        }
+
+        map
+    }
+
+    fn push_line(
+        &mut self,
+        file: usize,
+        line: usize,
+        start: usize,
+        expanded: usize,
+        indent: usize,
+    ) {
+        self.lines.push(MappedLine {
+            file: file,
+            line: line,
+            start: start,
+            expanded_start: expanded,
+            indent: indent,
+        });
+    }
+
+    /// Get the file, line and column a (1-based) line and column in the
+    /// expanded text came from.
+    pub fn position(&self, line: usize, column: usize) -> Option<(&Path, usize, usize)> {
+        self.lines.get(line.saturating_sub(1)).map(|mapped| {
+            let column = cmp::max(column.saturating_sub(mapped.indent), 1);
+            (self.files[mapped.file].0.as_path(), mapped.line, column)
+        })
+    }
+
+    /// Get the file a span in the expanded text came from, and the same span
+    /// in that file. Spans running across more than one file are `None`.
+    pub fn span(&self, span: Span) -> Option<(&Path, Span)> {
+        let start = match self.offset(span.start) {
+            Some(start) => start,
+            None => return None,
+        };
+        let end = if span.end > span.start {
+            match self.offset(span.end - 1) {
+                Some((file, last)) => (file, last + 1),
+                None => return None,
+            }
+        } else {
+            start
+        };
+        if start.0 != end.0 {
+            return None;
+        }
+
+        let span = Span {
+            start: start.1,
+            end: end.1,
+        };
+        Some((self.files[start.0].0.as_path(), span))
+    }
+
+    /// Get the text of a file which went into the summary.
+    pub fn text_of(&self, path: &Path) -> Option<&str> {
+        self.files
+            .iter()
+            .find(|&&(ref p, _)| p == path)
+            .map(|&(_, ref text)| text.as_str())
+    }
+
+    fn offset(&self, expanded: usize) -> Option<(usize, usize)> {
+        let ix = match self.lines
+            .binary_search_by_key(&expanded, |mapped| mapped.expanded_start)
+        {
+            Ok(ix) => ix,
+            Err(0) => return None,
+            Err(ix) => ix - 1,
+        };
+        let mapped = &self.lines[ix];
+        let within_line = (expanded - mapped.expanded_start).saturating_sub(mapped.indent);
+
+        Some((mapped.file, mapped.start + within_line))
+    }
+
+    /// Point a `Diagnostic` about the expanded text at the file (and the
+    /// position in it) the problem actually came from.
+    pub fn remap(&self, diagnostic: &mut Diagnostic) {
+        if let (Some(line), Some(column)) = (diagnostic.line, diagnostic.column) {
+            if let Some((file, line, column)) = self.position(line, column) {
+                diagnostic.file = file.display().to_string();
+                diagnostic.line = Some(line);
+                diagnostic.column = Some(column);
+            }
+        }
+
+        let file = diagnostic.file.clone();
+        let in_same_file = |span: Option<Span>| match span.and_then(|span| self.span(span)) {
+            Some((path, span)) if path.display().to_string() == file => Some(span),
+            _ => None,
+        };
+        diagnostic.span = in_same_file(diagnostic.span);
+        diagnostic.related_span = in_same_file(diagnostic.related_span);
+    }
+
+    /// Point an error from parsing the expanded text at the file it came
+    /// from, returning the updated error along with that file's path and
+    /// text (for `format_diagnostic()`).
+    pub fn remap_error(&self, err: &Error) -> Option<(Error, &Path, &str)> {
+        let (line, column, kind) = match *err.kind() {
+            ErrorKind::ParseError(line, column, ref kind) => (line, column, kind.clone()),
+            _ => return None,
+        };
+        let (file, line, column) = match self.position(line, column) {
+            Some(position) => position,
+            None => return None,
+        };
+        let kind = match kind {
+            ParseErrorKind::UnterminatedLink(span) => match self.span(span) {
+                Some((_, span)) => ParseErrorKind::UnterminatedLink(span),
+                None => return None,
+            },
+            other => other,
+        };
+        let text = self.text_of(file).unwrap_or("");
+
+        Some((ErrorKind::ParseError(line, column, kind).into(), file, text))
+    }
+}
+
+/// Turn the raw bytes of a summary file into text, skipping any UTF-8 byte
+/// order mark and giving a useful error for files in other encodings.
+fn decode_summary(path: &Path, mut bytes: Vec<u8>) -> Result<String> {
+    if bytes.starts_with(b"\xff\xfe") || bytes.starts_with(b"\xfe\xff") {
+        let reason = String::from("it looks like UTF-16");
+        bail!(ErrorKind::Encoding(path.to_path_buf(), reason));
+    }
+
+    if bytes.starts_with(b"\xef\xbb\xbf") {
+        bytes.drain(..3);
+    }
+
+    String::from_utf8(bytes).map_err(|e| {
+        let valid = e.utf8_error().valid_up_to();
+        let line = e.into_bytes()[..valid].iter().filter(|&&b| b == b'\n').count() + 1;
+        let reason = format!("found invalid UTF-8 on line {}", line);
+
+        ErrorKind::Encoding(path.to_path_buf(), reason).into()
+    })
+}
+
+/// Read a summary file, recursively expanding any `{{#include ...}}`
+/// directives. The `stack` holds every file currently being included so we
+/// can detect cycles.
+fn read_with_includes(path: &Path, stack: &mut Vec<PathBuf>) -> Result<(String, SourceMap)> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_end(&mut bytes))
+        .chain_err(|| format!("Couldn't read {}", path.display()))?;
+    let content = decode_summary(path, bytes)?;
+
+    if !content.lines().any(|line| include_directive(line).is_some()) {
+        // leave the text untouched so link spans still line up with the file
+        let map = SourceMap::identity(path, &content);
+        return Ok((content, map));
+    }
+
+    let canonical = path.canonicalize()?;
+    if stack.contains(&canonical) {
+        bail!("{} is included by itself", path.display());
+    }
+    stack.push(canonical);
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut expanded = String::with_capacity(content.len());
+    let mut map = SourceMap {
+        files: vec![(path.to_path_buf(), content.clone())],
+        lines: Vec::new(),
+    };
+    let mut start = 0;
+
+    for (i, raw_line) in content.split('\n').enumerate() {
+        if start == content.len() && i > 0 {
+            break;
+        }
+        let line = raw_line.trim_right_matches('\r');
+
+        match include_directive(line) {
+            Some((indent, included)) => {
+                debug!("[*] Including {} in {}", included, path.display());
+                let (sub_summary, sub_map) = read_with_includes(&dir.join(included), stack)
+                    .chain_err(|| format!("Unable to include {}", included))?;
+
+                let first_file = map.files.len();
+                for (sub_line, mapped) in sub_summary.lines().zip(sub_map.lines) {
+                    let added = if sub_line.trim().is_empty() {
+                        0
+                    } else {
+                        expanded.push_str(indent);
+                        indent.len()
+                    };
+                    let expanded_start = expanded.len() - added;
+                    map.push_line(
+                        first_file + mapped.file,
+                        mapped.line,
+                        mapped.start,
+                        expanded_start,
+                        mapped.indent + added,
+                    );
+                    expanded.push_str(sub_line);
+                    expanded.push('\n');
+                }
+                map.files.extend(sub_map.files);
+            }
+            None => {
+                map.push_line(0, i + 1, start, expanded.len(), 0);
+                expanded.push_str(line);
+                expanded.push('\n');
+            }
+        }
+
+        start += raw_line.len() + 1;
+    }
+
+    stack.pop();
+    Ok((expanded, map))
+}
+
+/// If the line is an include directive (`{{#include path/to/SUMMARY.md}}`)
+/// get its indentation and the file it points to.
+fn include_directive(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_left();
+    let indent = &line[..line.len() - trimmed.len()];
+    let trimmed = trimmed.trim_right();
+
+    if trimmed.starts_with("{{#include") && trimmed.ends_with("}}") {
+        let included = trimmed["{{#include".len()..trimmed.len() - 2].trim();
+        if !included.is_empty() {
+            return Some((indent, included));
+        }
+    }
+
+    None
+}
+
+fn walk_items_mut<F: FnMut(&mut Link)>(items: &mut [SummaryItem], f: &mut F) {
+    for item in items {
+        if let SummaryItem::Link(ref mut link) = *item {
+            f(link);
+            walk_items_mut(&mut link.nested_items, f);
+        }
+    }
+}
+
+/// A depth-first iterator over the `Link`s in a `Summary`, skipping any
+/// separators and part titles.
+///
+/// Created by `Summary::iter()`.
+pub struct Links<'a> {
+    items: VecDeque<(&'a SummaryItem, usize)>,
+}
+
+impl<'a> Iterator for Links<'a> {
+    type Item = (&'a Link, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((item, depth)) = self.items.pop_front() {
+            if let SummaryItem::Link(ref link) = *item {
+                for nested in link.nested_items.iter().rev() {
+                    self.items.push_front((nested, depth + 1));
+                }
+
+                return Some((link, depth));
+            }
+        }
+
+        None
+    }
+}
+
+/// A struct representing an entry in the `SUMMARY.md`, possibly with nested
+/// entries.
+///
+/// This is roughly the equivalent of `[Some section](./path/to/file.md)`.
+///
+/// Two links are equal if everything *except* their `span` is equal, that
+/// way a `Summary` that was built by hand or written out and parsed again
+/// still compares equal to the original.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Link {
+    /// The name of the chapter.
+    pub name: String,
+    /// The chapter's name with its inline markdown intact (e.g.
+    /// ``[`unsafe` Rust]``), if it has any formatting. The plain text is
+    /// always in `name`.
+    pub markdown_name: Option<String>,
+    /// The location of the chapter's source file, taking the book's `src`
+    /// directory as the root.
+    pub location: PathBuf,
+    /// The anchor at the end of the link's destination (e.g. `setup` in
+    /// `chapter_1.md#setup`), kept separate so it doesn't get mixed up with
+    /// the source file's path.
+    pub fragment: Option<String>,
+    /// The optional link title (e.g. `[Intro](intro.md "The introduction")`),
+    /// which renderers may use as a tooltip or description.
+    pub title: Option<String>,
+    /// The section number, if this chapter is in the numbered section.
+    pub number: Option<SectionNumber>,
+    /// Any nested items this chapter may contain.
+    pub nested_items: Vec<SummaryItem>,
+    /// Extra attributes given after the link, (e.g. `{hidden, class=advanced}`)
+    /// for themes and preprocessors to use. Bare flags get the value `"true"`.
+    pub attributes: HashMap<String, String>,
+    /// Where the link was found in the `SUMMARY.md` source, if it was parsed
+    /// from one.
+    pub span: Option<Span>,
+}
+
+impl Link {
+    /// Create a new link with no nested items.
+    pub fn new<S: Into<String>, P: AsRef<Path>>(name: S, location: P) -> Link {
+        Link {
+            name: name.into(),
+            location: location.as_ref().to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a nested item to this link, returning the updated link so calls can
+    /// be chained.
+    pub fn with_nested_item<I: Into<SummaryItem>>(mut self, item: I) -> Link {
+        self.nested_items.push(item.into());
+        self
+    }
+
+    /// Is this a draft chapter (e.g. `[Coming soon]()`) which doesn't point
+    /// at a file yet?
+    pub fn is_draft(&self) -> bool {
+        self.location.as_os_str().is_empty()
+    }
+
+    /// Does this chapter contain any nested chapters?
+    pub fn has_nested_items(&self) -> bool {
+        self.nested_items.iter().any(|item| item.is_link())
+    }
+
+    /// How deeply nested this chapter is, going by its section number.
+    /// Top-level and unnumbered chapters have a depth of `0`, just like in
+    /// `Summary::iter()`.
+    pub fn depth(&self) -> usize {
+        match self.number {
+            Some(ref number) if !number.is_empty() => number.len() - 1,
+            _ => 0,
+        }
+    }
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Link {
+            name: String::new(),
+            markdown_name: None,
+            location: PathBuf::new(),
+            fragment: None,
+            title: None,
+            number: None,
+            nested_items: Vec::new(),
+            attributes: HashMap::new(),
+            span: None,
+        }
+    }
+}
+
+impl PartialEq for Link {
+    fn eq(&self, other: &Link) -> bool {
+        self.name == other.name && self.markdown_name == other.markdown_name &&
+            self.location == other.location &&
+            self.fragment == other.fragment && self.title == other.title &&
+            self.number == other.number && self.nested_items == other.nested_items &&
+            self.attributes == other.attributes
+    }
+}
+
+impl Display for Link {
+    /// Write the link the way it would appear in a `SUMMARY.md`, ignoring
+    /// any nested items.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", link_to_markdown(self))
+    }
+}
+
+/// A range of bytes in the `SUMMARY.md` source, from `start` up to (but not
+/// including) `end`.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Span {
+    /// The byte offset the item starts at.
+    pub start: usize,
+    /// The byte offset just past the end of the item.
+    pub end: usize,
+}
+
+impl Span {
+    /// Get the (1-based) line and column this span starts at in `src`.
+    pub fn line_col(&self, src: &str) -> (usize, usize) {
+        let before = &src[..cmp::min(self.start, src.len())];
+        let line_start = before.rfind('\n').map(|ix| ix + 1).unwrap_or(0);
+
+        (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+    }
+}
+
+/// An item in `SUMMARY.md` which could be either a separator, a part title
+/// or a `Link`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SummaryItem {
+    /// A link to a chapter.
+    Link(Link),
+    /// A separator (`---`).
+    Separator,
+    /// A header (e.g. `# Part I`) used to split the numbered chapters into
+    /// named parts.
+    PartTitle(String),
+}
+
+impl SummaryItem {
+    fn is_link(&self) -> bool {
+        match *self {
+            SummaryItem::Link(_) => true,
+            _ => false,
+        }
+    }
+
+    fn maybe_link_mut(&mut self) -> Option<&mut Link> {
+        match *self {
+            SummaryItem::Link(ref mut l) => Some(l),
+            _ => None,
+        }
+    }
+}
+
+impl From<Link> for SummaryItem {
+    fn from(other: Link) -> SummaryItem {
+        SummaryItem::Link(other)
+    }
+}
+
+/// A helper for constructing a `Summary` programmatically instead of writing
+/// the markdown by hand.
+///
+/// Section numbers are assigned to the numbered chapters (and their nested
+/// items) when the `Summary` is built, so you don't need to set them
+/// yourself.
+///
+/// ```rust
+/// # use mdbook::book::{Link, SummaryBuilder};
+/// let summary = SummaryBuilder::new()
+///     .title("My Book")
+///     .prefix_chapter(Link::new("Introduction", "intro.md"))
+///     .numbered_chapter(Link::new("First", "first/index.md")
+///         .with_nested_item(Link::new("Nested", "first/nested.md")))
+///     .separator()
+///     .numbered_chapter(Link::new("Second", "second.md"))
+///     .suffix_chapter(Link::new("Conclusion", "conclusion.md"))
+///     .build();
+///
+/// assert_eq!(summary.numbered_chapters.len(), 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SummaryBuilder {
+    summary: Summary,
+    section: Section,
+}
+
+/// Which part of the `Summary` a `SummaryBuilder` is currently adding to.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Section {
+    Prefix,
+    Numbered,
+    Suffix,
+}
+
+impl Default for Section {
+    fn default() -> Section {
+        Section::Prefix
+    }
+}
+
+impl SummaryBuilder {
+    /// Create a new, empty `SummaryBuilder`.
+    pub fn new() -> SummaryBuilder {
+        SummaryBuilder::default()
+    }
+
+    /// Set the summary's title.
+    pub fn title<S: Into<String>>(&mut self, title: S) -> &mut SummaryBuilder {
+        self.summary.title = Some(title.into());
+        self
+    }
+
+    /// Add a chapter which comes before the numbered chapters.
+    pub fn prefix_chapter(&mut self, link: Link) -> &mut SummaryBuilder {
+        self.push(Section::Prefix, SummaryItem::Link(link))
+    }
+
+    /// Add a numbered chapter, along with any nested items it contains.
+    pub fn numbered_chapter(&mut self, link: Link) -> &mut SummaryBuilder {
+        self.push(Section::Numbered, SummaryItem::Link(link))
+    }
+
+    /// Start a new part within the numbered chapters.
+    pub fn part_title<S: Into<String>>(&mut self, title: S) -> &mut SummaryBuilder {
+        self.push(Section::Numbered, SummaryItem::PartTitle(title.into()))
+    }
+
+    /// Add a chapter which comes after the numbered chapters.
+    pub fn suffix_chapter(&mut self, link: Link) -> &mut SummaryBuilder {
+        self.push(Section::Suffix, SummaryItem::Link(link))
+    }
+
+    /// Add a separator to whichever section was added to most recently.
+    pub fn separator(&mut self) -> &mut SummaryBuilder {
+        let section = self.section;
+        self.push(section, SummaryItem::Separator)
+    }
+
+    fn push(&mut self, section: Section, item: SummaryItem) -> &mut SummaryBuilder {
+        self.section = section;
+
+        match section {
+            Section::Prefix => self.summary.prefix_chapters.push(item),
+            Section::Numbered => self.summary.numbered_chapters.push(item),
+            Section::Suffix => self.summary.suffix_chapters.push(item),
+        }
+
+        self
+    }
+
+    /// Create the `Summary`, assigning section numbers to all the numbered
+    /// chapters.
+    pub fn build(&self) -> Summary {
+        let mut summary = self.summary.clone();
+
+        for item in &mut summary.prefix_chapters {
+            clear_section_numbers(item);
+        }
+        for item in &mut summary.suffix_chapters {
+            clear_section_numbers(item);
+        }
+        assign_section_numbers(&mut summary.numbered_chapters, &SectionNumber::default());
+
+        summary
+    }
+}
+
+fn clear_section_numbers(item: &mut SummaryItem) {
+    if let SummaryItem::Link(ref mut link) = *item {
+        link.number = None;
+
+        for nested in &mut link.nested_items {
+            clear_section_numbers(nested);
+        }
+    }
+}
+
+fn assign_section_numbers(items: &mut [SummaryItem], parent: &SectionNumber) {
+    number_items_from(items, parent, 0);
+}
+
+/// Number `items` as if they came after `already_numbered` siblings.
+fn number_items_from(items: &mut [SummaryItem], parent: &SectionNumber, already_numbered: u32) {
+    let mut count = already_numbered;
+
+    for item in items {
+        if let SummaryItem::Link(ref mut link) = *item {
+            count += 1;
+            let mut number = parent.clone();
+            number.push(count);
+
+            assign_section_numbers(&mut link.nested_items, &number);
+            link.number = Some(number);
+        }
+    }
+}
+
+/// Writes the `Summary` back out as canonical `SUMMARY.md` markdown, which
+/// will parse back into the same `Summary`.
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut blocks = Vec::new();
+
+        if let Some(ref front_matter) = self.front_matter {
+            let front_matter = toml::to_string(front_matter).map_err(|_| fmt::Error)?;
+            blocks.push(format!("+++\n{}+++", front_matter));
+        }
+
+        if let Some(ref title) = self.title {
+            blocks.push(format!("# {}", title));
+        }
+
+        affix_blocks(&self.prefix_chapters, &mut blocks);
+        numbered_blocks(&self.numbered_chapters, &mut blocks);
+        affix_blocks(&self.suffix_chapters, &mut blocks);
+
+        writeln!(f, "{}", blocks.join("\n\n"))
+    }
+}
+
+/// Each affix item gets its own paragraph.
+fn affix_blocks(items: &[SummaryItem], blocks: &mut Vec<String>) {
+    for item in items {
+        match *item {
+            SummaryItem::Link(ref link) => blocks.push(link_to_markdown(link)),
+            SummaryItem::Separator => blocks.push(String::from("---")),
+            SummaryItem::PartTitle(ref title) => blocks.push(format!("# {}", title)),
+        }
+    }
+}
+
+/// Consecutive numbered chapters are written as a single (nested) list,
+/// broken up by any separators or part titles.
+fn numbered_blocks(items: &[SummaryItem], blocks: &mut Vec<String>) {
+    let mut lines = Vec::new();
+
+    for item in items {
+        match *item {
+            SummaryItem::Link(ref link) => list_item_lines(link, 0, &mut lines),
+            SummaryItem::Separator => {
+                flush_list(&mut lines, blocks);
+                blocks.push(String::from("---"));
+            }
+            SummaryItem::PartTitle(ref title) => {
+                flush_list(&mut lines, blocks);
+                blocks.push(format!("# {}", title));
+            }
+        }
+    }
+
+    flush_list(&mut lines, blocks);
+}
+
+fn flush_list(lines: &mut Vec<String>, blocks: &mut Vec<String>) {
+    if !lines.is_empty() {
+        blocks.push(lines.join("\n"));
+        lines.clear();
+    }
+}
+
+fn list_item_lines(link: &Link, depth: usize, lines: &mut Vec<String>) {
+    let indent = "    ".repeat(depth);
+    lines.push(format!("{}- {}", indent, link_to_markdown(link)));
+
+    for nested in &link.nested_items {
+        // separators and part titles can't be represented inside a list
+        if let SummaryItem::Link(ref nested) = *nested {
+            list_item_lines(nested, depth + 1, lines);
+        }
+    }
+}
+
+fn link_to_markdown(link: &Link) -> String {
+    let mut destination = percent_encode(&link.location.display().to_string());
+    if let Some(ref fragment) = link.fragment {
+        destination.push('#');
+        destination.push_str(fragment);
+    }
+
+    let name = match link.markdown_name {
+        Some(ref markdown_name) => markdown_name.clone(),
+        None => escape_markdown(&link.name),
+    };
+
+    let mut markdown = match link.title {
+        Some(ref title) => format!(
+            "[{}]({} \"{}\")",
+            name,
+            destination,
+            title.replace('\\', "\\\\").replace('"', "\\\"")
+        ),
+        None => format!("[{}]({})", name, destination),
+    };
+
+    if !link.attributes.is_empty() {
+        let mut attributes: Vec<_> = link.attributes
+            .iter()
+            .map(|(key, value)| if value == "true" {
+                key.clone()
+            } else {
+                format!("{}={}", key, value)
+            })
+            .collect();
+        attributes.sort();
+
+        markdown.push_str(&format!(" {{{}}}", attributes.join(", ")));
+    }
+
+    markdown
+}
+
+/// Decode any `%20`-style escapes in a link's destination, leaving it as is
+/// if the result wouldn't be valid UTF-8.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = ::std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8(decoded).unwrap_or_else(|_| text.to_string())
+}
+
+/// Escape the characters which can't appear in a link's destination, the
+/// opposite of `percent_decode()`.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            ' ' | '%' | '(' | ')' | '<' | '>' | '#' => {
+                encoded.push_str(&format!("%{:02X}", ch as u32));
+            }
+            _ => encoded.push(ch),
+        }
+    }
+
+    encoded
+}
+
+/// Backslash-escape anything which would otherwise be interpreted as
+/// markdown syntax.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '\\' | '[' | ']' | '*' | '_' | '`' | '<' => escaped.push('\\'),
+            _ => {}
+        }
+        escaped.push(ch);
+    }
+
+    escaped
+}
+
+/// A recursive descent (-ish) parser for a `SUMMARY.md`.
+///
+///
+/// # Grammar
+///
+/// The `SUMMARY.md` file has a grammar which looks something like this:
+///
+/// ```text
+/// summary           ::= title prefix_chapters numbered_chapters
+/// suffix_chapters
+/// title             ::= "# " TEXT
+///                     | EPSILON
+/// prefix_chapters   ::= item*
+/// suffix_chapters   ::= item*
+/// numbered_chapters ::= part+
+/// part              ::= part_title? dotted_item+
+/// part_title        ::= "#"+ " " TEXT
+/// dotted_item       ::= INDENT* DOT_POINT item
+/// item              ::= link
+///                     | separator
+/// separator         ::= "---"
+/// link              ::= "[" TEXT "]" "(" TEXT ")"
+/// DOT_POINT         ::= "-"
+///                     | "*"
+/// ```
+///
+/// > **Note:** the `TEXT` terminal is "normal" text, and should (roughly)
+/// > match the following regex: "[^<>\n[]]+".
+struct SummaryParser<'a> {
+    src: &'a str,
+    stream: pulldown_cmark::Parser<'a>,
+    /// Events which were read by one parsing step but belong to the next
+    /// one, so they get handed out again by `next_event()` (most recently
+    /// put back first).
+    back: Vec<Event<'a>>,
+    /// Any errors encountered so far.
+    errors: Vec<Error>,
+    /// Any warnings encountered so far.
+    warnings: Vec<Error>,
+    /// The byte offset each line starts at, so we can quickly turn an offset
+    /// into a line and column.
+    line_starts: Vec<usize>,
+    /// How nested items have to be indented, if the caller cares.
+    indentation: Option<Indentation>,
+}
+
+/// Reads `Events` from the provided stream until the corresponding
+/// `Event::End` is encountered which matches the `$delimiter` pattern.
+///
+/// This is the equivalent of doing
+/// `$stream.take_while(|e| e != $delimeter).collect()` but it allows you to
+/// use pattern matching and you won't get errors because `take_while()`
+/// moves `$stream` out of self.
+macro_rules! collect_events {
+    ($stream:expr, start $delimiter:pat) => {
+        collect_events!($stream, Event::Start($delimiter))
+    };
+    ($stream:expr, end $delimiter:pat) => {
+        collect_events!($stream, Event::End($delimiter))
+    };
+    ($stream:expr, $delimiter:pat) => {
+        {
+            let mut events = Vec::new();
+
+            loop {
+                let event = $stream.next();
+                trace!("Next event: {:?}", event);
+
+                match event {
+                    Some($delimiter) => break,
+                    Some(other) => events.push(other),
+                    None => {
+                        debug!("Reached end of stream without finding the closing pattern, {}", stringify!($delimiter));
+                        break;
+                    }
+                }
+            }
+
+            events
+        }
+    }
+}
+
+impl<'a> SummaryParser<'a> {
+    fn new(text: &str) -> SummaryParser {
+        let pulldown_parser = pulldown_cmark::Parser::new(text);
+        let line_starts = ::std::iter::once(0)
+            .chain(Memchr::new(b'\n', text.as_bytes()).map(|ix| ix + 1))
+            .collect();
+
+        SummaryParser {
+            src: text,
+            stream: pulldown_parser,
+            back: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            line_starts: line_starts,
+            indentation: None,
+        }
+    }
+
+    /// Get the current line and column to give the user more useful error 
+    /// messages.
+    fn current_location(&self) -> (usize, usize) {
+        self.to_linecol(self.stream.get_offset())
+    }
+
+    /// Convert a byte offset into a (1-based) line and column number.
+    fn to_linecol(&self, byte_offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&byte_offset) {
+            Ok(ix) => ix,
+            Err(ix) => ix - 1,
+        };
+        let start_of_line = self.line_starts[line];
+        let col = self.src[start_of_line..byte_offset].chars().count();
+
+        (line + 1, col + 1)
+    }
+
+    /// Parse the text the `SummaryParser` was created with.
+    ///
+    /// Errors are recorded as they are found and parsing resumes at the next
+    /// item, so the returned `Summary` is always as complete as possible.
+    fn parse(mut self) -> ParsedSummary {
+        self.check_lines();
+        let title = self.parse_title();
+
+        let prefix_chapters = self.parse_affix(true)
+            .chain_err(|| "There was an error parsing the prefix chapters");
+        let prefix_chapters = self.recover(prefix_chapters);
+        let numbered_chapters = self.parse_numbered()
+            .chain_err(|| "There was an error parsing the numbered chapters");
+        let numbered_chapters = self.recover(numbered_chapters);
+        let suffix_chapters = self.parse_affix(false)
+            .chain_err(|| "There was an error parsing the suffix chapters");
+        let suffix_chapters = self.recover(suffix_chapters);
+
+        let summary = Summary {
+            title,
+            front_matter: None,
+            prefix_chapters,
+            numbered_chapters,
+            suffix_chapters,
+        };
+
+        ParsedSummary {
+            summary: summary,
+            errors: self.errors,
+            warnings: self.warnings,
+        }
+    }
+
+    /// Look for problems which are easier to spot in the raw text than in
+    /// the markdown events.
+    ///
+    /// Mixing tabs and spaces makes it very easy to accidentally nest a
+    /// chapter under the wrong parent, so warn about list items which aren't
+    /// indented the same way. When the caller says how items should be
+    /// indented, anything else is an error instead. Code blocks and comments
+    /// aren't checked.
+    fn check_lines(&mut self) {
+        let src = self.src;
+        let mut uses_tabs = None;
+        let mut depth = None;
+        let literal = literal_lines(src);
+
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim_right_matches('\r');
+            if literal[i] {
+                continue;
+            }
+
+            let trimmed = line.trim_left_matches(|c: char| c == ' ' || c == '\t');
+            let indent = &line[..line.len() - trimmed.len()];
+
+            if !is_list_item(trimmed) {
+                // part titles, separators and prefix chapters start a new list
+                if indent.is_empty() && !trimmed.is_empty() {
+                    depth = None;
+                }
+                continue;
+            }
+
+            if let Some(indentation) = self.indentation {
+                depth = self.check_indentation(i + 1, indent, indentation, depth);
+                continue;
+            }
+            if indent.is_empty() {
+                continue;
+            }
+
+            let has_tabs = indent.contains('\t');
+            let kind = if has_tabs && indent.contains(' ') {
+                ParseErrorKind::MixedIndentation
+            } else if uses_tabs.is_some() && uses_tabs != Some(has_tabs) {
+                ParseErrorKind::InconsistentIndentation { uses_tabs: has_tabs }
+            } else {
+                uses_tabs = Some(has_tabs);
+                continue;
+            };
+
+            warn!("{} on line {}", kind, i + 1);
+            self.warnings.push(ErrorKind::ParseError(i + 1, 1, kind).into());
+        }
+    }
+
+    /// Check the `indent` of the list item on `line` against the indentation
+    /// the summary should use, given the depth of the item before it (if it
+    /// was in the same list). Returns the depth the next item is nested
+    /// relative to.
+    fn check_indentation(
+        &mut self,
+        line: usize,
+        indent: &str,
+        indentation: Indentation,
+        previous: Option<usize>,
+    ) -> Option<usize> {
+        let deepest = previous.map(|depth| depth + 1).unwrap_or(0);
+
+        let mixed = indent.contains(' ') && indent.contains('\t');
+
+        let (kind, depth) = match indent_depth(indent, indentation) {
+            _ if mixed => (ParseErrorKind::MixedIndentation, previous),
+            Some(depth) if depth <= deepest => return Some(depth),
+            Some(depth) => (ParseErrorKind::IndentedTooFar, Some(depth)),
+            None => (ParseErrorKind::WrongIndentation(indentation), previous),
+        };
+
+        self.errors.push(ErrorKind::ParseError(line, 1, kind).into());
+        depth
+    }
+
+    /// Record the error if a section couldn't be parsed at all, falling back
+    /// to an empty list of items.
+    fn recover(&mut self, items: Result<Vec<SummaryItem>>) -> Vec<SummaryItem> {
+        match items {
+            Ok(items) => items,
+            Err(e) => {
+                self.errors.push(e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Parse the affix chapters. This expects the first event (start of
+    /// paragraph) to have already been consumed by the previous parser.
+    fn parse_affix(&mut self, is_prefix: bool) -> Result<Vec<SummaryItem>> {
+        let mut items = Vec::new();
+        debug!(
+            "[*] Parsing {} items",
+            if is_prefix { "prefix" } else { "suffix" }
+        );
+
+        loop {
+            match self.next_event() {
+                Some(event @ Event::Start(Tag::List(..))) |
+                Some(event @ Event::Start(Tag::Header(_))) => {
+                    if is_prefix {
+                        // we've finished prefix chapters and are at the start
+                        // of the numbered section.
+                        self.back(event);
+                        break;
+                    } else if let Event::Start(Tag::List(..)) = event {
+                        self.record_error(ParseErrorKind::ListAfterSuffixChapters);
+                        self.skip_to_end_of(&Tag::List(None));
+                    }
+                }
+                Some(Event::Start(Tag::Link(href, title))) => {
+                    let link = self.parse_link(href.to_string(), title.to_string())?;
+                    items.push(SummaryItem::Link(link));
+                }
+                Some(Event::Start(Tag::Rule)) => items.push(SummaryItem::Separator),
+                Some(Event::Text(_)) => if let Some(span) = self.unterminated_link() {
+                    self.record_unterminated_link(span);
+                    self.skip_to_next_line();
+                },
+                Some(_) => {}
+                None => break,
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Check whether the line we're currently on contains a link which was
+    /// never closed (e.g. `[Title](path` or `[Title`), returning its span.
+    fn unterminated_link(&self) -> Option<Span> {
+        let offset = cmp::min(self.stream.get_offset(), self.src.len());
+        let line_start = self.src[..offset].rfind('\n').map(|ix| ix + 1).unwrap_or(0);
+        let line_end = self.src[offset..]
+            .find('\n')
+            .map(|ix| offset + ix)
+            .unwrap_or_else(|| self.src.len());
+        let line = self.src[line_start..line_end].trim_right();
+
+        let bracket = match line.find('[') {
+            Some(ix) => ix,
+            None => return None,
+        };
+        let link = &line[bracket..];
+
+        let terminated = match link.find("](") {
+            Some(ix) => link[ix..].contains(')'),
+            None => link.contains(']'),
+        };
+
+        if terminated {
+            None
+        } else {
+            Some(Span {
+                start: line_start + bracket,
+                end: line_start + line.len(),
+            })
+        }
+    }
+
+    fn record_unterminated_link(&mut self, span: Span) {
+        let (line, col) = self.to_linecol(span.start);
+        let kind = ParseErrorKind::UnterminatedLink(span);
+        self.errors.push(ErrorKind::ParseError(line, col, kind).into());
+    }
+
+    /// Skip the rest of the current line, so parsing can pick up again after
+    /// something malformed.
+    fn skip_to_next_line(&mut self) {
+        loop {
+            match self.next_event() {
+                Some(Event::SoftBreak) | Some(Event::HardBreak) | None => break,
+                Some(event @ Event::End(_)) => {
+                    self.back(event);
+                    break;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn parse_link(&mut self, href: String, title: String) -> Result<Link> {
+        let start = self.link_start();
+        let link_content = collect_events!(self.stream, end Tag::Link(..));
+        let end = self.link_end();
+
+        let markdown_name = inline_markdown(&link_content);
+        let name = stringify_events(link_content);
+        if name.trim().is_empty() {
+            self.record_warning(ParseErrorKind::EmptyChapterName);
+        }
+        // split on the raw `#` first, so an escaped `%23` stays in the path
+        let (location, fragment) = split_fragment(&href);
+        let attributes = self.parse_attributes();
+        // summaries written on Windows sometimes use `\` as the separator
+        let location = percent_decode(location).replace('\\', "/");
+
+        Ok(Link {
+            name: name,
+            markdown_name: markdown_name,
+            location: PathBuf::from(location),
+            fragment: fragment,
+            title: if title.is_empty() { None } else { Some(title) },
+            attributes: attributes,
+            span: Some(Span { start: start, end: end }),
+            ..Default::default()
+        })
+    }
+
+    /// Parse the (optional) attributes which may come straight after a link,
+    /// e.g. `[Chapter](ch.md) {draft, class=advanced}`.
+    fn parse_attributes(&mut self) -> HashMap<String, String> {
+        let mut attributes = HashMap::new();
+
+        let text = match self.next_event() {
+            Some(Event::Text(text)) => text,
+            Some(other) => {
+                self.back(other);
+                return attributes;
+            }
+            None => return attributes,
+        };
+
+        let is_attributes = {
+            let trimmed = text.trim();
+            trimmed.starts_with('{') && trimmed.ends_with('}')
+        };
+        if !is_attributes {
+            self.back(Event::Text(text));
+            return attributes;
+        }
+
+        let trimmed = text.trim();
+        for attribute in trimmed[1..trimmed.len() - 1].split(',') {
+            let mut pieces = attribute.splitn(2, '=');
+            let key = pieces.next().unwrap_or("").trim();
+            let value = pieces.next().map(|v| v.trim()).unwrap_or("true");
+
+            if !key.is_empty() {
+                attributes.insert(key.to_string(), value.to_string());
+            }
+        }
+
+        attributes
+    }
+
+    /// Does the link we've just started parsing begin at the very start of
+    /// its line?
+    fn link_is_unindented(&self) -> bool {
+        let start = self.link_start();
+        let line_start = self.src[..start].rfind('\n').map(|ix| ix + 1).unwrap_or(0);
+
+        line_start == start
+    }
+
+    /// Find the opening `[` of the link we've just started parsing.
+    fn link_start(&self) -> usize {
+        let offset = self.stream.get_offset();
+        let searchable = &self.src[..(offset + 1).min(self.src.len())];
+
+        searchable.rfind('[').unwrap_or(offset)
+    }
+
+    /// Find the byte just past the closing `)` (or `]` for reference-style
+    /// links) of the link we've just finished parsing.
+    fn link_end(&self) -> usize {
+        let offset = self.stream.get_offset();
+
+        if self.src[..offset].ends_with(')') || self.src[..offset].ends_with(']') {
+            return offset;
+        }
+
+        match self.src[offset..].find(|c: char| c == ')' || c == ']') {
+            Some(ix) => offset + ix + 1,
+            None => offset,
+        }
+    }
+
+    /// Parse the numbered chapters, along with any part titles and separators
+    /// which are interleaved with them.
+    fn parse_numbered(&mut self) -> Result<Vec<SummaryItem>> {
+        let mut items = Vec::new();
+        let root_number = SectionNumber::default();
+
+        // A rule or a header will close off any currently running list, so
+        // we keep reading blocks until we reach the start of the suffix
+        // chapters (a paragraph) or run out of input. Every time a new list
+        // is started we resume numbering where the previous one left off.
+        loop {
+            match self.next_event() {
+                Some(Event::Start(Tag::List(start))) => {
+                    let existing_chapters = items.iter().filter(|item| item.is_link()).count();
+                    self.check_list_start(start, existing_chapters + 1);
+
+                    let mut bunch_of_items = self.parse_nested_numbered(&root_number)?;
+
+                    // the root sections of this list will be numbered from 1,
+                    // we need to manually go back and update them
+                    update_section_numbers(&mut bunch_of_items, 0, existing_chapters as u32);
+                    items.extend(bunch_of_items);
+                }
+                Some(Event::Start(Tag::Paragraph)) => {
+                    // we're starting the suffix chapters
+                    break;
+                }
+                Some(Event::Start(Tag::Header(_))) => {
+                    let tags = collect_events!(self.stream, end Tag::Header(_));
+                    let title = stringify_events(tags);
+                    debug!("[*] Found a part title: {}", title);
+
+                    items.push(SummaryItem::PartTitle(title));
+                }
+                Some(Event::Start(other_tag)) => {
+                    if Tag::Rule == other_tag {
+                        items.push(SummaryItem::Separator);
+                    }
+                    self.skip_to_end_of(&other_tag);
+                }
+                Some(_) => {
+                    // something else... ignore
+                    continue;
+                }
+                None => {
+                    // EOF, bail...
+                    break;
+                }
+            }
+        }
+
+        Ok(items)
+    }
+
+    fn next_event(&mut self) -> Option<Event<'a>> {
+        let next = self.back.pop().or_else(|| self.stream.next());
+        trace!("Next event: {:?}", next);
+
+        next
+    }
+
+    /// Put an event back so it will be returned by the next call to
+    /// `next_event()`.
+    fn back(&mut self, event: Event<'a>) {
+        self.back.push(event);
+    }
+
+    fn parse_nested_numbered(&mut self, parent: &SectionNumber) -> Result<Vec<SummaryItem>> {
+        debug!("[*] Parsing numbered chapters at level {}", parent);
+        let mut items = Vec::new();
+
+        loop {
+            match self.next_event() {
+                Some(Event::Start(Tag::Item)) => {
+                    let parsed = self.parse_nested_item(parent, items.len())?;
+                    items.extend(parsed);
+                }
+                Some(Event::Start(Tag::List(start))) => {
+                    self.check_list_start(start, 1);
+
+                    // recurse to parse the nested list
+                    let (_, last_item) = get_last_link(&mut items)?;
+                    let last_item_number = last_item
+                        .number
+                        .as_ref()
+                        .expect("All numbered chapters have numbers");
+
+                    let sub_items = self.parse_nested_numbered(last_item_number)?;
+
+                    last_item.nested_items = sub_items;
+                }
+                Some(Event::End(Tag::List(..))) => break,
+                Some(Event::Start(Tag::Link(..))) => {
+                    // A link outside of a list item usually means the line
+                    // was indented too far and got merged into the previous
+                    // item as a lazy continuation line.
+                    let kind = if self.link_is_unindented() {
+                        ParseErrorKind::UnnumberedLinkAfterList
+                    } else {
+                        ParseErrorKind::LinkOutsideListItem
+                    };
+                    self.record_error(kind);
+                    self.skip_to_end_of(&Tag::Item);
+                }
+                Some(Event::Text(ref text)) if !text.trim().is_empty() => {
+                    self.record_error(ParseErrorKind::UnexpectedText(text.to_string()));
+                    self.skip_to_end_of(&Tag::Item);
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Parse a single numbered item.
+    ///
+    /// If the item isn't a link an error is recorded, and any chapters nested
+    /// under it are returned in its place so they don't get lost.
+    fn parse_nested_item(
+        &mut self,
+        parent: &SectionNumber,
+        num_existing_items: usize,
+    ) -> Result<Vec<SummaryItem>> {
+        let mut seen_comment = false;
+
+        loop {
+            match self.next_event() {
+                Some(Event::Start(Tag::Paragraph)) => continue,
+                Some(Event::Html(ref html)) | Some(Event::InlineHtml(ref html))
+                    if seen_comment || html.trim_left().starts_with("<!--") =>
+                {
+                    // multi-line comments may be split across several events
+                    seen_comment = true;
+                    continue;
+                }
+                Some(Event::SoftBreak) | Some(Event::End(Tag::Paragraph)) if seen_comment => continue,
+                Some(Event::End(Tag::Item)) if seen_comment => {
+                    // the entire item was commented out
+                    return Ok(Vec::new());
+                }
+                Some(Event::Start(Tag::Link(href, title))) => {
+                    let mut link = self.parse_link(href.to_string(), title.to_string())?;
+
+                    let mut number = parent.clone();
+                    number.0.push(num_existing_items as u32 + 1);
+                    trace!(
+                        "[*] Found chapter: {} {} ({})",
+                        number,
+                        link.name,
+                        link.location.display()
+                    );
+
+                    link.number = Some(number);
+
+                    return Ok(vec![SummaryItem::Link(link)]);
+                }
+                other => {
+                    let unterminated = match other {
+                        Some(Event::Text(_)) => self.unterminated_link(),
+                        _ => None,
+                    };
+
+                    match unterminated {
+                        Some(span) => self.record_unterminated_link(span),
+                        None => {
+                            warn!("Expected a start of a link, actually got {:?}", other);
+                            self.record_error(ParseErrorKind::NestedItemNotALink);
+                        }
+                    }
+
+                    let has_children = match other {
+                        Some(Event::End(Tag::Item)) => return Ok(Vec::new()),
+                        Some(Event::Start(Tag::List(_))) => true,
+                        _ => self.skip_to_nested_list(),
+                    };
+                    if !has_children {
+                        return Ok(Vec::new());
+                    }
+
+                    let mut children = self.parse_nested_numbered(parent)?;
+                    self.skip_to_end_of(&Tag::Item);
+                    number_items_from(&mut children, parent, num_existing_items as u32);
+                    return Ok(children);
+                }
+            }
+        }
+    }
+
+    /// Skip the rest of a list item up to the list nested inside it, returning
+    /// `false` if the item ended without one.
+    fn skip_to_nested_list(&mut self) -> bool {
+        loop {
+            match self.next_event() {
+                Some(Event::Start(Tag::List(_))) => return true,
+                Some(Event::End(Tag::Item)) | None => return false,
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Skip events until we reach the `Event::End` which closes the current
+    /// `tag`, taking nesting into account (e.g. lists within lists).
+    fn skip_to_end_of(&mut self, tag: &Tag<'a>) {
+        trace!("Skipping to the end of {:?}", tag);
+        let kind = mem::discriminant(tag);
+        let mut depth = 0;
+
+        loop {
+            match self.next_event() {
+                Some(Event::Start(ref t)) if mem::discriminant(t) == kind => depth += 1,
+                Some(Event::End(ref t)) if mem::discriminant(t) == kind => {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+    }
+
+    /// Record an error at the current location and carry on parsing.
+    fn record_error(&mut self, kind: ParseErrorKind) {
+        let err = self.parse_error(kind);
+        self.errors.push(err);
+    }
+
+    fn record_warning(&mut self, kind: ParseErrorKind) {
+        let warning = self.parse_error(kind);
+        warn!("{}", warning);
+        self.warnings.push(warning);
+    }
+
+    /// Section numbers always come from a chapter's position in `SUMMARY.md`,
+    /// so if an ordered list (`1. [Intro](intro.md)`) starts at a different
+    /// number the author probably made a mistake.
+    fn check_list_start(&mut self, list_start: Option<usize>, expected: usize) {
+        if let Some(start) = list_start {
+            if start != expected {
+                self.record_warning(ParseErrorKind::OrderedListOutOfSequence {
+                    start: start,
+                    expected: expected,
+                });
+            }
+        }
+    }
+
+    fn parse_error(&self, kind: ParseErrorKind) -> Error {
+        let (line, col) = self.current_location();
+
+        ErrorKind::ParseError(line, col, kind).into()
+    }
+
+    /// Try to parse the title line.
+    fn parse_title(&mut self) -> Option<String> {
+        match self.next_event() {
+            Some(Event::Start(Tag::Header(1))) => {
+                debug!("[*] Found a h1 in the SUMMARY");
+
+                let tags = collect_events!(self.stream, end Tag::Header(1));
+                Some(stringify_events(tags))
+            }
+            Some(other) => {
+                // not a title, let the prefix chapter parser deal with it
+                self.back(other);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+fn update_section_numbers(sections: &mut [SummaryItem], level: usize, by: u32) {
+    for section in sections {
+        if let SummaryItem::Link(ref mut link) = *section {
+            if let Some(ref mut number) = link.number {
+                number.0[level] += by;
+            }
+
+            update_section_numbers(&mut link.nested_items, level, by);
+        }
+    }
+}
+
+/// Work out which lines of `src` are inside a fenced code block or an HTML
+/// comment, where list items and whitespace are just part of the text.
+pub fn literal_lines(src: &str) -> Vec<bool> {
+    let mut fence: Option<&str> = None;
+    let mut in_comment = false;
+
+    src.lines()
+        .map(|line| {
+            let trimmed = line.trim();
+
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                return true;
+            }
+            if in_comment {
+                in_comment = !trimmed.contains("-->");
+                return true;
+            }
+
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                fence = Some(&trimmed[..3]);
+                return true;
+            }
+            match trimmed.find("<!--") {
+                Some(ix) if !trimmed[ix..].contains("-->") => {
+                    in_comment = true;
+                    true
+                }
+                Some(0) => trimmed.ends_with("-->"),
+                _ => false,
+            }
+        })
+        .collect()
+}
+
+/// Does this (already unindented) line start with a list marker like `- `,
+/// `* `, `+ ` or `1. `?
+fn is_list_item(line: &str) -> bool {
+    let after_digits = line.trim_left_matches(|c: char| c.is_digit(10));
+
+    if after_digits.len() < line.len() {
+        after_digits.starts_with(". ") || after_digits.starts_with(") ")
+    } else {
+        line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ")
+    }
+}
+
+/// How many levels of nesting `indent` is worth, or `None` if it isn't
+/// made up of whole levels of `indentation`.
+fn indent_depth(indent: &str, indentation: Indentation) -> Option<usize> {
+    match indentation {
+        Indentation::Spaces(width) => {
+            if indent.chars().all(|c| c == ' ') && indent.len() % width == 0 {
+                Some(indent.len() / width)
+            } else {
+                None
+            }
+        }
+        Indentation::Tabs => {
+            if indent.chars().all(|c| c == '\t') {
+                Some(indent.len())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Gets a pointer to the last `Link` in a list of `SummaryItem`s, and its
+/// index.
+fn get_last_link(links: &mut [SummaryItem]) -> Result<(usize, &mut Link)> {
+    links
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(i, item)| item.maybe_link_mut().map(|l| (i, l)))
+        .rev()
+        .next()
+        .ok_or_else(|| {
+            "Unable to get last link because the list of SummaryItems doesn't contain any Links"
+                .into()
+        })
+}
+
+/// Split a link's destination into the path and the (optional) anchor
+/// fragment, `chapter_1.md#setup` becomes `("chapter_1.md", Some("setup"))`.
+fn split_fragment(href: &str) -> (&str, Option<String>) {
+    match href.find('#') {
+        Some(ix) => {
+            let fragment = &href[ix + 1..];
+            let fragment = if fragment.is_empty() {
+                None
+            } else {
+                Some(fragment.to_string())
+            };
+
+            (&href[..ix], fragment)
+        }
+        None => (href, None),
+    }
+}
+
+/// Turn the events for a piece of inline text back into markdown, returning
+/// `None` if there was no formatting (so the plain text is all you need).
+fn inline_markdown(events: &[Event]) -> Option<String> {
+    let mut markdown = String::new();
+    let mut has_formatting = false;
+    let mut in_code = false;
+
+    for event in events {
+        match *event {
+            Event::Text(ref text) if in_code => markdown.push_str(text),
+            Event::Text(ref text) => markdown.push_str(&escape_markdown(text)),
+            Event::Start(Tag::Code) | Event::End(Tag::Code) => {
+                has_formatting = true;
+                in_code = !in_code;
+                markdown.push('`');
+            }
+            Event::Start(Tag::Emphasis) | Event::End(Tag::Emphasis) => {
+                has_formatting = true;
+                markdown.push('*');
+            }
+            Event::Start(Tag::Strong) | Event::End(Tag::Strong) => {
+                has_formatting = true;
+                markdown.push_str("**");
+            }
+            Event::InlineHtml(ref html) => {
+                has_formatting = true;
+                markdown.push_str(html);
+            }
+            Event::SoftBreak | Event::HardBreak => markdown.push(' '),
+            _ => {}
+        }
+    }
+
+    if has_formatting {
+        Some(markdown)
+    } else {
+        None
+    }
+}
+
+/// Removes the styling from a list of Markdown events and returns just the
+/// plain text.
+fn stringify_events(events: Vec<Event>) -> String {
+    events
+        .into_iter()
+        .filter_map(|t| match t {
+            Event::Text(text) => Some(text.into_owned()),
+            // a link's text may have been wrapped onto several lines
+            Event::SoftBreak | Event::HardBreak => Some(String::from(" ")),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A section number like "1.2.3", basically just a newtype'd `Vec<u32>` with
+/// a pretty `Display` impl.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct SectionNumber(pub Vec<u32>);
+
+impl Display for SectionNumber {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            write!(f, "0")
+        } else {
+            for item in &self.0 {
+                write!(f, "{}.", item)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl Deref for SectionNumber {
+    type Target = Vec<u32>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SectionNumber {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FromIterator<u32> for SectionNumber {
+    fn from_iter<I: IntoIterator<Item = u32>>(it: I) -> Self {
+        SectionNumber(it.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use config::BuildConfig;
+
+    /// Get the links among `items`, skipping separators and part titles.
+    fn links_in(items: &[SummaryItem]) -> Vec<&Link> {
+        items
+            .iter()
+            .filter_map(|item| match *item {
+                SummaryItem::Link(ref link) => Some(link),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn section_number_has_correct_dotted_representation() {
+        let inputs = vec![
+            (vec![0], "0."),
+            (vec![1, 3], "1.3."),
+            (vec![1, 2, 3], "1.2.3."),
+        ];
+
+        for (input, should_be) in inputs {
+            let section_number = SectionNumber(input).to_string();
+            assert_eq!(section_number, should_be);
+        }
+    }
+
+    #[test]
+    fn summary_can_be_written_back_out_as_markdown() {
+        let src = "# Summary\n\n[Introduction](intro.md)\n\n- [First Chapter](./first/index.md)\n    \
+                   - [Nested Chapter](./first/nested.md)\n    - [Draft]()\n- [Second](./second.md)\n\n\
+                   ---\n\n# Part II\n\n- [Third \\[3\\]](./third.md)\n\n[Conclusion](./conclusion.md)\n";
+
+        let got = parse_summary(src).unwrap().to_string();
+
+        assert_eq!(got, src);
+    }
+
+    #[test]
+    fn writing_a_summary_round_trips() {
+        let src = "[Intro](intro.md)\n\n- [A `code` *title*](a.md)\n- [B](b.md)\n    - [C](c.md)\n";
+        let original = parse_summary(src).unwrap();
+
+        let got = parse_summary(&original.to_string()).unwrap();
+
+        assert_eq!(got, original);
+    }
+
+    #[test]
+    fn summaries_can_be_serialized_and_deserialized() {
+        let src = "# Title\n\n[Intro](intro.md \"An introduction\")\n\n\
+                   - [First](first.md#setup)\n    - [Nested](nested.md)\n\n\
+                   # Part II\n\n- [Draft]()\n\n---\n\n[Conclusion](conclusion.md)\n";
+        let original = parse_summary(src).unwrap();
+
+        let json = ::serde_json::to_string(&original).unwrap();
+        let got: Summary = ::serde_json::from_str(&json).unwrap();
+
+        assert_eq!(got, original);
+    }
+
+    #[test]
+    fn iterate_over_every_link_in_the_summary() {
+        let src = "[Intro](intro.md)\n\n- [First](first.md)\n    - [Nested](nested.md)\n\
+                   \x20       - [Deeper](deeper.md)\n- [Second](second.md)\n\n---\n\n\
+                   [Conclusion](conclusion.md)\n";
+        let summary = parse_summary(src).unwrap();
+
+        let got: Vec<_> = summary
+            .iter()
+            .map(|(link, depth)| (link.name.as_str(), depth))
+            .collect();
+        let should_be = vec![
+            ("Intro", 0),
+            ("First", 0),
+            ("Nested", 1),
+            ("Deeper", 2),
+            ("Second", 0),
+            ("Conclusion", 0),
+        ];
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn walk_mut_can_rewrite_every_link() {
+        let src = "[Intro](intro.md)\n\n- [First](first.md)\n    - [Nested](nested.md)\n\n\
+                   [Conclusion](conclusion.md)\n";
+        let mut summary = parse_summary(src).unwrap();
+        let mut visited = Vec::new();
+
+        summary.walk_mut(|link| {
+            visited.push(link.name.clone());
+            link.location = Path::new("src").join(&link.location);
+        });
+
+        assert_eq!(visited, vec!["Intro", "First", "Nested", "Conclusion"]);
+        let locations: Vec<_> = summary.iter().map(|(link, _)| link.location.clone()).collect();
+        let should_be: Vec<_> = ["intro.md", "first.md", "nested.md", "conclusion.md"]
+            .iter()
+            .map(|name| Path::new("src").join(name))
+            .collect();
+        assert_eq!(locations, should_be);
+    }
+
+    #[test]
+    fn errors_from_a_summary_file_mention_its_path() {
+        let path = Path::new("this/file/does/not/exist/SUMMARY.md");
+
+        let err = Summary::from_path(path).unwrap_err();
+
+        assert!(err.to_string().contains(&path.display().to_string()));
+    }
+
+    #[test]
+    fn duplicate_destinations_fail_validation() {
+        let src = "- [First](first.md)\n- [Second](second.md)\n    - [Again](first.md)\n";
+        let summary = parse_summary(src).unwrap();
+
+        let err = summary.validate().unwrap_err();
+
+        assert!(err.to_string().contains("first.md"));
+
+        let src = "- [First](first.md)\n- [Again](./first.md)\n";
+        assert!(parse_summary(src).unwrap().validate().is_err());
+    }
+
+    #[test]
+    fn links_to_anchors_in_a_chapter_are_not_duplicates() {
+        let src = "- [Guide](guide.md)\n    - [Setup](guide.md#setup)\n";
+        let summary = parse_summary(src).unwrap();
+
+        assert!(summary.validate().is_ok());
+    }
+
+    #[test]
+    fn drafts_dont_count_as_duplicates() {
+        let src = "- [First](first.md)\n- [Coming Soon]()\n- [Also Coming Soon]()\n";
+        let summary = parse_summary(src).unwrap();
+
+        assert!(summary.validate().is_ok());
+    }
+
+    #[test]
+    fn chapters_nested_too_deeply_are_an_error() {
+        let src = "- [First](first.md)\n    - [Nested](nested.md)\n        - [Deeper](deeper.md)\n";
+        let summary = parse_summary(src).unwrap();
+
+        assert!(summary.check_depth(3).is_ok());
+        let err = summary.check_depth(2).unwrap_err();
+        assert!(err.to_string().contains("Deeper"));
+    }
+
+    #[test]
+    fn sub_summaries_can_be_included() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        ::std::fs::create_dir(temp.path().join("part2")).unwrap();
+        let main_summary = "- [First](first.md)\n    {{#include part2/SUMMARY.md}}\n- [Last](last.md)\n";
+        File::create(temp.path().join("SUMMARY.md"))
+            .unwrap()
+            .write_all(main_summary.as_bytes())
+            .unwrap();
+        File::create(temp.path().join("part2").join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [Nested](part2/nested.md)\n    - [Deeper](part2/deeper.md)\n")
+            .unwrap();
+
+        let got = Summary::from_path(temp.path().join("SUMMARY.md")).unwrap();
+
+        let should_be = parse_summary(
+            "- [First](first.md)\n    - [Nested](part2/nested.md)\n        \
+             - [Deeper](part2/deeper.md)\n- [Last](last.md)\n",
+        ).unwrap();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn problems_in_included_files_are_reported_against_them() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        ::std::fs::create_dir(temp.path().join("part2")).unwrap();
+        let main_summary = "- [First](first.md)\n    {{#include part2/SUMMARY.md}}\n\
+                            - [Last](last.md)\n";
+        let included = "- [Nested](part2/nested.md)\n- Not a link\n";
+        File::create(temp.path().join("SUMMARY.md"))
+            .unwrap()
+            .write_all(main_summary.as_bytes())
+            .unwrap();
+        File::create(temp.path().join("part2").join("SUMMARY.md"))
+            .unwrap()
+            .write_all(included.as_bytes())
+            .unwrap();
+
+        for chapter in &["first.md", "last.md", "part2/nested.md"] {
+            File::create(temp.path().join(chapter)).unwrap();
+        }
+
+        let cfg = BuildConfig::default();
+        let got = diagnose_summary(temp.path().join("SUMMARY.md"), &cfg).unwrap();
+
+        assert_eq!(got.len(), 1);
+        let included_path = temp.path().join("part2").join("SUMMARY.md");
+        assert_eq!(got[0].file, included_path.display().to_string());
+        assert_eq!(got[0].line, Some(2));
+        assert_eq!(got[0].column, Some(3));
+        let span = got[0].span.unwrap();
+        assert_eq!(&included[span.start..span.end], "Not a link");
+
+        let err = Summary::from_path(temp.path().join("SUMMARY.md")).unwrap_err();
+        assert!(err.to_string().contains(&format!("{}:2:3", included_path.display())));
+    }
+
+    #[test]
+    fn cyclic_includes_are_an_error() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        File::create(temp.path().join("SUMMARY.md"))
+            .unwrap()
+            .write_all(b"- [First](first.md)\n{{#include other.md}}\n")
+            .unwrap();
+        File::create(temp.path().join("other.md"))
+            .unwrap()
+            .write_all(b"{{#include SUMMARY.md}}\n")
+            .unwrap();
+
+        assert!(Summary::from_path(temp.path().join("SUMMARY.md")).is_err());
+    }
+
+    #[test]
+    fn missing_chapter_files_are_reported() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        File::create(temp.path().join("first.md")).unwrap();
+
+        let src = "- [First](first.md)\n- [Second](second.md)\n- [Draft]()\n";
+        let summary = parse_summary(src).unwrap();
+
+        let errors = summary.validate_destinations(temp.path()).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        match *errors[0].kind() {
+            ErrorKind::MissingChapter(ref name, ref location, span, _) => {
+                assert_eq!(name, "Second");
+                assert_eq!(location, Path::new("second.md"));
+                let span = span.unwrap();
+                assert_eq!(&src[span.start..span.end], "[Second](second.md)");
+            }
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_chapters_suggest_similarly_named_files() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        fs::create_dir(temp.path().join("nested")).unwrap();
+        File::create(temp.path().join("chapter_1.md")).unwrap();
+        File::create(temp.path().join("nested").join("setup.md")).unwrap();
+
+        let src = "- [One](./chapter1.md)\n- [Setup](nested/stup.md)\n- [Other](unrelated.md)\n";
+        let summary = parse_summary(src).unwrap();
+
+        let suggestions: Vec<_> = summary
+            .validate_destinations(temp.path())
+            .unwrap_err()
+            .iter()
+            .map(|err| match *err.kind() {
+                ErrorKind::MissingChapter(_, _, _, ref suggestion) => suggestion.clone(),
+                ref other => panic!("Unexpected error, {:?}", other),
+            })
+            .collect();
+        let should_be = vec![
+            Some(PathBuf::from("chapter_1.md")),
+            Some(Path::new("nested").join("setup.md")),
+            None,
+        ];
+        assert_eq!(suggestions, should_be);
+    }
+
+    #[test]
+    fn look_up_chapters_by_path_and_number() {
+        let src = "[Intro](intro.md)\n\n- [First](first.md)\n    - [Nested](first/nested.md)\n\
+                   - [Second](second.md)\n";
+        let summary = parse_summary(src).unwrap();
+
+        let nested = summary.get_by_path("first/nested.md").unwrap();
+        assert_eq!(nested.name, "Nested");
+        let second = summary.get_by_number(&SectionNumber(vec![2])).unwrap();
+        assert_eq!(second.name, "Second");
+        let nested_by_number = summary.get_by_number(&SectionNumber(vec![1, 1])).unwrap();
+        assert_eq!(nested_by_number, nested);
+
+        assert!(summary.get_by_path("missing.md").is_none());
+        assert!(summary.get_by_number(&SectionNumber(vec![3])).is_none());
+    }
+
+    #[test]
+    fn link_helpers() {
+        let summary = SummaryBuilder::new()
+            .prefix_chapter(Link::new("Intro", "intro.md"))
+            .numbered_chapter(Link::new("First", "first.md")
+                .with_nested_item(Link::new("Nested", "nested.md")))
+            .build();
+        let links: Vec<_> = summary.iter().map(|(link, _)| link).collect();
+
+        let got: Vec<_> = links
+            .iter()
+            .map(|link| (link.has_nested_items(), link.depth()))
+            .collect();
+        assert_eq!(got, vec![(false, 0), (true, 0), (false, 1)]);
+        assert!(!links[0].is_draft());
+        assert!(Link::new("Coming Soon", "").is_draft());
+    }
+
+    #[test]
+    fn builder_assigns_section_numbers() {
+        let got = SummaryBuilder::new()
+            .prefix_chapter(Link::new("Intro", "intro.md"))
+            .numbered_chapter(Link::new("First", "first.md")
+                .with_nested_item(Link::new("Nested", "nested.md")))
+            .part_title("Part II")
+            .numbered_chapter(Link::new("Second", "second.md"))
+            .separator()
+            .suffix_chapter(Link::new("Conclusion", "conclusion.md"))
+            .build();
+
+        // The builder should produce exactly what the parser would
+        let src = "[Intro](intro.md)\n\n- [First](first.md)\n    - [Nested](nested.md)\n\n\
+                   # Part II\n\n- [Second](second.md)\n\n---\n\n[Conclusion](conclusion.md)\n";
+        let should_be = parse_summary(src).unwrap();
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn parse_initial_title() {
+        let src = "# Summary";
+        let should_be = String::from("Summary");
+
+        let mut parser = SummaryParser::new(src);
+        let got = parser.parse_title().unwrap();
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn parse_title_with_styling() {
+        let src = "# My **Awesome** Summary";
+        let should_be = String::from("My Awesome Summary");
+
+        let mut parser = SummaryParser::new(src);
+        let got = parser.parse_title().unwrap();
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn several_events_can_be_put_back() {
+        let mut parser = SummaryParser::new("# Summary");
+        let first = parser.next_event().unwrap();
+        let second = parser.next_event().unwrap();
+
+        parser.back(second.clone());
+        parser.back(first.clone());
+
+        assert_eq!(parser.next_event(), Some(first));
+        assert_eq!(parser.next_event(), Some(second));
+    }
+
+    #[test]
+    fn convert_markdown_events_to_a_string() {
+        let src = "Hello *World*, `this` is some text [and a link](./path/to/link)";
+        let should_be = "Hello World, this is some text and a link";
+
+        let events = pulldown_cmark::Parser::new(src).collect();
+        let got = stringify_events(events);
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn parse_some_prefix_items() {
+        let src = "[First](./first.md)\n[Second](./second.md)\n";
+        let mut parser = SummaryParser::new(src);
+
+        let should_be = vec![
+            SummaryItem::Link(Link {
+                name: String::from("First"),
+                location: PathBuf::from("./first.md"),
+                ..Default::default()
+            }),
+            SummaryItem::Link(Link {
+                name: String::from("Second"),
+                location: PathBuf::from("./second.md"),
+                ..Default::default()
+            }),
+        ];
+
+        let _ = parser.stream.next(); // step past first event
+        let got = parser.parse_affix(true).unwrap();
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn parse_prefix_items_with_a_separator() {
+        let src = "[First](./first.md)\n\n---\n\n[Second](./second.md)\n";
+        let mut parser = SummaryParser::new(src);
+
+        let _ = parser.stream.next(); // step past first event
+        let got = parser.parse_affix(true).unwrap();
+
+        assert_eq!(got.len(), 3);
+        assert_eq!(got[1], SummaryItem::Separator);
+    }
+
+    #[test]
+    fn suffix_items_cannot_be_followed_by_a_list() {
+        let src = "[First](./first.md)\n- [Second](./second.md)\n";
+        let mut parser = SummaryParser::new(src);
+
+        let _ = parser.stream.next(); // step past first event
+        let got = parser.parse_affix(false).unwrap();
+
+        assert_eq!(got.len(), 1);
+        assert_eq!(parser.errors.len(), 1);
+    }
+
+    #[test]
+    fn parse_a_link() {
+        let src = "[First](./first.md)";
+        let should_be = Link {
+            name: String::from("First"),
+            location: PathBuf::from("./first.md"),
+            ..Default::default()
+        };
+
+        let mut parser = SummaryParser::new(src);
+        let _ = parser.stream.next(); // skip past start of paragraph
+
+        let (href, title) = match parser.stream.next() {
+            Some(Event::Start(Tag::Link(href, title))) => (href.to_string(), title.to_string()),
+            other => panic!("Unreachable, {:?}", other),
+        };
+
+        let got = parser.parse_link(href, title).unwrap();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn links_know_where_they_came_from() {
+        let src = "[First](first.md)\n\n- [Second](second.md)\n    - [Third][third]\n\n\
+                   [third]: third.md\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let spans: Vec<_> = got.iter()
+            .map(|(link, _)| {
+                let span = link.span.expect("Parsed links always have a span");
+                &src[span.start..span.end]
+            })
+            .collect();
+        assert_eq!(spans, vec!["[First](first.md)", "[Second](second.md)", "[Third][third]"]);
+    }
+
+    #[test]
+    fn convert_byte_offsets_to_line_and_column() {
+        let src = "# Summary\n\n- [First](first.md)\n";
+        let parser = SummaryParser::new(src);
+
+        assert_eq!(parser.to_linecol(0), (1, 1));
+        assert_eq!(parser.to_linecol(2), (1, 3));
+        assert_eq!(parser.to_linecol(10), (2, 1));
+        assert_eq!(parser.to_linecol(11), (3, 1));
+        assert_eq!(parser.to_linecol(13), (3, 3));
+        assert_eq!(parser.to_linecol(src.len()), (4, 1));
+    }
+
+    #[test]
+    fn columns_are_counted_in_characters_not_bytes() {
+        let src = "- [Café ☕](cafe.md)\n";
+        let parser = SummaryParser::new(src);
+        let after_emoji = src.find(']').unwrap();
+
+        assert_eq!(parser.to_linecol(after_emoji), (1, 10));
+    }
+
+    #[test]
+    fn backslash_escapes_are_removed_from_chapter_names() {
+        let src = "- [Preface \\[3](preface.md)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let link = match got.numbered_chapters[0] {
+            SummaryItem::Link(ref link) => link.clone(),
+            ref other => panic!("Expected a link, got {:?}", other),
+        };
+        assert_eq!(link.name, "Preface [3");
+
+        // writing it back out has to escape the bracket again
+        assert_eq!(got.to_string(), src);
+    }
+
+    #[test]
+    fn any_bullet_list_marker_can_be_used() {
+        let dashes = parse_summary("- [First](first.md)\n    - [Nested](nested.md)\n").unwrap();
+        let stars = parse_summary("* [First](first.md)\n    * [Nested](nested.md)\n").unwrap();
+        let pluses = parse_summary("+ [First](first.md)\n    + [Nested](nested.md)\n").unwrap();
+
+        assert_eq!(stars, dashes);
+        assert_eq!(pluses, dashes);
+    }
+
+    #[test]
+    fn ordered_lists_are_numbered_by_position() {
+        let bullets = parse_summary("- [First](first.md)\n    - [Nested](nested.md)\n\n\
+                                     # Part II\n\n- [Second](second.md)\n")
+            .unwrap();
+        let ordered = parse_summary("1. [First](first.md)\n    1. [Nested](nested.md)\n\n\
+                                     # Part II\n\n7. [Second](second.md)\n")
+            .unwrap();
+
+        assert_eq!(ordered, bullets);
+    }
+
+    #[test]
+    fn links_can_have_attributes() {
+        let src = "[Intro](intro.md) {hidden}\n\n\
+                   - [Chapter](ch.md) {draft, hidden, class=advanced}\n\
+                   - [Plain](plain.md)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let attributes: Vec<_> = got.iter().map(|(link, _)| link.attributes.clone()).collect();
+        let mut chapter = HashMap::new();
+        chapter.insert(String::from("draft"), String::from("true"));
+        chapter.insert(String::from("hidden"), String::from("true"));
+        chapter.insert(String::from("class"), String::from("advanced"));
+        let mut intro = HashMap::new();
+        intro.insert(String::from("hidden"), String::from("true"));
+        assert_eq!(attributes, vec![intro, chapter, HashMap::new()]);
+
+        let round_tripped = parse_summary(&got.to_string()).unwrap();
+        assert_eq!(round_tripped, got);
+    }
+
+    #[test]
+    fn backslash_separators_are_normalized() {
+        let got = parse_summary("- [Intro](chapters\\intro.md)\n").unwrap();
+        let should_be = parse_summary("- [Intro](chapters/intro.md)\n").unwrap();
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn destinations_are_percent_decoded() {
+        let src = "- [Mine](my%20chapter.md)\n- [Odd](100%25%28ish%29.md)\n- [Bad](bad%zz.md)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let locations: Vec<_> = got.iter().map(|(link, _)| link.location.clone()).collect();
+        let should_be = vec![
+            PathBuf::from("my chapter.md"),
+            PathBuf::from("100%(ish).md"),
+            PathBuf::from("bad%zz.md"),
+        ];
+        assert_eq!(locations, should_be);
+
+        let round_tripped = parse_summary(&got.to_string()).unwrap();
+        assert_eq!(round_tripped, got);
+    }
+
+    #[test]
+    fn escaped_hashes_are_part_of_the_path() {
+        let src = "- [Sharp](c%23.md)\n- [Setup](c%23.md#setup)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let links: Vec<_> = got.iter()
+            .map(|(link, _)| (link.location.clone(), link.fragment.clone()))
+            .collect();
+        let should_be = vec![
+            (PathBuf::from("c#.md"), None),
+            (PathBuf::from("c#.md"), Some(String::from("setup"))),
+        ];
+        assert_eq!(links, should_be);
+
+        let round_tripped = parse_summary(&got.to_string()).unwrap();
+        assert_eq!(round_tripped, got);
+    }
+
+    #[test]
+    fn chapter_names_keep_their_inline_markdown() {
+        let src = "- [The **fast** path](fast.md)\n- [`unsafe` Rust](unsafe.md)\n- [Plain](plain.md)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let names: Vec<_> = got.iter()
+            .map(|(link, _)| (link.name.clone(), link.markdown_name.clone()))
+            .collect();
+        let should_be = vec![
+            (String::from("The fast path"), Some(String::from("The **fast** path"))),
+            (String::from("unsafe Rust"), Some(String::from("`unsafe` Rust"))),
+            (String::from("Plain"), None),
+        ];
+        assert_eq!(names, should_be);
+        assert_eq!(got.to_string(), src);
+    }
+
+    #[test]
+    fn links_can_be_wrapped_onto_several_lines() {
+        let src = "[A long\nintroduction](intro.md)\n\n- [A chapter with a\n  really long name](long.md)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let names: Vec<_> = got.iter().map(|(link, _)| link.name.clone()).collect();
+        assert_eq!(names, vec!["A long introduction", "A chapter with a really long name"]);
+    }
+
+    #[test]
+    fn find_files_not_in_the_summary() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        fs::create_dir(temp.path().join("nested")).unwrap();
+        for name in &["SUMMARY.md", "first.md", "orphan.md", "nested/second.md", "notes.txt"] {
+            File::create(temp.path().join(name)).unwrap();
+        }
+
+        let summary = parse_summary("- [First](./first.md)\n- [Second](nested/second.md)\n")
+            .unwrap();
+
+        let got = summary.find_orphans(temp.path());
+
+        assert_eq!(got, vec![PathBuf::from("orphan.md")]);
+    }
+
+    #[test]
+    fn sub_books_dont_have_orphans() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        fs::create_dir(temp.path().join("guide")).unwrap();
+        for name in &["first.md", "guide/SUMMARY.md", "guide/intro.md"] {
+            File::create(temp.path().join(name)).unwrap();
+        }
+        let summary = parse_summary("- [First](first.md)\n").unwrap();
+
+        let got = summary.find_orphans_excluding(temp.path(), &[PathBuf::from("guide")]);
+
+        assert!(got.is_empty());
+        assert_eq!(summary.find_orphans(temp.path()), vec![PathBuf::from("guide/intro.md")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycles_dont_hang_the_orphan_search() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        fs::create_dir(temp.path().join("nested")).unwrap();
+        File::create(temp.path().join("nested/orphan.md")).unwrap();
+        ::std::os::unix::fs::symlink(temp.path(), temp.path().join("nested/loop")).unwrap();
+
+        let got = Summary::default().find_orphans(temp.path());
+
+        assert_eq!(got, vec![PathBuf::from("nested/orphan.md")]);
+    }
+
+    #[test]
+    fn strict_parsing_from_a_file() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        let path = temp.path().join("SUMMARY.md");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"- [First](first.md)   \n")
+            .unwrap();
+
+        assert!(Summary::from_path(&path).is_ok());
+
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(Summary::from_path_with(&path, &options).is_err());
+    }
+
+    #[test]
+    fn chapters_nested_under_an_invalid_item_are_kept() {
+        let src = "- [First](first.md)\n- Not a link\n    - [Child](child.md)\n\
+                   - [Last](last.md)\n";
+
+        let (summary, errors) = parse_summary_partial(src).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        let got: Vec<(String, String)> = summary
+            .iter()
+            .map(|(link, _)| (link.name.clone(), link.number.as_ref().unwrap().to_string()))
+            .collect();
+        let should_be = vec![
+            (String::from("First"), String::from("1.")),
+            (String::from("Child"), String::from("2.")),
+            (String::from("Last"), String::from("3.")),
+        ];
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn unterminated_links_are_an_error() {
+        let src = "[Intro](intro.md)\n\n- [First](first.md)\n- [Second](second.md\n\
+                   - [Third](third.md)\n\n[Conclusion](conclusion.md\n";
+
+        let (summary, errors) = parse_summary_partial(src).unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        match *errors[0].kind() {
+            ErrorKind::ParseError(line, col, ParseErrorKind::UnterminatedLink(span)) => {
+                assert_eq!((line, col), (4, 3));
+                assert_eq!(&src[span.start..span.end], "[Second](second.md");
+            }
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+        match *errors[1].kind() {
+            ErrorKind::ParseError(line, _, ParseErrorKind::UnterminatedLink(_)) => {
+                assert_eq!(line, 7)
+            }
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+
+        assert_eq!(summary.prefix_chapters.len(), 1);
+        assert_eq!(summary.numbered_chapters.len(), 2);
+        assert!(summary.suffix_chapters.is_empty());
+    }
+
+    #[test]
+    fn byte_order_marks_are_skipped() {
+        let temp = ::tempdir::TempDir::new("mdbook").unwrap();
+        let path = temp.path().join("SUMMARY.md");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"\xef\xbb\xbf[Intro](intro.md)\n")
+            .unwrap();
+
+        let got = Summary::from_path(&path).unwrap();
+
+        assert_eq!(got.prefix_chapters.len(), 1);
+        assert_eq!(parse_summary("\u{feff}[Intro](intro.md)\n").unwrap(), got);
+    }
+
+    #[test]
+    fn spans_after_a_byte_order_mark_match_the_input() {
+        let src = "\u{feff}[Intro](intro.md)\n\n- [First](first.md)\n";
+
+        let parsed = parse_summary_with_warnings(src);
+
+        let spans: Vec<&str> = parsed
+            .summary
+            .iter()
+            .map(|(link, _)| {
+                let span = link.span.unwrap();
+                &src[span.start..span.end]
+            })
+            .collect();
+        assert_eq!(spans, vec!["[Intro](intro.md)", "[First](first.md)"]);
+
+        let parsed = parse_summary_with_warnings("\u{feff}[Intro](intro.md\n");
+        match *parsed.errors[0].kind() {
+            ErrorKind::ParseError(1, 2, ParseErrorKind::UnterminatedLink(span)) => {
+                assert_eq!(span.start, 3)
+            }
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+    }
+
+    #[test]
+    fn files_in_other_encodings_are_a_clear_error() {
+        let path = Path::new("SUMMARY.md");
+
+        match *decode_summary(path, b"\xff\xfe[\0I\0".to_vec()).unwrap_err().kind() {
+            ErrorKind::Encoding(_, ref reason) => assert!(reason.contains("UTF-16")),
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+
+        match *decode_summary(path, b"# Summary\n\n- [Caf\xe9](cafe.md)\n".to_vec())
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::Encoding(_, ref reason) => assert!(reason.contains("line 3")),
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unnumbered_links_straight_after_the_list_get_a_targeted_error() {
+        let src = "- [First](first.md)\n[Preface](preface.md)\n";
+
+        let (_, errors) = parse_summary_partial(src).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        match *errors[0].kind() {
+            ErrorKind::ParseError(_, _, ParseErrorKind::UnnumberedLinkAfterList) => {}
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+
+        // indenting it is still reported as an indentation problem
+        let src = "- [First](first.md)\n      [Nested](nested.md)\n";
+        let (_, errors) = parse_summary_partial(src).unwrap_err();
+        match *errors[0].kind() {
+            ErrorKind::ParseError(_, _, ParseErrorKind::LinkOutsideListItem) => {}
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+    }
+
+    #[test]
+    fn find_the_chapter_at_an_offset() {
+        let src = "# Summary\n\n- [First](first.md)\n    - [Nested](nested.md)\n";
+        let summary = parse_summary(src).unwrap();
+
+        let nested = src.find("nested.md").unwrap();
+        assert_eq!(summary.item_at_offset(nested).unwrap().name, "Nested");
+
+        let first = src.find("[First]").unwrap();
+        assert_eq!(summary.item_at_offset(first).unwrap().name, "First");
+
+        assert!(summary.item_at_offset(0).is_none());
+        assert!(summary.item_at_offset(src.len()).is_none());
+    }
+
+    #[test]
+    fn whitespace_only_lines_are_blank_lines() {
+        let src = "# Summary\n    \n[Intro](intro.md)\n \t \n- [First](first.md)\n  \n\
+                   - [Second](second.md)\n        \n[Outro](outro.md)\n";
+
+        let got = parse_summary_with_warnings(src);
+
+        assert!(got.errors.is_empty());
+        assert!(got.warnings.is_empty());
+        assert_eq!(got.summary.prefix_chapters.len(), 1);
+        assert_eq!(got.summary.numbered_chapters.len(), 2);
+        assert_eq!(got.summary.suffix_chapters.len(), 1);
+    }
+
+    #[test]
+    fn parse_front_matter() {
+        let src = "+++\ntitle = \"My Book\"\nauthors = [\"Jane Doe\"]\n+++\n\n# Summary\n\n\
+                   - [First](first.md)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let should_be = FrontMatter {
+            title: Some(String::from("My Book")),
+            authors: vec![String::from("Jane Doe")],
+            language: None,
+        };
+        assert_eq!(got.front_matter, Some(should_be));
+        assert_eq!(got.title, Some(String::from("Summary")));
+        assert_eq!(got.numbered_chapters.len(), 1);
+
+        let link = got.get_by_path("first.md").unwrap();
+        let span = link.span.unwrap();
+        assert_eq!(&src[span.start..span.end], "[First](first.md)");
+
+        let round_tripped = parse_summary(&got.to_string()).unwrap();
+        assert_eq!(round_tripped, got);
+    }
+
+    #[test]
+    fn invalid_front_matter_is_an_error() {
+        let src = "+++\nthis isn't toml\n+++\n\n- [First](first.md)\n";
+
+        let (partial, errors) = parse_summary_partial(src).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(partial.numbered_chapters.len(), 1);
+    }
+
+    #[test]
+    fn parse_a_link_with_a_title() {
+        let src = "- [Intro](intro.md \"The \\\"introduction\\\"\")\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let link = match got.numbered_chapters[0] {
+            SummaryItem::Link(ref link) => link.clone(),
+            ref other => panic!("Expected a link, got {:?}", other),
+        };
+        assert_eq!(link.location, PathBuf::from("intro.md"));
+        assert_eq!(link.title, Some(String::from("The \"introduction\"")));
+
+        // and it should survive being written back out
+        let round_tripped = parse_summary(&got.to_string()).unwrap();
+        assert_eq!(round_tripped, got);
+    }
+
+    #[test]
+    fn anchor_fragments_are_kept_separate_from_the_path() {
+        let src = "- [Setup](chapter_1.md#setup)\n- [No Fragment](chapter_2.md#)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let links: Vec<_> = links_in(&got.numbered_chapters)
+            .into_iter()
+            .map(|link| (link.location.clone(), link.fragment.clone()))
+            .collect();
+        let should_be = vec![
+            (PathBuf::from("chapter_1.md"), Some(String::from("setup"))),
+            (PathBuf::from("chapter_2.md"), None),
+        ];
+        assert_eq!(links, should_be);
+
+        let round_tripped = parse_summary(&got.to_string()).unwrap();
+        assert_eq!(round_tripped, got);
+    }
+
+    #[test]
+    fn reference_style_links_are_resolved() {
+        let src = "- [Chapter One][ch1]\n- [Chapter Two]\n\n[ch1]: ./ch1.md\n[Chapter Two]: ./ch2.md\n";
+
+        let got = parse_summary(src).unwrap();
+
+        let locations: Vec<_> = links_in(&got.numbered_chapters)
+            .into_iter()
+            .map(|link| link.location.clone())
+            .collect();
+        assert_eq!(
+            locations,
+            vec![PathBuf::from("./ch1.md"), PathBuf::from("./ch2.md")]
+        );
+        assert!(got.suffix_chapters.is_empty());
+    }
+
+    #[test]
+    fn parse_a_numbered_chapter() {
+        let src = "- [First](./first.md)\n";
+        let link = Link {
+            name: String::from("First"),
+            location: PathBuf::from("./first.md"),
+            number: Some(SectionNumber(vec![1])),
+            ..Default::default()
+        };
+        let should_be = vec![SummaryItem::Link(link)];
+
+        let mut parser = SummaryParser::new(src);
+        let got = parser.parse_numbered().unwrap();
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn parse_nested_numbered_chapters() {
+        let src = "- [First](./first.md)\n  - [Nested](./nested.md)\n- [Second](./second.md)";
+
+        let should_be = vec![
+            SummaryItem::Link(Link {
+                name: String::from("First"),
+                location: PathBuf::from("./first.md"),
+                number: Some(SectionNumber(vec![1])),
+                nested_items: vec![
+                    SummaryItem::Link(Link {
+                        name: String::from("Nested"),
+                        location: PathBuf::from("./nested.md"),
+                        number: Some(SectionNumber(vec![1, 1])),
+                        ..Default::default()
+                    }),
+                ],
+                ..Default::default()
+            }),
+            SummaryItem::Link(Link {
+                name: String::from("Second"),
+                location: PathBuf::from("./second.md"),
+                number: Some(SectionNumber(vec![2])),
+                ..Default::default()
+            }),
+        ];
+
+        let mut parser = SummaryParser::new(src);
+        let got = parser.parse_numbered().unwrap();
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn parse_a_draft_chapter() {
+        let src = "- [Coming soon]()\n";
+
+        let mut parser = SummaryParser::new(src);
+        let got = parser.parse_numbered().unwrap();
+
+        match got[0] {
+            SummaryItem::Link(ref link) => {
+                assert_eq!(link.name, "Coming soon");
+                assert!(link.is_draft());
+            }
+            ref other => panic!("Expected a link, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn html_comments_are_skipped() {
+        let src = "<!-- a comment before the prefix chapters -->\n\
+                   [Intro](intro.md) <!-- inline comment -->\n\n\
+                   - [First](first.md)\n\
+                   <!--\n\
+                   - [Commented Out](commented.md)\n\
+                   -->\n\
+                   - [Second](second.md) <!-- trailing comment -->\n\
+                   - <!-- [Also Commented Out](also.md) -->\n\
+                   - [Third](third.md)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        assert_eq!(got.prefix_chapters.len(), 1);
+        let numbered: Vec<_> = links_in(&got.numbered_chapters)
+            .into_iter()
+            .map(|link| (link.name.clone(), link.number.clone()))
+            .collect();
+        let should_be = vec![
+            (String::from("First"), Some(SectionNumber(vec![1]))),
+            (String::from("Second"), Some(SectionNumber(vec![2]))),
+            (String::from("Third"), Some(SectionNumber(vec![3]))),
+        ];
+        assert_eq!(numbered, should_be);
+    }
+
+    #[test]
+    fn mixing_tabs_and_spaces_is_a_warning() {
+        let src = "- [First](first.md)\n    - [Nested](nested.md)\n- [Second](second.md)\n\
+                   \t- [Tabbed](tabbed.md)\n";
+
+        let got = parse_summary_with_warnings(src);
+
+        assert!(got.errors.is_empty());
+        assert_eq!(got.warnings.len(), 1);
+        match *got.warnings[0].kind() {
+            ErrorKind::ParseError(line, _, ref kind) => {
+                assert_eq!(line, 4);
+                assert_eq!(*kind, ParseErrorKind::InconsistentIndentation { uses_tabs: true });
+            }
+            ref other => panic!("Unexpected error, {:?}", other),
+        }
+    }
+
+    #[test]
+    fn indentation_in_code_blocks_and_comments_is_ignored() {
+        let src = "- [First](first.md)\n    - [Nested](nested.md)\n\n```\n\t- tabbed\n```\n\n\
+                   <!--\n\t- [Old](old.md)\n-->\n";
+
+        let got = parse_summary_with_warnings(src);
+
+        assert!(got.warnings.is_empty());
+        let should_be = vec![false, false, false, true, true, true, false, true, true, true];
+        assert_eq!(literal_lines(src), should_be);
+    }
+
+    #[test]
+    fn consistent_tab_indentation_is_fine() {
+        let src = "- [First](first.md)\n\t- [Nested](nested.md)\n\t\t- [Deeper](deeper.md)\n";
+
+        assert!(parse_summary(src).is_ok());
+    }
+
+    #[test]
+    fn recoverable_problems_are_warnings() {
+        let src = "- [First](first.md)   \n- [](nameless.md)\n\n# Part II\n\n\
+                   5. [Second](second.md)\n";
+
+        let got = parse_summary_with_warnings(src);
+
+        assert!(got.errors.is_empty());
+        assert_eq!(got.warnings.len(), 2);
+        assert_eq!(got.summary.numbered_chapters.len(), 4);
+    }
+
+    #[test]
+    fn strict_mode_turns_warnings_into_errors() {
+        let src = "- [First](first.md)\n- [](second.md)\n";
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        let lenient = parse_summary_with_warnings(src);
+        let strict = parse_summary_with(src, &options);
+
+        assert!(lenient.errors.is_empty());
+        assert_eq!(lenient.warnings.len(), 1);
+        assert_eq!(strict.errors.len(), 1);
+        assert!(strict.warnings.is_empty());
+        assert_eq!(strict.summary, lenient.summary);
+    }
+
+    #[test]
+    fn over_indented_items_are_an_error() {
+        let src = "- [First](./first.md)\n      - [Second](./second.md)\n";
+
+        let mut parser = SummaryParser::new(src);
+        let got = parser.parse_numbered().unwrap();
+
+        assert_eq!(got.len(), 1);
+        assert_eq!(parser.errors.len(), 1);
+    }
+
+    #[test]
+    fn nested_items_can_use_a_different_indent_width() {
+        let src = "- [First](first.md)\n  - [Nested](nested.md)\n    - [Deeper](deeper.md)\n\
+                   - [Second](second.md)\n";
+        let options = ParseOptions {
+            indentation: Some(Indentation::Spaces(2)),
+            ..Default::default()
+        };
+
+        let got = parse_summary_with(src, &options);
+
+        assert!(got.errors.is_empty());
+        let depths: Vec<_> = got.summary.iter().map(|(_, depth)| depth).collect();
+        assert_eq!(depths, vec![0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn items_which_dont_match_the_indent_width_are_errors() {
+        let src = "- [First](first.md)\n   - [Odd](odd.md)\n- [Second](second.md)\n\
+                   \x20   - [Deep](deep.md)\n";
+        let options = ParseOptions {
+            indentation: Some(Indentation::Spaces(2)),
+            ..Default::default()
+        };
+
+        let got: Vec<_> = parse_summary_with(src, &options)
+            .errors
+            .iter()
+            .map(|e| match *e.kind() {
+                ErrorKind::ParseError(line, _, ref kind) => (line, kind.clone()),
+                ref other => panic!("Unexpected error: {}", other),
+            })
+            .collect();
+
+        let should_be = vec![
+            (2, ParseErrorKind::WrongIndentation(Indentation::Spaces(2))),
+            (4, ParseErrorKind::IndentedTooFar),
+        ];
+        assert_eq!(got, should_be);
+        assert!(parse_summary_with_warnings(src).errors.is_empty());
+    }
+
+    #[test]
+    fn nested_items_can_be_indented_with_tabs() {
+        let src = "- [First](first.md)\n\t- [Nested](nested.md)\n- [Second](second.md)\n\
+                   \x20 - [Spaces](spaces.md)\n- [Third](third.md)\n \t- [Mixed](mixed.md)\n";
+        let options = ParseOptions {
+            indentation: Some(Indentation::Tabs),
+            ..Default::default()
+        };
+
+        let got: Vec<_> = parse_summary_with(src, &options)
+            .errors
+            .iter()
+            .map(|e| match *e.kind() {
+                ErrorKind::ParseError(line, column, ref kind) => (line, column, kind.clone()),
+                ref other => panic!("Unexpected error: {}", other),
+            })
+            .collect();
+
+        let should_be = vec![
+            (4, 1, ParseErrorKind::WrongIndentation(Indentation::Tabs)),
+            (6, 1, ParseErrorKind::MixedIndentation),
+        ];
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn partial_summary_is_returned_alongside_errors() {
+        let src = "- [First](./first.md)\n- Not a link\n- [Second](./second.md)\n";
+
+        let (summary, errors) = parse_summary_partial(src).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        let names: Vec<_> = links_in(&summary.numbered_chapters)
+            .into_iter()
+            .map(|link| link.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["First", "Second"]);
+        assert!(parse_summary(src).is_err());
+    }
+
+    /// This test ensures the book will continue to pass because it breaks the
+    /// `SUMMARY.md` up using level 2 headers ([example]).
+    ///
+    /// [example]: https://github.com/rust-lang/book/blob/2c942dc094f4ddcdc7aba7564f80782801197c99/second-edition/src/SUMMARY.md#basic-rust-literacy
+    #[test]
+    fn can_have_a_subheader_between_nested_items() {
+        extern crate env_logger;
+        env_logger::init().ok();
+        let src = "- [First](./first.md)\n\n## Subheading\n\n- [Second](./second.md)\n";
+        let should_be = vec![
+            SummaryItem::Link(Link {
+                name: String::from("First"),
+                location: PathBuf::from("./first.md"),
+                number: Some(SectionNumber(vec![1])),
+                ..Default::default()
+            }),
+            SummaryItem::PartTitle(String::from("Subheading")),
+            SummaryItem::Link(Link {
+                name: String::from("Second"),
+                location: PathBuf::from("./second.md"),
+                number: Some(SectionNumber(vec![2])),
+                ..Default::default()
+            }),
+        ];
+
+        let mut parser = SummaryParser::new(src);
+        let got = parser.parse_numbered().unwrap();
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn separators_between_numbered_chapters_dont_affect_numbering() {
+        let src = "- [First](./first.md)\n\n---\n\n- [Second](./second.md)\n";
+        let should_be = vec![
+            SummaryItem::Link(Link {
+                name: String::from("First"),
+                location: PathBuf::from("./first.md"),
+                number: Some(SectionNumber(vec![1])),
+                ..Default::default()
+            }),
+            SummaryItem::Separator,
+            SummaryItem::Link(Link {
+                name: String::from("Second"),
+                location: PathBuf::from("./second.md"),
+                number: Some(SectionNumber(vec![2])),
+                ..Default::default()
+            }),
+        ];
+
+        let mut parser = SummaryParser::new(src);
+        let got = parser.parse_numbered().unwrap();
+
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn part_titles_can_come_before_the_first_numbered_chapter() {
+        let src = "# Summary\n\n[Intro](./intro.md)\n\n# Part I\n\n- [First](./first.md)\n\n---\n\n\
+                   # Part II\n\n- [Second](./second.md)\n";
+
+        let got = parse_summary(src).unwrap();
+
+        assert_eq!(got.title, Some(String::from("Summary")));
+        assert_eq!(got.prefix_chapters.len(), 1);
+        assert_eq!(got.numbered_chapters.len(), 5);
+        assert_eq!(
+            got.numbered_chapters[0],
+            SummaryItem::PartTitle(String::from("Part I"))
+        );
+        assert_eq!(got.numbered_chapters[2], SummaryItem::Separator);
+        assert_eq!(
+            got.numbered_chapters[3],
+            SummaryItem::PartTitle(String::from("Part II"))
+        );
+
+        // numbering carries on across parts
+        match got.numbered_chapters[4] {
+            SummaryItem::Link(ref link) => assert_eq!(link.number, Some(SectionNumber(vec![2]))),
+            ref other => panic!("Expected a link, got {:?}", other),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,216 @@
+//! Loading a multilingual book, where each language has its own directory
+//! (e.g. `src/en` and `src/fr`) with its own `SUMMARY.md`.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::book::{load_book, Book, BookItem};
+use config::BuildConfig;
+use errors::*;
+
+
+/// Every translation of a book, keyed by the name of its language's
+/// directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translations {
+    /// The language every other one is translated from.
+    pub default_language: String,
+    /// The book for each language.
+    pub books: BTreeMap<String, Book>,
+}
+
+/// Which chapters of the default language each of the other languages is
+/// missing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranslationReport {
+    /// The language every other one is translated from.
+    pub default_language: String,
+    /// The paths of the chapters each language has no translation for.
+    pub untranslated: BTreeMap<String, Vec<PathBuf>>,
+}
+
+/// Where the reader of a chapter can find it in one of the book's languages,
+/// for renderers to build a language switcher from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageLink {
+    /// The language's name.
+    pub language: String,
+    /// The chapter's translation, relative to the top-level `src` directory,
+    /// or `None` if it hasn't been translated into this language yet.
+    pub path: Option<PathBuf>,
+}
+
+impl Translations {
+    /// Load a book for every directory in `src_dir` which has its own
+    /// `SUMMARY.md`, using the same build settings for all of them.
+    pub fn load<P: AsRef<Path>>(
+        src_dir: P,
+        default_language: &str,
+        cfg: &BuildConfig,
+    ) -> Result<Translations> {
+        let src_dir = src_dir.as_ref();
+        let mut books = BTreeMap::new();
+
+        let entries = fs::read_dir(src_dir)
+            .chain_err(|| format!("Unable to read {}", src_dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if !path.join("SUMMARY.md").is_file() {
+                continue;
+            }
+
+            let language = match path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            debug!("[*] Loading the {} translation", language);
+            let book = load_book(&path, cfg)
+                .chain_err(|| format!("Unable to load the {} translation", language))?;
+            books.insert(language, book);
+        }
+
+        if !books.contains_key(default_language) {
+            bail!(
+                "There is no SUMMARY.md for the default language in {}",
+                src_dir.join(default_language).display()
+            );
+        }
+
+        Ok(Translations {
+            default_language: default_language.to_string(),
+            books: books,
+        })
+    }
+
+    /// Get the book for the default language.
+    pub fn default_book(&self) -> &Book {
+        &self.books[&self.default_language]
+    }
+
+    /// Find the chapters in the default language which each of the other
+    /// languages are missing.
+    pub fn report(&self) -> TranslationReport {
+        let untranslated = self.books
+            .iter()
+            .filter(|&(language, _)| *language != self.default_language)
+            .map(|(language, book)| {
+                let translated = chapter_paths(book);
+                let missing = chapter_paths(self.default_book())
+                    .into_iter()
+                    .filter(|path| !translated.contains(path))
+                    .collect();
+                (language.clone(), missing)
+            })
+            .collect();
+
+        TranslationReport {
+            default_language: self.default_language.clone(),
+            untranslated: untranslated,
+        }
+    }
+
+    /// Get a link to the chapter at `path` (relative to its language's
+    /// directory) in every language.
+    pub fn language_links<P: AsRef<Path>>(&self, path: P) -> Vec<LanguageLink> {
+        let path = path.as_ref();
+
+        self.books
+            .iter()
+            .map(|(language, book)| {
+                let translated = if chapter_paths(book).iter().any(|p| p == path) {
+                    Some(Path::new(language).join(path))
+                } else {
+                    None
+                };
+
+                LanguageLink {
+                    language: language.clone(),
+                    path: translated,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Display for TranslationReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (language, missing) in &self.untranslated {
+            if missing.is_empty() {
+                writeln!(f, "{}: fully translated", language)?;
+                continue;
+            }
+
+            writeln!(f, "{}: {} untranslated chapter(s)", language, missing.len())?;
+            for path in missing {
+                writeln!(f, "    {}", path.display())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The paths of every (non-draft) chapter in the book.
+fn chapter_paths(book: &Book) -> Vec<PathBuf> {
+    book.iter()
+        .filter_map(|item| match *item {
+            BookItem::Chapter(ref ch) if !ch.is_draft() => Some(ch.path.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    fn create_language(src_dir: &Path, language: &str, chapters: &[&str]) {
+        let dir = src_dir.join(language);
+        fs::create_dir(&dir).unwrap();
+
+        let mut summary = File::create(dir.join("SUMMARY.md")).unwrap();
+        for chapter in chapters {
+            writeln!(summary, "- [{}]({})", chapter, chapter).unwrap();
+            File::create(dir.join(chapter)).unwrap();
+        }
+    }
+
+    #[test]
+    fn report_untranslated_chapters() {
+        let temp = TempDir::new("book").unwrap();
+        create_language(temp.path(), "en", &["intro.md", "setup.md"]);
+        create_language(temp.path(), "fr", &["intro.md"]);
+
+        let translations = Translations::load(temp.path(), "en", &BuildConfig::default()).unwrap();
+        let report = translations.report();
+
+        assert_eq!(report.untranslated.len(), 1);
+        assert_eq!(report.untranslated["fr"], vec![PathBuf::from("setup.md")]);
+
+        let links = translations.language_links("setup.md");
+        let should_be = vec![
+            LanguageLink {
+                language: String::from("en"),
+                path: Some(PathBuf::from("en/setup.md")),
+            },
+            LanguageLink {
+                language: String::from("fr"),
+                path: None,
+            },
+        ];
+        assert_eq!(links, should_be);
+    }
+
+    #[test]
+    fn the_default_language_must_exist() {
+        let temp = TempDir::new("book").unwrap();
+        create_language(temp.path(), "fr", &["intro.md"]);
+
+        assert!(Translations::load(temp.path(), "en", &BuildConfig::default()).is_err());
+    }
+}
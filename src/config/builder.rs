@@ -0,0 +1,734 @@
+//! Building a `Config` out of several layers while remembering where each
+//! value came from.
+
+use std::env;
+use std::ffi::OsString;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use toml::{self, Value};
+use toml::value::Table;
+use serde::Serialize;
+use serde_json;
+#[cfg(feature = "yaml")]
+use serde_yaml;
+
+use errors::*;
+use utils;
+use super::{as_table, inline_value, path_to_string, recursive_get, recursive_get_mut, Config};
+use super::environment::env_overrides;
+use super::json::{gitbook_to_table, is_native_json};
+
+
+/// The layers a `Config` is built from, in order of precedence (each one
+/// overrides the ones before it).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConfigSource {
+    /// `Config::default()`.
+    Default,
+    /// The `book.toml` file.
+    File,
+    /// The `[profile.<name>]` table selected with
+    /// `ConfigBuilder::with_profile()`.
+    Profile,
+    /// An `MDBOOK_*` environment variable.
+    Environment,
+    /// A command-line flag, like `--dest-dir`.
+    CommandLine,
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match *self {
+            ConfigSource::Default => "the defaults",
+            ConfigSource::File => "book.toml",
+            ConfigSource::Profile => "the selected profile",
+            ConfigSource::Environment => "the environment",
+            ConfigSource::CommandLine => "the command line",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// One of the values in the config and the layer it came from, as returned
+/// by `ConfigBuilder::explain()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigValue {
+    /// The value's dotted key, e.g. `build.build-dir`.
+    pub key: String,
+    /// The value itself.
+    pub value: Value,
+    /// The layer which set it.
+    pub source: ConfigSource,
+}
+
+impl Display for ConfigValue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} = {} (from {})", self.key, inline_value(&self.value), self.source)
+    }
+}
+
+/// A value set by one of the layers above the config file.
+#[derive(Debug, Clone, PartialEq)]
+struct Override {
+    source: ConfigSource,
+    key: String,
+    value: Value,
+    origin: String,
+}
+
+/// Builds a `Config` out of the defaults, `book.toml`, environment variables
+/// and command-line flags, in that order of precedence, while keeping track
+/// of where each value came from.
+///
+/// ```rust
+/// # extern crate mdbook;
+/// # use mdbook::config::{ConfigBuilder, ConfigSource};
+/// # fn run() -> mdbook::errors::Result<()> {
+/// let mut builder = ConfigBuilder::new();
+/// builder
+///     .with_str("[build]\nbuild-dir = \"from-the-file\"")?
+///     .set("build.build-dir", "from-a-flag")?;
+///
+/// let config = builder.build()?;
+/// assert_eq!(config.build.build_dir.to_str(), Some("from-a-flag"));
+/// assert_eq!(builder.source_of("build.build-dir"), ConfigSource::CommandLine);
+/// assert_eq!(builder.source_of("build.create-missing"), ConfigSource::Default);
+/// # Ok(())
+/// # }
+/// # fn main() { run().unwrap() }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigBuilder {
+    file: Option<(String, Table)>,
+    profile: Option<String>,
+    overrides: Vec<Override>,
+}
+
+impl ConfigBuilder {
+    /// Create a builder which will only use the defaults.
+    pub fn new() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Use the config file at `path` (normally `book.toml`).
+    ///
+    /// If the file has a top-level `extends = "path/to/parent.toml"` key
+    /// (relative to the file), the parent is loaded first and the file's own
+    /// values are merged over it. Parents can extend other files in turn.
+    pub fn with_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut ConfigBuilder> {
+        let path = path.as_ref();
+
+        let canonical = path.canonicalize()
+            .chain_err(|| format!("Unable to open {}", path.display()))?;
+        let table = load_extended(path, &mut vec![canonical])?;
+
+        self.file = Some((path.display().to_string(), table));
+        Ok(self)
+    }
+
+    /// Use the config in a book's root directory. This is normally
+    /// `book.toml`, but if there isn't one then `book.yaml` (with the `yaml`
+    /// feature) or `book.json` is used instead. Without the `yaml` feature,
+    /// YAML files are never picked up.
+    ///
+    /// Unlike YAML, JSON support isn't behind a feature because `serde_json`
+    /// is needed by the HTML renderer anyway, so it costs nothing extra.
+    pub fn with_book_dir<P: AsRef<Path>>(&mut self, book_root: P) -> Result<&mut ConfigBuilder> {
+        let book_root = book_root.as_ref();
+        let candidates: &[&str] = if cfg!(feature = "yaml") {
+            &["book.toml", "book.yaml", "book.yml", "book.json"]
+        } else {
+            &["book.toml", "book.json"]
+        };
+
+        let mut found = candidates
+            .iter()
+            .map(|name| book_root.join(name))
+            .filter(|path| path.exists());
+        let path = match found.next() {
+            Some(path) => path,
+            None => return Ok(self),
+        };
+        for ignored in found {
+            warn!(
+                "Ignoring {}, the configuration in {} is used instead",
+                ignored.display(),
+                path.display()
+            );
+        }
+
+        debug!("[*] Loading config from {}", path.display());
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => self.with_book_json(&path),
+            Some("yaml") | Some("yml") => self.with_yaml_file(&path),
+            _ => self.with_file(&path),
+        }
+    }
+
+    /// Use a `book.json`, which can either have exactly the same layout as
+    /// `book.toml` or be a GitBook-style config. For a GitBook config, the
+    /// keys which mdBook has an equivalent for are mapped onto the normal
+    /// config, and the rest are ignored with a warning.
+    pub fn with_book_json<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut ConfigBuilder> {
+        let path = path.as_ref();
+
+        let file = File::open(path).chain_err(|| format!("Unable to open {}", path.display()))?;
+        let json: serde_json::Value = serde_json::from_reader(file)
+            .chain_err(|| format!("{} isn't valid JSON", path.display()))?;
+
+        let table = if is_native_json(&json) {
+            as_table(&json).chain_err(|| {
+                format!("{} has values with no TOML equivalent (like null)", path.display())
+            })?
+        } else {
+            warn!("It appears you are still using a GitBook-style book.json for configuration.");
+            warn!("It will be used for now, but you should migrate to book.toml.");
+            warn!("Check the user guide for migration information:");
+            warn!("\thttps://rust-lang-nursery.github.io/mdBook/format/config.html");
+
+            gitbook_to_table(&json)?
+        };
+
+        self.file = Some((path.display().to_string(), table));
+        Ok(self)
+    }
+
+    /// Use a `book.yaml`, which has exactly the same layout as `book.toml`.
+    #[cfg(feature = "yaml")]
+    pub fn with_yaml_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut ConfigBuilder> {
+        let path = path.as_ref();
+
+        let file = File::open(path).chain_err(|| format!("Unable to open {}", path.display()))?;
+        let yaml: serde_yaml::Value = serde_yaml::from_reader(file)
+            .chain_err(|| format!("{} isn't valid YAML", path.display()))?;
+        let table = as_table(&yaml).chain_err(|| {
+            format!("{} has values with no TOML equivalent (like null)", path.display())
+        })?;
+
+        self.file = Some((path.display().to_string(), table));
+        Ok(self)
+    }
+
+    /// Use a `book.yaml`, which needs mdbook to be built with the `yaml`
+    /// feature.
+    #[cfg(not(feature = "yaml"))]
+    pub fn with_yaml_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut ConfigBuilder> {
+        bail!(
+            "Unable to load {}, YAML configs need mdbook to be built with the `yaml` feature",
+            path.as_ref().display()
+        )
+    }
+
+    /// Use a config file's contents which have already been read into memory.
+    pub fn with_str(&mut self, src: &str) -> Result<&mut ConfigBuilder> {
+        self.with_named_str("book.toml", src)
+    }
+
+    fn with_named_str(&mut self, name: &str, src: &str) -> Result<&mut ConfigBuilder> {
+        let table: Table = toml::from_str(src).chain_err(|| format!("{} isn't valid TOML", name))?;
+        if table.contains_key("extends") {
+            warn!("`extends` only works for config files loaded from disk");
+        }
+
+        self.file = Some((name.to_string(), table));
+        Ok(self)
+    }
+
+    /// Overlay the `[profile.<name>]` table from the config file (e.g.
+    /// `[profile.release]`) on top of the rest of the file. The profile
+    /// table can contain anything the file itself can, like
+    /// `[profile.release.build]`.
+    pub fn with_profile<S: Into<String>>(&mut self, name: S) -> &mut ConfigBuilder {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Use any `MDBOOK_*` environment variables, as described in
+    /// `Config::update_from_env()`.
+    pub fn with_env(&mut self) -> Result<&mut ConfigBuilder> {
+        self.with_vars(env::vars_os())
+    }
+
+    fn with_vars<I, K, V>(&mut self, vars: I) -> Result<&mut ConfigBuilder>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        for (name, key, value) in env_overrides(vars) {
+            self.overrides.push(Override {
+                source: ConfigSource::Environment,
+                key: key,
+                value: value,
+                origin: format!("the {} environment variable", name),
+            });
+        }
+
+        Ok(self)
+    }
+
+    /// Set a value from a command-line flag, which takes precedence over
+    /// every other layer.
+    pub fn set<S: Serialize, I: AsRef<str>>(
+        &mut self,
+        index: I,
+        value: S,
+    ) -> Result<&mut ConfigBuilder> {
+        let index = index.as_ref();
+        let value = Value::try_from(value)
+            .chain_err(|| format!("Unable to represent the value of `{}` as TOML", index))?;
+
+        self.overrides.push(Override {
+            source: ConfigSource::CommandLine,
+            key: index.to_string(),
+            value: value,
+            origin: String::from("the command line"),
+        });
+
+        Ok(self)
+    }
+
+    /// Merge every layer into a `Config`. If a value is invalid, the error
+    /// says which layer set it.
+    pub fn build(&self) -> Result<Config> {
+        let mut config = match self.file {
+            Some((ref name, ref table)) => {
+                let mut table = table.clone();
+                let context = match self.profile {
+                    Some(ref profile) => {
+                        apply_profile(&mut table, profile);
+                        format!("Invalid configuration in {} (using the {} profile)", name, profile)
+                    }
+                    None => format!("Invalid configuration in {}", name),
+                };
+
+                Value::Table(table).try_into().chain_err(|| context)?
+            }
+            None => {
+                if let Some(ref profile) = self.profile {
+                    warn!("There is no config file, so the {} profile can't be used", profile);
+                }
+                Config::default()
+            }
+        };
+
+        let mut overrides: Vec<&Override> = self.overrides.iter().collect();
+        overrides.sort_by_key(|o| o.source);
+
+        for o in overrides {
+            debug!("[*] Setting `{}` from {}", o.key, o.origin);
+            config
+                .set(&o.key, o.value.clone())
+                .chain_err(|| format!("Invalid value for `{}` from {}", o.key, o.origin))?;
+        }
+
+        Ok(config)
+    }
+
+    /// Build the `Config` and list every value in it (sorted by key) along
+    /// with the layer it came from, to help work out why a setting isn't
+    /// taking effect.
+    pub fn explain(&self) -> Result<Vec<ConfigValue>> {
+        let config = self.build()?;
+
+        let mut table = config.rest.clone();
+        table.insert("book".to_string(), Value::Table(as_table(&config.book)?));
+        table.insert("build".to_string(), Value::Table(as_table(&config.build)?));
+
+        let mut values = Vec::new();
+        flatten(&table, "", &mut values);
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(values
+            .into_iter()
+            .map(|(key, value)| ConfigValue {
+                source: self.source_of(&key),
+                key: key,
+                value: value,
+            })
+            .collect())
+    }
+
+    /// Find out which layer the value at `index` (a dotted key, as used by
+    /// `Config::get()`) comes from.
+    pub fn source_of<S: AsRef<str>>(&self, index: S) -> ConfigSource {
+        let index = index.as_ref();
+
+        let overridden = self.overrides
+            .iter()
+            .filter(|o| index == o.key || index.starts_with(&format!("{}.", o.key)))
+            .map(|o| o.source)
+            .max();
+        if let Some(source) = overridden {
+            return source;
+        }
+
+        if let Some((_, ref table)) = self.file {
+            if let Some(ref profile) = self.profile {
+                let key = format!("profile.{}.{}", profile, index);
+                let pieces: Vec<_> = key.split(".").collect();
+                if recursive_get(&pieces, table).is_some() {
+                    return ConfigSource::Profile;
+                }
+            }
+
+            let pieces: Vec<_> = index.split(".").collect();
+            if recursive_get(&pieces, table).is_some() {
+                return ConfigSource::File;
+            }
+        }
+
+        ConfigSource::Default
+    }
+}
+
+/// Collect every non-table value in `table` along with its dotted key, where
+/// `prefix` is the key of the table itself.
+fn flatten(table: &Table, prefix: &str, values: &mut Vec<(String, Value)>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match *value {
+            Value::Table(ref nested) => flatten(nested, &key, values),
+            _ => values.push((key, value.clone())),
+        }
+    }
+}
+
+/// Read the config file at `path` as a TOML table.
+fn read_table(path: &Path) -> Result<Table> {
+    let mut buffer = String::new();
+    File::open(path)
+        .chain_err(|| format!("Unable to open {}", path.display()))?
+        .read_to_string(&mut buffer)
+        .chain_err(|| format!("Couldn't read {}", path.display()))?;
+
+    toml::from_str(&buffer).chain_err(|| format!("{} isn't valid TOML", path.display()))
+}
+
+/// Read the config file at `path`, merged over the file it `extends` (if
+/// any). `seen` holds the canonical path of every file in the chain so far,
+/// to catch files which end up extending themselves.
+fn load_extended(path: &Path, seen: &mut Vec<PathBuf>) -> Result<Table> {
+    let mut table = read_table(path)?;
+
+    let parent = match table.remove("extends") {
+        Some(Value::String(parent)) => parent,
+        Some(_) => bail!("`extends` in {} should be the path to a config file", path.display()),
+        None => return Ok(table),
+    };
+
+    let parent = path.parent().unwrap_or_else(|| Path::new("")).join(parent);
+    debug!("[*] {} extends {}", path.display(), parent.display());
+
+    let canonical = parent.canonicalize().chain_err(|| {
+        format!("Unable to find {}, which {} extends", parent.display(), path.display())
+    })?;
+    if seen.contains(&canonical) {
+        bail!("{} ends up extending itself", parent.display());
+    }
+    let parent_dir = canonical.parent().map(Path::to_path_buf).unwrap_or_default();
+    seen.push(canonical);
+
+    let mut merged = load_extended(&parent, seen)?;
+    rebase_paths(&mut merged, &parent_dir)?;
+    merge_tables(&mut merged, table);
+    Ok(merged)
+}
+
+/// The config keys which hold paths (or lists of paths).
+const PATH_KEYS: &'static [&'static str] = &[
+    "book.src",
+    "build.build-dir",
+    "output.html.theme",
+    "output.html.additional-css",
+    "output.html.additional-js",
+    "output.html.playpen.editor",
+];
+
+/// Turn the relative paths in a file which is being extended into absolute
+/// ones inside `dir`, the directory it's in, so they don't end up relative
+/// to the book which extends it.
+fn rebase_paths(table: &mut Table, dir: &Path) -> Result<()> {
+    let rebase = |path: &mut String, key: &str| -> Result<()> {
+        let rebased = utils::fs::collapse_path(dir.join(path.as_str()));
+        *path = path_to_string(&rebased, key)?;
+        Ok(())
+    };
+
+    for &key in PATH_KEYS {
+        let pieces: Vec<&str> = key.split('.').collect();
+
+        match recursive_get_mut(&pieces, table) {
+            Some(&mut Value::String(ref mut path)) => rebase(path, key)?,
+            Some(&mut Value::Array(ref mut paths)) => for path in paths {
+                if let Value::String(ref mut path) = *path {
+                    rebase(path, key)?;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Merge the `[profile.<name>]` table into the rest of the config file's
+/// table.
+fn apply_profile(table: &mut Table, name: &str) {
+    let profile = table
+        .get("profile")
+        .and_then(|profiles| profiles.as_table())
+        .and_then(|profiles| profiles.get(name))
+        .cloned();
+
+    match profile {
+        Some(Value::Table(profile)) => {
+            debug!("[*] Using the {} profile", name);
+            merge_tables(table, profile);
+        }
+        Some(_) => warn!("Ignoring the {} profile, `profile.{}` should be a table", name, name),
+        None => warn!("There is no `[profile.{}]` table, using the normal configuration", name),
+    }
+}
+
+/// Recursively copy every item in `overlay` into `base`, replacing whatever
+/// was already there unless both are tables.
+fn merge_tables(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        let both_tables = value.is_table() && base.get(&key).map(|v| v.is_table()) == Some(true);
+        if !both_tables {
+            base.insert(key, value);
+            continue;
+        }
+
+        if let (Some(&mut Value::Table(ref mut existing)), Value::Table(nested)) =
+            (base.get_mut(&key), value)
+        {
+            merge_tables(existing, nested);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn the_builder_skips_environment_variables_which_arent_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let vars = vec![(OsString::from("MDBOOK_BOOK__TITLE"), OsString::from_vec(vec![0xff]))];
+
+        let mut builder = ConfigBuilder::new();
+        builder.with_vars(vars).unwrap();
+
+        assert_eq!(builder.source_of("book.title"), ConfigSource::Default);
+        assert_eq!(builder.build().unwrap().book.title, None);
+    }
+
+    #[test]
+    fn later_layers_take_precedence() {
+        let src = r#"
+        [book]
+        title = "From the file"
+        description = "Also from the file"
+
+        [build]
+        build-dir = "from-the-file"
+        "#;
+        let vars = vec![
+            (String::from("MDBOOK_BOOK__TITLE"), String::from("From the environment")),
+            (String::from("MDBOOK_BUILD__BUILD_DIR"), String::from("from-the-environment")),
+        ];
+
+        let mut builder = ConfigBuilder::new();
+        builder
+            .set("build.build-dir", "from-a-flag")
+            .unwrap()
+            .with_str(src)
+            .unwrap()
+            .with_vars(vars)
+            .unwrap();
+        let cfg = builder.build().unwrap();
+
+        assert_eq!(cfg.book.title, Some(String::from("From the environment")));
+        assert_eq!(cfg.book.description, Some(String::from("Also from the file")));
+        assert_eq!(cfg.build.build_dir, PathBuf::from("from-a-flag"));
+
+        assert_eq!(builder.source_of("build.build-dir"), ConfigSource::CommandLine);
+        assert_eq!(builder.source_of("book.title"), ConfigSource::Environment);
+        assert_eq!(builder.source_of("book.description"), ConfigSource::File);
+        assert_eq!(builder.source_of("book.src"), ConfigSource::Default);
+    }
+
+    #[test]
+    fn profiles_are_overlaid_on_the_config_file() {
+        let src = r#"
+        [book]
+        title = "My Book"
+
+        [build]
+        build-dir = "book"
+        create-missing = true
+
+        [profile.release.build]
+        build-dir = "public"
+        deny-warnings = true
+
+        [profile.release.output.html]
+        google-analytics = "123456"
+        "#;
+
+        let mut builder = ConfigBuilder::new();
+        builder.with_str(src).unwrap();
+        let dev = builder.build().unwrap();
+        let release = builder.with_profile("release").build().unwrap();
+
+        assert_eq!(dev.build.build_dir, PathBuf::from("book"));
+        assert_eq!(release.build.build_dir, PathBuf::from("public"));
+        assert!(release.build.deny_warnings);
+        assert!(release.build.create_missing);
+        assert_eq!(release.book.title, Some(String::from("My Book")));
+        assert_eq!(
+            release.html_config().unwrap().google_analytics,
+            Some(String::from("123456"))
+        );
+
+        assert_eq!(builder.source_of("build.build-dir"), ConfigSource::Profile);
+        assert_eq!(builder.source_of("build.create-missing"), ConfigSource::File);
+    }
+
+    #[test]
+    fn a_config_file_can_extend_another() {
+        let temp = TempDir::new("workspace").unwrap();
+        fs::create_dir(temp.path().join("common")).unwrap();
+        fs::create_dir(temp.path().join("book")).unwrap();
+
+        let common = temp.path().join("common").join("book.toml");
+        File::create(&common)
+            .unwrap()
+            .write_all(
+                b"[build]\ncreate-missing = false\n\n\
+                  [output.html]\ntheme = \"shared-theme\"\ncurly-quotes = true\n",
+            )
+            .unwrap();
+        let book = temp.path().join("book").join("book.toml");
+        File::create(&book)
+            .unwrap()
+            .write_all(
+                b"extends = \"../common/book.toml\"\n\n\
+                  [book]\ntitle = \"Local\"\n\n[output.html]\ncurly-quotes = false\n",
+            )
+            .unwrap();
+
+        let cfg = ConfigBuilder::new().with_file(&book).unwrap().build().unwrap();
+        let html = cfg.html_config().unwrap();
+
+        assert_eq!(cfg.book.title, Some(String::from("Local")));
+        assert!(!cfg.build.create_missing);
+        let common_dir = temp.path().canonicalize().unwrap().join("common");
+        assert_eq!(html.theme, Some(common_dir.join("shared-theme")));
+        assert!(!html.curly_quotes);
+        assert!(cfg.get("extends").is_none());
+    }
+
+    #[test]
+    fn config_files_cant_extend_themselves() {
+        let temp = TempDir::new("workspace").unwrap();
+        let book = temp.path().join("book.toml");
+        File::create(&book)
+            .unwrap()
+            .write_all(b"extends = \"./book.toml\"\n")
+            .unwrap();
+
+        assert!(ConfigBuilder::new().with_file(&book).is_err());
+    }
+
+    #[test]
+    fn explain_where_each_value_came_from() {
+        let vars = vec![(String::from("MDBOOK_BOOK__TITLE"), String::from("Env"))];
+
+        let mut builder = ConfigBuilder::new();
+        builder
+            .with_str("[build]\nmax-depth = 2\n\n[output.random]\nfoo = 5\n")
+            .unwrap()
+            .with_vars(vars)
+            .unwrap()
+            .set("build.build-dir", "out")
+            .unwrap();
+        let explained = builder.explain().unwrap();
+
+        let find = |key: &str| explained.iter().find(|v| v.key == key).unwrap().clone();
+        assert_eq!(find("book.title").source, ConfigSource::Environment);
+        assert_eq!(find("build.build-dir").source, ConfigSource::CommandLine);
+        assert_eq!(find("build.max-depth").source, ConfigSource::File);
+        assert_eq!(find("build.create-missing").source, ConfigSource::Default);
+        assert_eq!(find("output.random.foo").value, Value::Integer(5));
+        assert_eq!(
+            find("build.build-dir").to_string(),
+            "build.build-dir = \"out\" (from the command line)"
+        );
+
+        let keys: Vec<_> = explained.iter().map(|v| v.key.clone()).collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+    }
+
+    #[test]
+    fn errors_say_which_layer_set_a_bad_value() {
+        let vars = vec![(String::from("MDBOOK_BUILD__MAX_DEPTH"), String::from("deep"))];
+
+        let mut builder = ConfigBuilder::new();
+        builder.with_vars(vars).unwrap();
+        let err = builder.build().unwrap_err();
+
+        assert!(err.to_string().contains("MDBOOK_BUILD__MAX_DEPTH"));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn load_a_yaml_config() {
+        let temp = TempDir::new("book").unwrap();
+        let path = temp.path().join("book.yaml");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"book:\n  title: From YAML\nbuild:\n  max-depth: 2\n")
+            .unwrap();
+
+        let cfg = ConfigBuilder::new().with_book_dir(temp.path()).unwrap().build().unwrap();
+
+        assert_eq!(cfg.book.title, Some(String::from("From YAML")));
+        assert_eq!(cfg.build.max_depth, Some(2));
+    }
+
+    #[cfg(not(feature = "yaml"))]
+    #[test]
+    fn yaml_configs_are_ignored_without_the_feature() {
+        let temp = TempDir::new("book").unwrap();
+        File::create(temp.path().join("book.yaml"))
+            .unwrap()
+            .write_all(b"book:\n  title: From YAML\n")
+            .unwrap();
+        File::create(temp.path().join("book.json"))
+            .unwrap()
+            .write_all(br#"{"book": {"title": "From JSON"}}"#)
+            .unwrap();
+
+        let cfg = ConfigBuilder::new().with_book_dir(temp.path()).unwrap().build().unwrap();
+
+        assert_eq!(cfg.book.title, Some(String::from("From JSON")));
+    }
+}
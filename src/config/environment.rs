@@ -0,0 +1,228 @@
+//! Overriding the config with `MDBOOK_*` environment variables.
+
+use std::ffi::OsString;
+use toml::Value;
+use serde_json;
+
+use super::{update_typed, BookConfig, BuildConfig, BOOK_KEYS, BUILD_KEYS, HTML_KEYS, LINT_KEYS};
+
+
+/// Find the `MDBOOK_*` variables in `vars`, returning the name of each one
+/// along with the config key it overrides and its parsed value.
+pub fn env_overrides<I, K, V>(vars: I) -> Vec<(String, String, Value)>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<OsString>,
+    V: Into<OsString>,
+{
+    let mut overrides = Vec::new();
+
+    for (name, value) in vars {
+        let (name, value) = (name.into(), value.into());
+        let lossy_name = name.to_string_lossy().into_owned();
+        let key = match parse_env_key(&lossy_name) {
+            Some(key) => key,
+            None => continue,
+        };
+
+        let value = match (name.to_str(), value.into_string()) {
+            (Some(_), Ok(value)) => value,
+            _ => {
+                warn!(
+                    "Ignoring the {} environment variable because it isn't valid UTF-8",
+                    lossy_name
+                );
+                continue;
+            }
+        };
+        debug!("[*] Setting `{}` from {}", key, lossy_name);
+
+        let value = parse_env_value(&key, &value);
+        overrides.push((lossy_name, key, value));
+    }
+
+    overrides
+}
+
+/// Turn the name of an `MDBOOK_*` environment variable into the config key
+/// it overrides.
+///
+/// Pieces of the name are only turned into kebab-case when that gives one of
+/// mdbook's own keys, so keys for other tools keep their underscores.
+fn parse_env_key(name: &str) -> Option<String> {
+    const PREFIX: &'static str = "MDBOOK_";
+
+    if !name.starts_with(PREFIX) || name.len() == PREFIX.len() {
+        return None;
+    }
+
+    let mut key = String::new();
+    for piece in name[PREFIX.len()..].to_lowercase().split("__") {
+        let dashed = piece.replace("_", "-");
+        let piece = if known_keys(&key).iter().any(|known| *known == dashed) {
+            dashed
+        } else {
+            piece.to_string()
+        };
+
+        if !key.is_empty() {
+            key.push('.');
+        }
+        key.push_str(&piece);
+    }
+
+    if key == "book" || key == "build" {
+        warn!(
+            "Ignoring the {} environment variable because it would replace the whole `{}` \
+             table, set one of the keys inside it instead",
+            name, key
+        );
+        return None;
+    }
+
+    Some(key)
+}
+
+/// The keys mdbook itself knows about in the table called `table`.
+fn known_keys(table: &str) -> &'static [&'static str] {
+    match table {
+        "book" => BOOK_KEYS,
+        "build" => BUILD_KEYS,
+        "build.lints" => LINT_KEYS,
+        "output.html" => HTML_KEYS,
+        _ => &[],
+    }
+}
+
+/// Parse an environment variable's value as JSON, falling back to a plain
+/// string when it isn't JSON, can't be represented as TOML (like `null`), or
+/// has the wrong type for one of mdbook's own settings. That way
+/// `MDBOOK_BOOK__TITLE=1984` gives the book a title of "1984".
+fn parse_env_value(key: &str, value: &str) -> Value {
+    let raw = Value::String(value.to_string());
+
+    let parsed = match serde_json::from_str::<serde_json::Value>(value) {
+        Ok(json) => match Value::try_from(json) {
+            Ok(parsed) => parsed,
+            Err(_) => return raw,
+        },
+        Err(_) => return raw,
+    };
+
+    if parsed.is_str() || fits_typed_config(key, &parsed) {
+        parsed
+    } else {
+        raw
+    }
+}
+
+/// Would setting `key` to `value` work, as far as the `book` and `build`
+/// tables are concerned? Anything else can hold whatever it likes.
+fn fits_typed_config(key: &str, value: &Value) -> bool {
+    let pieces: Vec<_> = key.split('.').collect();
+
+    match pieces[0] {
+        "book" => update_typed(&BookConfig::default(), key, &pieces[1..], value.clone()).is_ok(),
+        "build" => {
+            update_typed(&BuildConfig::default(), key, &pieces[1..], value.clone()).is_ok()
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use config::Config;
+
+    #[test]
+    fn environment_variables_override_the_config() {
+        let src = r#"
+        [book]
+        title = "Original"
+
+        [output.html]
+        curly-quotes = false
+        "#;
+        let vars = vec![
+            ("MDBOOK_BOOK__TITLE", "From the environment"),
+            ("MDBOOK_BUILD__BUILD_DIR", "\"out\""),
+            ("MDBOOK_BUILD__MAX_DEPTH", "2"),
+            ("MDBOOK_OUTPUT__HTML__CURLY_QUOTES", "true"),
+            ("MDBOOK_OUTPUT__RANDOM__FOO", "[1, 2]"),
+            ("MDBOOK_EXTRA__PRODUCT_VERSION", "\"1.2\""),
+            ("PATH", "/usr/bin"),
+        ];
+        let vars = vars.into_iter().map(|(k, v)| (k.to_string(), v.to_string()));
+
+        let mut cfg = Config::from_str(src).unwrap();
+        cfg.update_from_vars(vars).unwrap();
+
+        assert_eq!(cfg.book.title, Some(String::from("From the environment")));
+        assert_eq!(cfg.build.build_dir, PathBuf::from("out"));
+        assert_eq!(cfg.build.max_depth, Some(2));
+        assert_eq!(cfg.get("output.html.curly-quotes"), Some(&Value::Boolean(true)));
+        assert_eq!(
+            cfg.get_deserialized::<Vec<u32>, _>("output.random.foo").unwrap(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            cfg.get("extra.product_version"),
+            Some(&Value::String(String::from("1.2")))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn environment_variables_which_arent_utf8_are_skipped() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let vars = vec![
+            (OsString::from("MDBOOK_BOOK__TITLE"), OsString::from_vec(vec![0x66, 0xff])),
+            (OsString::from("MDBOOK_BOOK__DESCRIPTION"), OsString::from("Fine")),
+        ];
+
+        let mut cfg = Config::default();
+        cfg.update_from_vars(vars).unwrap();
+
+        assert_eq!(cfg.book.title, None);
+        assert_eq!(cfg.book.description, Some(String::from("Fine")));
+    }
+
+    #[test]
+    fn numeric_looking_titles_stay_strings() {
+        for title in &["1984", "2.0", "null", "true", "[1, 2]"] {
+            let vars = vec![(String::from("MDBOOK_BOOK__TITLE"), title.to_string())];
+
+            let mut cfg = Config::default();
+            cfg.update_from_vars(vars).unwrap();
+
+            assert_eq!(cfg.book.title, Some(title.to_string()));
+        }
+    }
+
+    #[test]
+    fn environment_variables_naming_a_whole_typed_table_are_skipped() {
+        let vars = vec![
+            (String::from("MDBOOK_BOOK"), String::from("{}")),
+            (String::from("MDBOOK_BUILD"), String::from("out")),
+            (String::from("MDBOOK_EXTRA"), String::from("\"kept\"")),
+        ];
+
+        let mut cfg = Config::default();
+        cfg.update_from_vars(vars).unwrap();
+
+        assert_eq!(cfg.book, BookConfig::default());
+        assert_eq!(cfg.build, BuildConfig::default());
+        assert_eq!(cfg.get("extra"), Some(&Value::String(String::from("kept"))));
+    }
+
+    #[test]
+    fn bad_environment_overrides_are_errors() {
+        let vars = vec![(String::from("MDBOOK_BUILD__MAX_DEPTH"), String::from("deep"))];
+
+        let mut cfg = Config::default();
+        assert!(cfg.update_from_vars(vars).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! Reading the config from a `book.json`, which may be a GitBook config.
+
+use toml::Value;
+use toml::value::Table;
+use serde_json;
+
+use errors::*;
+use super::recursive_insert;
+
+
+/// Does a `book.json` use the same layout as `book.toml`, rather than being a
+/// GitBook config? Native configs only have tables at the top level, and at
+/// least one of them is a table mdBook knows about.
+pub fn is_native_json(json: &serde_json::Value) -> bool {
+    let known = ["book", "build", "output", "preprocessor", "profile", "extra"];
+
+    match json.as_object() {
+        Some(items) => {
+            items.values().all(|value| value.is_object())
+                && items.keys().any(|key| known.contains(&key.as_str()))
+        }
+        None => false,
+    }
+}
+
+/// Convert the contents of a GitBook-style `book.json` into the equivalent
+/// `book.toml` table.
+pub fn gitbook_to_table(json: &serde_json::Value) -> Result<Table> {
+    let items = match json.as_object() {
+        Some(items) => items,
+        None => bail!("book.json should contain a JSON object"),
+    };
+
+    let mut table = Table::new();
+    for (key, value) in items {
+        let value = match Value::try_from(value) {
+            Ok(value) => value,
+            Err(_) => {
+                warn!("Ignoring `{}` from book.json, it has no TOML equivalent", key);
+                continue;
+            }
+        };
+
+        let new_key = match key.as_str() {
+            "title" => "book.title",
+            "description" => "book.description",
+            "language" => "book.language",
+            "author" => {
+                recursive_insert(&["book", "authors"], &mut table, Value::Array(vec![value]));
+                continue;
+            }
+            "authors" => "book.authors",
+            "root" | "src" => "book.src",
+            "dest" => "build.build-dir",
+            "styles" => {
+                match value.as_table().and_then(|styles| styles.get("website")).cloned() {
+                    Some(website) => {
+                        let key = ["output", "html", "additional-css"];
+                        recursive_insert(&key, &mut table, Value::Array(vec![website]));
+                    }
+                    None => warn!("Only the `website` style from book.json is supported"),
+                }
+                continue;
+            }
+            "structure" => {
+                let summary = value
+                    .as_table()
+                    .and_then(|structure| structure.get("summary"))
+                    .and_then(|summary| summary.as_str())
+                    .map(String::from);
+                match summary {
+                    Some(ref summary) if summary != "SUMMARY.md" => warn!(
+                        "mdBook always uses SUMMARY.md, so `{}` from book.json will be ignored",
+                        summary
+                    ),
+                    _ => {}
+                }
+                continue;
+            }
+            _ => {
+                warn!("Ignoring `{}` from book.json, mdBook doesn't support it", key);
+                continue;
+            }
+        };
+
+        let pieces: Vec<_> = new_key.split(".").collect();
+        recursive_insert(&pieces, &mut table, value);
+    }
+
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use config::{as_table, Config};
+
+    #[test]
+    fn load_a_gitbook_book_json() {
+        let src = r#"{
+            "title": "A GitBook",
+            "author": "Jane Doe",
+            "root": "./docs",
+            "styles": { "website": "styles/website.css" },
+            "plugins": ["disqus"]
+        }"#;
+
+        let table = gitbook_to_table(&serde_json::from_str(src).unwrap()).unwrap();
+        let cfg: Config = Value::Table(table).try_into().unwrap();
+
+        assert_eq!(cfg.book.title, Some(String::from("A GitBook")));
+        assert_eq!(cfg.book.authors, vec![String::from("Jane Doe")]);
+        assert_eq!(cfg.book.src, PathBuf::from("./docs"));
+        assert_eq!(
+            cfg.html_config().unwrap().additional_css,
+            vec![PathBuf::from("styles/website.css")]
+        );
+        assert!(cfg.get("plugins").is_none());
+    }
+
+    #[test]
+    fn tell_native_and_gitbook_json_apart() {
+        let native = json!({"book": {"title": "Native"}, "build": {"create-missing": false}});
+        let gitbook = json!({"title": "GitBook", "styles": {"website": "website.css"}});
+
+        assert!(is_native_json(&native));
+        assert!(!is_native_json(&gitbook));
+
+        let cfg: Config = Value::Table(as_table(&native).unwrap()).try_into().unwrap();
+        assert_eq!(cfg.book.title, Some(String::from("Native")));
+        assert!(!cfg.build.create_missing);
+    }
+}
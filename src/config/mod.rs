@@ -0,0 +1,1527 @@
+use std::cmp;
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+use toml::{self, Value};
+use toml::value::Table;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use errors::*;
+use utils;
+
+mod builder;
+mod environment;
+mod json;
+
+pub use self::builder::{ConfigBuilder, ConfigSource, ConfigValue};
+use self::environment::env_overrides;
+
+/// The overall configuration object for MDBook.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    /// Metadata about the book.
+    pub book: BookConfig,
+    pub build: BuildConfig,
+    rest: Table,
+}
+
+impl Config {
+    /// Load a `Config` from some string.
+    pub fn from_str(src: &str) -> Result<Config> {
+        toml::from_str(src).chain_err(|| Error::from("Invalid configuration file"))
+    }
+
+    /// Load the configuration file from disk, along with any file it
+    /// `extends`.
+    pub fn from_disk<P: AsRef<Path>>(config_file: P) -> Result<Config> {
+        ConfigBuilder::new().with_file(config_file)?.build()
+    }
+
+    /// Fetch an arbitrary item from the `Config` as a `toml::Value`.
+    ///
+    /// You can use dotted indices to access nested items (e.g.
+    /// `output.html.playpen` will fetch the "playpen" out of the html output
+    /// table).
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let pieces: Vec<_> = key.split(".").collect();
+        recursive_get(&pieces, &self.rest)
+    }
+
+    /// Fetch a value from the `Config` so you can mutate it.
+    pub fn get_mut<'a>(&'a mut self, key: &str) -> Option<&'a mut Value> {
+        let pieces: Vec<_> = key.split(".").collect();
+        recursive_get_mut(&pieces, &mut self.rest)
+    }
+
+    /// Set a value in the `Config`, using the same dotted indices as `get()`.
+    /// Keys inside `book` and `build` update `BookConfig` and `BuildConfig`,
+    /// so they still need to be valid for those types.
+    pub fn set<S: Serialize, I: AsRef<str>>(&mut self, index: I, value: S) -> Result<()> {
+        let index = index.as_ref();
+        let value = Value::try_from(value)
+            .chain_err(|| format!("Unable to represent the value of `{}` as TOML", index))?;
+        let pieces: Vec<_> = index.split(".").collect();
+
+        match pieces[0] {
+            "book" => {
+                let book = update_typed(&self.book, index, &pieces[1..], value)?;
+                self.book = book;
+            }
+            "build" => {
+                let build = update_typed(&self.build, index, &pieces[1..], value)?;
+                self.build = build;
+            }
+            _ => recursive_insert(&pieces, &mut self.rest, value),
+        }
+
+        Ok(())
+    }
+
+    /// Override values in the `Config` with any `MDBOOK_*` environment
+    /// variables.
+    ///
+    /// The rest of the variable's name is the key to set, lowercased, with
+    /// `__` separating tables. Within mdbook's own settings `_` stands in for
+    /// `-`, so `MDBOOK_BOOK__TITLE` sets `book.title` and
+    /// `MDBOOK_BUILD__BUILD_DIR` sets `build.build-dir`. Any other key is used
+    /// as is, meaning `MDBOOK_EXTRA__PRODUCT_VERSION` sets
+    /// `extra.product_version`. Values are parsed as JSON if they can be,
+    /// otherwise (or if the JSON has the wrong type for one of mdbook's own
+    /// settings) they're used as a plain string.
+    ///
+    /// Variables which aren't valid UTF-8 are skipped with a warning, as are
+    /// `MDBOOK_BOOK` and `MDBOOK_BUILD`, which name a whole table.
+    pub fn update_from_env(&mut self) -> Result<()> {
+        debug!("[*] Checking the environment for config overrides");
+        self.update_from_vars(env::vars_os())
+    }
+
+    fn update_from_vars<I, K, V>(&mut self, vars: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        for (name, key, value) in env_overrides(vars) {
+            self.set(&key, value)
+                .chain_err(|| format!("Invalid value in the {} environment variable", name))?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the `[output.<name>]` table with the settings for a particular
+    /// renderer, if the config has one.
+    pub fn get_renderer<S: AsRef<str>>(&self, name: S) -> Option<&Table> {
+        self.subtable("output", name.as_ref())
+    }
+
+    /// Deserialize a renderer's `[output.<name>]` table into its own config
+    /// type, falling back to the type's defaults if there is no table.
+    pub fn get_renderer_config<T, S>(&self, name: S) -> Result<T>
+    where
+        T: DeserializeOwned + Default,
+        S: AsRef<str>,
+    {
+        let name = name.as_ref();
+
+        match self.get_renderer(name) {
+            Some(table) => Value::Table(table.clone())
+                .try_into()
+                .chain_err(|| format!("Invalid configuration for the {} renderer", name)),
+            None => Ok(T::default()),
+        }
+    }
+
+    /// Resolve every relative path in the config against the book's `root`
+    /// directory, so they don't depend on where mdbook happens to be run from.
+    ///
+    /// This covers `book.src`, `book.sub-books` (which are relative to the
+    /// source directory) and `build.build-dir`, plus the html renderer's
+    /// `theme`, `additional-css`, `additional-js` and `playpen.editor`. The
+    /// source directory and any additional files have to exist, so they're
+    /// canonicalized and it's an error if they're missing. The html paths
+    /// are stored as strings, so they also have to be valid UTF-8.
+    pub fn resolve_paths<P: AsRef<Path>>(&mut self, root: P) -> Result<()> {
+        let root = root.as_ref();
+        let root = root.canonicalize()
+            .chain_err(|| format!("Unable to find the book's root directory, {}", root.display()))?;
+
+        let src = root.join(&self.book.src);
+        if !src.is_dir() {
+            bail!(
+                "The source directory ({}) doesn't exist, check the `book.src` setting",
+                src.display()
+            );
+        }
+        self.book.src = src.canonicalize()?;
+        let sub_books: Vec<PathBuf> = self.book
+            .sub_books
+            .iter()
+            .map(|dir| utils::fs::collapse_path(self.book.src.join(dir)))
+            .collect();
+        self.book.sub_books = sub_books;
+        self.build.build_dir = utils::fs::collapse_path(root.join(&self.build.build_dir));
+
+        let html = match self.rest
+            .get_mut("output")
+            .and_then(|output| output.as_table_mut())
+            .and_then(|output| output.get_mut("html"))
+            .and_then(|html| html.as_table_mut())
+        {
+            Some(html) => html,
+            None => return Ok(()),
+        };
+
+        if let Some(&mut Value::String(ref mut theme)) = html.get_mut("theme") {
+            let dir = utils::fs::collapse_path(root.join(theme.as_str()));
+            if !dir.is_dir() {
+                warn!("The theme directory ({}) doesn't exist", dir.display());
+            }
+            *theme = path_to_string(&dir, "output.html.theme")?;
+        }
+
+        for key in &["additional-css", "additional-js"] {
+            if let Some(&mut Value::Array(ref mut files)) = html.get_mut(*key) {
+                for file in files {
+                    if let Value::String(ref mut file) = *file {
+                        let path = root.join(file.as_str());
+                        let path = path.canonicalize().chain_err(|| {
+                            format!(
+                                "`output.html.{}` refers to {}, which doesn't exist",
+                                key,
+                                path.display()
+                            )
+                        })?;
+                        *file = path_to_string(&path, &format!("output.html.{}", key))?;
+                    }
+                }
+            }
+        }
+
+        let editor = html.get_mut("playpen")
+            .and_then(|playpen| playpen.as_table_mut())
+            .and_then(|playpen| playpen.get_mut("editor"));
+        if let Some(&mut Value::String(ref mut editor)) = editor {
+            let path = utils::fs::collapse_path(root.join(editor.as_str()));
+            *editor = path_to_string(&path, "output.html.playpen.editor")?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the `[extra]` table, which holds the book author's own variables
+    /// (e.g. a product version or support email) for templates and
+    /// preprocessors to use.
+    pub fn extra(&self) -> Option<&Table> {
+        self.rest.get("extra").and_then(|extra| extra.as_table())
+    }
+
+    /// Get the `[preprocessor.<name>]` table with the settings for a
+    /// particular preprocessor, if the config has one.
+    pub fn get_preprocessor<S: AsRef<str>>(&self, name: S) -> Option<&Table> {
+        self.subtable("preprocessor", name.as_ref())
+    }
+
+    /// Get the standard settings (ordering and which renderers to run for)
+    /// of every preprocessor with a `[preprocessor.<name>]` table.
+    pub fn preprocessors(&self) -> Result<BTreeMap<String, PreprocessorConfig>> {
+        let tables = match self.rest.get("preprocessor").and_then(|p| p.as_table()) {
+            Some(tables) => tables,
+            None => return Ok(BTreeMap::new()),
+        };
+
+        let mut preprocessors = BTreeMap::new();
+        for (name, table) in tables {
+            let cfg = table
+                .clone()
+                .try_into()
+                .chain_err(|| format!("Invalid configuration for the {} preprocessor", name))?;
+            preprocessors.insert(name.clone(), cfg);
+        }
+
+        Ok(preprocessors)
+    }
+
+    fn subtable(&self, section: &str, name: &str) -> Option<&Table> {
+        self.rest
+            .get(section)
+            .and_then(|tables| tables.as_table())
+            .and_then(|tables| tables.get(name))
+            .and_then(|table| table.as_table())
+    }
+
+    /// Convenience method for getting the html renderer's configuration.
+    ///
+    /// # Note
+    ///
+    /// This is for compatibility only. It will be removed completely once the
+    /// rendering and plugin system is established.
+    pub fn html_config(&self) -> Option<HtmlConfig> {
+        match self.get_deserialized("output.html") {
+            Ok(cfg) => Some(cfg),
+            Err(e) => {
+                if self.get("output.html").is_some() {
+                    warn!("Ignoring the html renderer's config. {}", e);
+                }
+                None
+            }
+        }
+    }
+
+    /// Convenience function to fetch a value from the config and deserialize it
+    /// into some arbitrary type, using the same dotted indices as `get()`.
+    /// Keys inside `book` and `build` (e.g. `book.title`) can be fetched too.
+    ///
+    /// If the value can't be found, the error says which part of the key is
+    /// missing, and if it's the wrong type the error says what was expected.
+    pub fn get_deserialized<'de, T: Deserialize<'de>, S: AsRef<str>>(&self, name: S) -> Result<T> {
+        let name = name.as_ref();
+        let pieces: Vec<_> = name.split(".").collect();
+
+        let typed;
+        let table = match pieces[0] {
+            "book" => {
+                typed = typed_table("book", &self.book)?;
+                &typed
+            }
+            "build" => {
+                typed = typed_table("build", &self.build)?;
+                &typed
+            }
+            _ => &self.rest,
+        };
+
+        let value = match recursive_get(&pieces, table) {
+            Some(value) => value.clone(),
+            None => bail!("{}", missing_key(&pieces, table)),
+        };
+
+        match value.try_into() {
+            Ok(value) => Ok(value),
+            Err(e) => bail!("Invalid value for `{}`: {}", name, e),
+        }
+    }
+
+    /// Write the `Config` out as a `book.toml` which lists every known option
+    /// with a comment explaining it. Options which are still at their default
+    /// are commented out, so the file doubles as documentation.
+    pub fn to_commented_toml(&self) -> Result<String> {
+        let mut lines = vec![
+            String::from("# The configuration for this book. Options which are commented out are"),
+            String::from("# set to their default, so uncomment one to change it. See"),
+            String::from("# https://rust-lang-nursery.github.io/mdBook/format/config.html"),
+            String::from("# for more details."),
+        ];
+
+        let mut rest = self.rest.clone();
+        let html = match rest.get_mut("output").and_then(|output| output.as_table_mut()) {
+            Some(output) => output.remove("html"),
+            None => None,
+        };
+        let no_other_outputs = match rest.get("output") {
+            Some(&Value::Table(ref output)) => output.is_empty(),
+            _ => false,
+        };
+        if no_other_outputs {
+            rest.remove("output");
+        }
+
+        // top-level values have to come before the first table header
+        let (tables, values): (Table, Table) =
+            rest.into_iter().partition(|&(_, ref value)| value.is_table());
+        if !values.is_empty() {
+            lines.push(String::new());
+            lines.push(toml::to_string(&values).chain_err(|| "Unable to serialize the config")?);
+        }
+
+        let html = match html {
+            Some(Value::Table(html)) => html,
+            _ => Table::new(),
+        };
+        let sections = vec![
+            ("book", as_table(&self.book)?, as_table(&BookConfig::default())?, BOOK_KEYS),
+            ("build", as_table(&self.build)?, as_table(&BuildConfig::default())?, BUILD_KEYS),
+            ("output.html", html, as_table(&HtmlConfig::default())?, HTML_KEYS),
+        ];
+        for (name, current, defaults, known) in sections {
+            let known: Vec<String> = known.iter().map(|key| key.to_string()).collect();
+            write_section(&mut lines, name, &current, &defaults, &known);
+        }
+
+        if !tables.is_empty() {
+            lines.push(String::new());
+            lines.push(toml::to_string(&tables).chain_err(|| "Unable to serialize the config")?);
+        }
+
+        let mut toml = lines.join("\n");
+        if !toml.ends_with('\n') {
+            toml.push('\n');
+        }
+        Ok(toml)
+    }
+
+    fn from_legacy(mut table: Table) -> Config {
+        let mut cfg = Config::default();
+
+        // we use a macro here instead of a normal loop because the $out
+        // variable can be different types. This way we can make type inference
+        // figure out what try_into() deserializes to.
+        macro_rules! get_and_insert {
+            ($table:expr, $key:expr => $out:expr) => {
+                if let Some(value) = $table.remove($key).and_then(|v| v.try_into().ok()) {
+                    $out = value;
+                }
+            };
+        }
+
+        get_and_insert!(table, "title" => cfg.book.title);
+        get_and_insert!(table, "authors" => cfg.book.authors);
+        get_and_insert!(table, "source" => cfg.book.src);
+        get_and_insert!(table, "description" => cfg.book.description);
+
+        // This complicated chain of and_then's is so we can move 
+        // "output.html.destination" to "build.build_dir" and parse it into a 
+        // PathBuf.
+        let destination: Option<PathBuf> = table.get_mut("output")
+            .and_then(|output| output.as_table_mut())
+            .and_then(|output| output.get_mut("html"))
+            .and_then(|html| html.as_table_mut())
+            .and_then(|html| html.remove("destination"))
+            .and_then(|dest| dest.try_into().ok());
+
+        if let Some(dest) = destination {
+            cfg.build.build_dir = dest;
+        }
+
+        cfg.rest = table;
+        cfg
+    }
+}
+
+fn recursive_get<'a>(key: &[&str], table: &'a Table) -> Option<&'a Value> {
+    if key.is_empty() {
+        return None;
+    } else if key.len() == 1 {
+        return table.get(key[0]);
+    }
+
+    let first = key[0];
+    let rest = &key[1..];
+
+    if let Some(&Value::Table(ref nested)) = table.get(first) {
+        recursive_get(rest, nested)
+    } else {
+        None
+    }
+}
+
+/// Explain why `recursive_get()` couldn't find `key` in `table`.
+fn missing_key(key: &[&str], table: &Table) -> String {
+    let mut table = table;
+
+    for (i, piece) in key.iter().enumerate() {
+        match table.get(*piece) {
+            Some(&Value::Table(ref nested)) => table = nested,
+            Some(_) => {
+                return format!(
+                    "`{}` isn't a table, so it can't contain `{}`",
+                    key[..i + 1].join("."),
+                    key[i + 1]
+                );
+            }
+            None if i == 0 => return format!("There is no `{}` in the config", piece),
+            None => return format!("`{}` has no `{}` key", key[..i].join("."), piece),
+        }
+    }
+
+    format!("`{}` isn't a valid config key", key.join("."))
+}
+
+/// Put one of the typed config tables into a table under `name`, so it can
+/// be indexed like the rest of the config.
+fn typed_table<T: Serialize>(name: &str, cfg: &T) -> Result<Table> {
+    let value = Value::try_from(cfg).chain_err(|| format!("Unable to serialize `{}`", name))?;
+
+    let mut table = Table::new();
+    table.insert(name.to_string(), value);
+    Ok(table)
+}
+
+fn recursive_get_mut<'a>(key: &[&str], table: &'a mut Table) -> Option<&'a mut Value> {
+    // TODO: Figure out how to abstract over mutability to reduce copy-pasta
+    if key.is_empty() {
+        return None;
+    } else if key.len() == 1 {
+        return table.get_mut(key[0]);
+    }
+
+    let first = key[0];
+    let rest = &key[1..];
+
+    if let Some(&mut Value::Table(ref mut nested)) = table.get_mut(first) {
+        recursive_get_mut(rest, nested)
+    } else {
+        None
+    }
+}
+
+fn recursive_insert(key: &[&str], table: &mut Table, value: Value) {
+    if key.len() == 1 {
+        table.insert(key[0].to_string(), value);
+        return;
+    }
+
+    let nested = table
+        .entry(key[0].to_string())
+        .or_insert_with(|| Value::Table(Table::new()));
+    if !nested.is_table() {
+        *nested = Value::Table(Table::new());
+    }
+
+    if let Value::Table(ref mut nested) = *nested {
+        recursive_insert(&key[1..], nested, value);
+    }
+}
+
+/// Set the item at `key` inside one of the typed config tables by going
+/// through its TOML representation.
+fn update_typed<T>(current: &T, index: &str, key: &[&str], value: Value) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    if key.is_empty() || key.iter().any(|piece| piece.is_empty()) {
+        bail!("`{}` isn't a valid config key", index);
+    }
+
+    let mut table = match Value::try_from(current) {
+        Ok(Value::Table(table)) => table,
+        _ => bail!("Unable to update `{}`", index),
+    };
+    recursive_insert(key, &mut table, value);
+
+    Value::Table(table)
+        .try_into()
+        .chain_err(|| format!("Invalid value for `{}`", index))
+}
+
+/// Convert a resolved path back into a string so it can go back into a TOML
+/// table, which can only happen when it's valid UTF-8.
+fn path_to_string(path: &Path, key: &str) -> Result<String> {
+    match path.to_str() {
+        Some(path) => Ok(path.to_string()),
+        None => bail!("`{}` refers to {}, which isn't valid UTF-8", key, path.display()),
+    }
+}
+
+impl<'de> Deserialize<'de> for Config {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        let raw = Value::deserialize(de)?;
+
+        let mut table = match raw {
+            Value::Table(t) => t,
+            _ => {
+                use serde::de::Error;
+                return Err(D::Error::custom(
+                    "A config file should always be a toml table",
+                ));
+            }
+        };
+
+        if is_legacy_format(&table) {
+            warn!("It looks like you are using the legacy book.toml format.");
+            warn!("We'll parse it for now, but you should probably convert to the new format.");
+            warn!("See the mdbook documentation for more details, although as a rule of thumb");
+            warn!("just move all top level configuration entries like `title`, `author` and");
+            warn!("`description` under a table called `[book]`, move the `destination` entry");
+            warn!("from `[output.html]`, renamed to `build-dir`, under a table called");
+            warn!("`[build]`, and it should all work.");
+            warn!("Documentation: http://rust-lang-nursery.github.io/mdBook/format/config.html");
+            return Ok(Config::from_legacy(table));
+        }
+
+        let book: BookConfig = parse_table(&mut table, "book", BOOK_KEYS)?;
+        let build: BuildConfig = parse_table(&mut table, "build", BUILD_KEYS)?;
+
+        let html = table
+            .get("output")
+            .and_then(|output| output.as_table())
+            .and_then(|output| output.get("html"));
+        if let Some(html) = html {
+            check_table::<HtmlConfig, D::Error>(html, "output.html", HTML_KEYS)?;
+        }
+
+        Ok(Config {
+            book: book,
+            build: build,
+            rest: table,
+        })
+    }
+}
+
+impl Serialize for Config {
+    fn serialize<S: Serializer>(&self, s: S) -> ::std::result::Result<S::Ok, S::Error> {
+        let mut table = self.rest.clone();
+
+        let book_config = match Value::try_from(self.book.clone()) {
+            Ok(cfg) => cfg,
+            Err(_) => {
+                use serde::ser::Error;
+                return Err(S::Error::custom("Unable to serialize the BookConfig"));
+            }
+        };
+
+        table.insert("book".to_string(), book_config);
+        
+        Value::Table(table).serialize(s)
+    }
+}
+
+/// The keys allowed in the `[book]` table, which should match `BookConfig`.
+const BOOK_KEYS: &'static [&'static str] = &[
+    "title",
+    "authors",
+    "description",
+    "src",
+    "multilingual",
+    "sub-books",
+    "language",
+];
+
+/// The keys allowed in the `[build]` table, which should match `BuildConfig`.
+const BUILD_KEYS: &'static [&'static str] = &[
+    "build-dir",
+    "create-missing",
+    "max-depth",
+    "use-heading-titles",
+    "orphans",
+    "lints",
+    "deny-warnings",
+    "allow-symlinks",
+    "summary-indent",
+];
+
+/// The lints which can be configured in the `[build.lints]` table, which
+/// should match `LintConfig`.
+const LINT_KEYS: &'static [&'static str] = &[
+    "empty-title",
+    "trailing-whitespace",
+    "non-markdown-destination",
+    "inconsistent-list-markers",
+    "inconsistent-title-case",
+    "duplicate-title",
+];
+
+/// The keys allowed in the `[output.html]` table, which should match
+/// `HtmlConfig`.
+const HTML_KEYS: &'static [&'static str] = &[
+    "theme",
+    "curly-quotes",
+    "mathjax-support",
+    "google-analytics",
+    "additional-css",
+    "additional-js",
+    "playpen",
+];
+
+/// A description of each option for `Config::to_commented_toml()`, and an
+/// example value to show for options which have no default.
+const OPTION_DOCS: &'static [(&'static str, &'static str, &'static str)] = &[
+    ("book.title", "The book's title", "\"My Book\""),
+    ("book.authors", "The book's authors", ""),
+    ("book.description", "A description of the book", "\"A book about books\""),
+    ("book.src", "The directory with SUMMARY.md and the chapters", ""),
+    ("book.multilingual", "Does the book come in more than one language?", ""),
+    ("book.sub-books", "Directories inside `src` with other books to add to this one", ""),
+    ("book.language", "The language the book is written in", "\"en\""),
+    ("build.build-dir", "Where to put the rendered book", ""),
+    ("build.create-missing", "Create files mentioned in SUMMARY.md which don't exist", ""),
+    ("build.max-depth", "The deepest chapters can be nested in SUMMARY.md", "3"),
+    ("build.use-heading-titles", "Name untitled chapters after their first heading", ""),
+    ("build.orphans", "What to do about chapters missing from SUMMARY.md", ""),
+    ("build.deny-warnings", "Treat warnings about the book's layout as errors", ""),
+    ("build.allow-symlinks", "Can chapters be symlinks?", ""),
+    ("build.lints", "How seriously to take each SUMMARY.md lint", ""),
+    ("build.summary-indent", "Spaces (or \"tabs\") per level of nesting in SUMMARY.md", "4"),
+    ("output.html.theme", "A directory of theme files to use instead of the default", "\"theme\""),
+    ("output.html.curly-quotes", "Convert straight quotes to curly quotes", ""),
+    ("output.html.mathjax-support", "Render maths with MathJax", ""),
+    ("output.html.google-analytics", "Your Google Analytics ID", "\"UA-123456-7\""),
+    ("output.html.additional-css", "Extra stylesheets to include", ""),
+    ("output.html.additional-js", "Extra scripts to include", ""),
+    ("output.html.playpen", "Settings for the Rust playpen", ""),
+];
+
+/// Keys which used to be supported, and what to do about them now.
+const DEPRECATED_KEYS: &'static [(&'static str, &'static str)] = &[
+    ("book.author", "use `authors = [\"...\"]` instead"),
+    ("book.source", "it has been renamed to `src`"),
+    ("book.dest", "use `build-dir` in the `[build]` table instead"),
+    ("build.destination", "it has been renamed to `build-dir`"),
+    ("output.html.destination", "use `build-dir` in the `[build]` table instead"),
+];
+
+/// Remove the table called `name` from the top level of the config and
+/// deserialize it, warning about any keys which aren't `known`.
+fn parse_table<T, E>(table: &mut Table, name: &str, known: &[&str]) -> ::std::result::Result<T, E>
+where
+    T: DeserializeOwned + Default,
+    E: ::serde::de::Error,
+{
+    let value = match table.remove(name) {
+        Some(value) => value,
+        None => return Ok(T::default()),
+    };
+
+    check_table::<T, E>(&value, name, known)?;
+
+    value
+        .try_into()
+        .map_err(|e| E::custom(format!("Invalid `[{}]` table: {}", name, e)))
+}
+
+/// Warn about any keys in the table called `name` which aren't `known`, then
+/// make sure each of the known ones has the right type for `T`.
+///
+/// Each key is checked on its own so that if something is wrong the error
+/// can say which key is to blame.
+fn check_table<T, E>(value: &Value, name: &str, known: &[&str]) -> ::std::result::Result<(), E>
+where
+    T: DeserializeOwned,
+    E: ::serde::de::Error,
+{
+    let items = match *value {
+        Value::Table(ref items) => items,
+        _ => return Err(E::custom(format!("`{}` should be a table", name))),
+    };
+
+    for (key, item) in items {
+        if !known.contains(&key.as_str()) {
+            warn!("{}", unknown_key_message(name, key, known));
+            continue;
+        }
+
+        let mut single = Table::new();
+        single.insert(key.clone(), item.clone());
+        if let Err(e) = Value::Table(single).try_into::<T>() {
+            return Err(E::custom(format!("Invalid value for `{}.{}`: {}", name, key, e)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Serialize one of the typed config structs into a TOML table.
+fn as_table<T: Serialize>(cfg: &T) -> Result<Table> {
+    match Value::try_from(cfg) {
+        Ok(Value::Table(table)) => Ok(table),
+        _ => bail!("Unable to serialize the config"),
+    }
+}
+
+/// Add a commented `[name]` table to the lines of a `book.toml`, listing the
+/// `known` keys plus any others which are set. Nested tables get their own
+/// sections afterwards.
+fn write_section(
+    lines: &mut Vec<String>,
+    name: &str,
+    current: &Table,
+    defaults: &Table,
+    known: &[String],
+) {
+    lines.push(String::new());
+    lines.push(format!("[{}]", name));
+
+    let mut keys: Vec<&String> = known.iter().collect();
+    keys.extend(current.keys().filter(|key| !known.contains(key)));
+
+    let mut nested = Vec::new();
+    for key in keys {
+        let full_key = format!("{}.{}", name, key);
+        let value = current.get(key.as_str());
+        let default = defaults.get(key.as_str());
+
+        if value.or(default).map(|v| v.is_table()) == Some(true) {
+            nested.push((full_key, key));
+            continue;
+        }
+
+        let doc = OPTION_DOCS.iter().find(|&&(option, _, _)| option == full_key);
+        if let Some(&(_, description, _)) = doc {
+            lines.push(format!("# {}.", description));
+        }
+
+        match (value, default) {
+            (Some(value), default) if Some(value) != default => {
+                lines.push(format!("{} = {}", key, inline_value(value)));
+            }
+            (_, Some(default)) => lines.push(format!("# {} = {}", key, inline_value(default))),
+            (_, None) => {
+                let example = doc.map(|&(_, _, example)| example).unwrap_or("");
+                lines.push(format!("# {} = {}", key, example));
+            }
+        }
+    }
+
+    for (full_key, key) in nested {
+        let empty = Table::new();
+        let sub_table = |table: &Table| match table.get(key.as_str()) {
+            Some(&Value::Table(ref sub)) => sub.clone(),
+            _ => empty.clone(),
+        };
+        let current = sub_table(current);
+        let defaults = sub_table(defaults);
+        let known: Vec<String> = defaults.keys().cloned().collect();
+
+        write_section(lines, &full_key, &current, &defaults, &known);
+    }
+}
+
+/// Format a value the way it would appear on the right of a `key = value`
+/// line.
+fn inline_value(value: &Value) -> String {
+    match *value {
+        Value::String(ref s) => serde_json::to_string(s).unwrap_or_default(),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => format!("{:?}", f),
+        Value::Boolean(b) => b.to_string(),
+        Value::Datetime(ref d) => d.to_string(),
+        Value::Array(ref items) => {
+            let items: Vec<String> = items.iter().map(inline_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Table(ref table) => {
+            let items: Vec<String> = table
+                .iter()
+                .map(|(key, value)| format!("{} = {}", key, inline_value(value)))
+                .collect();
+            format!("{{ {} }}", items.join(", "))
+        }
+    }
+}
+
+/// Explain that the `key` in the table called `name` will be ignored, with a
+/// hint about what was probably meant.
+fn unknown_key_message(name: &str, key: &str, known: &[&str]) -> String {
+    let full_key = format!("{}.{}", name, key);
+
+    for &(deprecated, hint) in DEPRECATED_KEYS {
+        if deprecated == full_key {
+            return format!("Ignoring the deprecated configuration key `{}`, {}", full_key, hint);
+        }
+    }
+
+    let max_distance = cmp::max(key.chars().count() / 3, 1);
+    let suggestion = known
+        .iter()
+        .map(|candidate| (utils::edit_distance(key, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance);
+
+    match suggestion {
+        Some((_, candidate)) => format!(
+            "Ignoring the unknown configuration key `{}`, did you mean `{}.{}`?",
+            full_key, name, candidate
+        ),
+        None => format!("Ignoring the unknown configuration key `{}`", full_key),
+    }
+}
+
+fn is_legacy_format(table: &Table) -> bool {
+    let top_level_items = ["title", "author", "authors"];
+
+    top_level_items.iter().any(|key| table.contains_key(&key.to_string()))
+}
+
+
+/// Configuration options which are specific to the book and required for
+/// loading it from disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BookConfig {
+    /// The book's title.
+    pub title: Option<String>,
+    /// The book's authors.
+    pub authors: Vec<String>,
+    /// An optional description for the book.
+    pub description: Option<String>,
+    /// Location of the book source relative to the book's root directory. It
+    /// must already exist when the book is loaded.
+    pub src: PathBuf,
+    /// Does this book support more than one language? If so, each language
+    /// has its own directory in `src` with its own `SUMMARY.md`, and
+    /// `language` says which one is the default.
+    pub multilingual: bool,
+    /// The main language the book is written in (e.g. `en`).
+    pub language: Option<String>,
+    /// Directories inside `src` holding other books (each with its own
+    /// `book.toml` and `SUMMARY.md`) to add to the end of this one.
+    pub sub_books: Vec<PathBuf>,
+}
+
+impl Default for BookConfig {
+    fn default() -> BookConfig {
+        BookConfig {
+            title: None,
+            authors: Vec::new(),
+            description: None,
+            src: PathBuf::from("src"),
+            multilingual: false,
+            language: None,
+            sub_books: Vec::new(),
+        }
+    }
+}
+
+/// Configuration for the build procedure.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BuildConfig {
+    /// Where to put built artefacts relative to the book's root directory.
+    pub build_dir: PathBuf,
+    /// Should non-existent markdown files specified in `SETTINGS.md` be created
+    /// if they don't exist?
+    pub create_missing: bool,
+    /// The deepest chapters in `SUMMARY.md` are allowed to be nested, where
+    /// top-level chapters are at depth `1`.
+    pub max_depth: Option<usize>,
+    /// Should chapters with an empty name in `SUMMARY.md` (e.g.
+    /// `[](chapter.md)`) be named after the first heading in their file?
+    pub use_heading_titles: bool,
+    /// What to do about markdown files in the source directory which aren't
+    /// mentioned in `SUMMARY.md`.
+    pub orphans: OrphanPolicy,
+    /// How seriously to take each of the lints run over `SUMMARY.md`.
+    pub lints: LintConfig,
+    /// Treat every warning about the book's structure (from parsing
+    /// `SUMMARY.md`, lints and orphaned files) as an error.
+    pub deny_warnings: bool,
+    /// Can chapters be symlinks, or live in a symlinked directory? Turning
+    /// this off stops a book from pulling in files from elsewhere on disk.
+    pub allow_symlinks: bool,
+    /// How nested chapters in `SUMMARY.md` have to be indented. By default
+    /// anything CommonMark accepts as a nested list is fine.
+    pub summary_indent: Option<Indentation>,
+}
+
+impl Default for BuildConfig {
+    fn default() -> BuildConfig {
+        BuildConfig {
+            build_dir: PathBuf::from("book"),
+            create_missing: true,
+            max_depth: None,
+            use_heading_titles: false,
+            orphans: OrphanPolicy::default(),
+            lints: LintConfig::default(),
+            deny_warnings: false,
+            allow_symlinks: true,
+            summary_indent: None,
+        }
+    }
+}
+
+/// How to handle markdown files which aren't referenced by `SUMMARY.md`.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OrphanPolicy {
+    /// Don't check for orphaned files.
+    Ignore,
+    /// Log a warning for each orphaned file.
+    Warn,
+    /// Refuse to build the book.
+    Error,
+    /// Add each orphaned file to the end of the table of contents as a
+    /// draft chapter. `mdbook build --fix` writes the drafts to `SUMMARY.md`.
+    Append,
+}
+
+impl Default for OrphanPolicy {
+    fn default() -> OrphanPolicy {
+        OrphanPolicy::Warn
+    }
+}
+
+/// How each level of nested chapters in `SUMMARY.md` is indented.
+///
+/// In `book.toml` this is written as the number of spaces, e.g.
+/// `summary-indent = 2`, or as `summary-indent = "tabs"`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Indentation {
+    /// This many spaces per level.
+    Spaces(usize),
+    /// One tab per level.
+    Tabs,
+}
+
+impl Display for Indentation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Indentation::Spaces(1) => write!(f, "1 space"),
+            Indentation::Spaces(width) => write!(f, "{} spaces", width),
+            Indentation::Tabs => write!(f, "a tab"),
+        }
+    }
+}
+
+impl Serialize for Indentation {
+    fn serialize<S: Serializer>(&self, s: S) -> ::std::result::Result<S::Ok, S::Error> {
+        match *self {
+            Indentation::Spaces(width) => (width as i64).serialize(s),
+            Indentation::Tabs => "tabs".serialize(s),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Indentation {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        use serde::de::Error;
+
+        match Value::deserialize(de)? {
+            Value::Integer(width) if width > 0 => Ok(Indentation::Spaces(width as usize)),
+            Value::String(ref tabs) if tabs == "tabs" => Ok(Indentation::Tabs),
+            other => Err(D::Error::custom(format!(
+                "expected the number of spaces to indent by or \"tabs\", found {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// What should happen when a lint finds a problem.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintLevel {
+    /// Don't run the lint.
+    Allow,
+    /// Log a warning.
+    Warn,
+    /// Refuse to build the book.
+    Deny,
+}
+
+/// The level each `SUMMARY.md` lint runs at.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LintConfig {
+    /// Chapters whose name is empty.
+    pub empty_title: LintLevel,
+    /// Lines which end in whitespace.
+    pub trailing_whitespace: LintLevel,
+    /// Chapters whose file doesn't end in `.md`.
+    pub non_markdown_destination: LintLevel,
+    /// Mixing `-`, `*` and `+` for list items.
+    pub inconsistent_list_markers: LintLevel,
+    /// Mixing "Title Case" and "Sentence case" chapter names.
+    pub inconsistent_title_case: LintLevel,
+    /// Chapters with the same name at the same nesting level.
+    pub duplicate_title: LintLevel,
+}
+
+impl Default for LintConfig {
+    fn default() -> LintConfig {
+        LintConfig {
+            empty_title: LintLevel::Warn,
+            trailing_whitespace: LintLevel::Allow,
+            non_markdown_destination: LintLevel::Warn,
+            inconsistent_list_markers: LintLevel::Warn,
+            inconsistent_title_case: LintLevel::Allow,
+            duplicate_title: LintLevel::Allow,
+        }
+    }
+}
+
+impl LintConfig {
+    /// Get a copy of this config where every lint which would warn is denied
+    /// instead.
+    pub fn deny_warnings(&self) -> LintConfig {
+        let deny = |level: LintLevel| if level == LintLevel::Warn {
+            LintLevel::Deny
+        } else {
+            level
+        };
+
+        LintConfig {
+            empty_title: deny(self.empty_title),
+            trailing_whitespace: deny(self.trailing_whitespace),
+            non_markdown_destination: deny(self.non_markdown_destination),
+            inconsistent_list_markers: deny(self.inconsistent_list_markers),
+            inconsistent_title_case: deny(self.inconsistent_title_case),
+            duplicate_title: deny(self.duplicate_title),
+        }
+    }
+}
+
+/// The settings every `[preprocessor.<name>]` table can have. Anything else
+/// in the table is left for the preprocessor itself to interpret.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreprocessorConfig {
+    /// Preprocessors which this one needs to run before.
+    pub before: Vec<String>,
+    /// Preprocessors which this one needs to run after.
+    pub after: Vec<String>,
+    /// The renderers this preprocessor runs for, where an empty list means
+    /// all of them.
+    pub renderers: Vec<String>,
+}
+
+impl PreprocessorConfig {
+    /// Should the preprocessor run before the renderer called `renderer`?
+    pub fn runs_for(&self, renderer: &str) -> bool {
+        self.renderers.is_empty() || self.renderers.iter().any(|r| r == renderer)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HtmlConfig {
+    pub theme: Option<PathBuf>,
+    pub curly_quotes: bool,
+    pub mathjax_support: bool,
+    pub google_analytics: Option<String>,
+    pub additional_css: Vec<PathBuf>,
+    pub additional_js: Vec<PathBuf>,
+    pub playpen: Playpen,
+}
+
+/// Configuration for tweaking how the the HTML renderer handles the playpen.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Playpen {
+    pub editor: PathBuf,
+    pub editable: bool,
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    const COMPLEX_CONFIG: &'static str = r#"
+        [book]
+        title = "Some Book"
+        authors = ["Michael-F-Bryan <michaelfbryan@gmail.com>"]
+        description = "A completely useless book"
+        multilingual = true
+        language = "en"
+        src = "source"
+
+        [build]
+        build-dir = "outputs"
+        create-missing = false
+        max-depth = 3
+        use-heading-titles = true
+        orphans = "error"
+        deny-warnings = true
+        allow-symlinks = false
+        summary-indent = 2
+
+        [build.lints]
+        trailing-whitespace = "deny"
+
+        [output.html]
+        theme = "./themedir"
+        curly-quotes = true
+        google-analytics = "123456"
+        additional-css = ["./foo/bar/baz.css"]
+
+        [output.html.playpen]
+        editable = true
+        editor = "ace"
+        "#;
+
+    #[test]
+    fn load_a_complex_config_file() {
+        let src = COMPLEX_CONFIG;
+
+        let book_should_be = BookConfig {
+            title: Some(String::from("Some Book")),
+            authors: vec![String::from("Michael-F-Bryan <michaelfbryan@gmail.com>")],
+            description: Some(String::from("A completely useless book")),
+            multilingual: true,
+            language: Some(String::from("en")),
+            src: PathBuf::from("source"),
+            ..Default::default()
+        };
+        let build_should_be = BuildConfig {
+            build_dir: PathBuf::from("outputs"),
+            create_missing: false,
+            max_depth: Some(3),
+            use_heading_titles: true,
+            orphans: OrphanPolicy::Error,
+            lints: LintConfig {
+                trailing_whitespace: LintLevel::Deny,
+                ..Default::default()
+            },
+            deny_warnings: true,
+            allow_symlinks: false,
+            summary_indent: Some(Indentation::Spaces(2)),
+        };
+        let playpen_should_be = Playpen {
+            editable: true,
+            editor: PathBuf::from("ace"),
+        };
+        let html_should_be = HtmlConfig {
+            curly_quotes: true,
+            google_analytics: Some(String::from("123456")),
+            additional_css: vec![PathBuf::from("./foo/bar/baz.css")],
+            theme: Some(PathBuf::from("./themedir")),
+            playpen: playpen_should_be,
+            ..Default::default()
+        };
+
+        let got = Config::from_str(src).unwrap();
+
+        assert_eq!(got.book, book_should_be);
+        assert_eq!(got.build, build_should_be);
+        assert_eq!(got.html_config().unwrap(), html_should_be);
+    }
+
+    #[test]
+    fn load_arbitrary_output_type() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct RandomOutput {
+            foo: u32,
+            bar: String,
+            baz: Vec<bool>,
+        }
+
+        let src = r#"
+        [output.random]
+        foo = 5
+        bar = "Hello World"
+        baz = [true, true, false]
+        "#;
+
+        let should_be = RandomOutput {
+            foo: 5,
+            bar: String::from("Hello World"),
+            baz: vec![true, true, false],
+        };
+
+        let cfg = Config::from_str(src).unwrap();
+        let got: RandomOutput = cfg.get_deserialized("output.random").unwrap();
+
+        assert_eq!(got, should_be);
+
+        let baz: Vec<bool> = cfg.get_deserialized("output.random.baz").unwrap();
+        let baz_should_be = vec![true, true, false];
+
+        assert_eq!(baz, baz_should_be);
+    }
+
+    #[test]
+    fn deserialize_typed_config_values() {
+        let src = r#"
+        [book]
+        title = "Some Book"
+
+        [build]
+        max-depth = 3
+        "#;
+
+        let cfg = Config::from_str(src).unwrap();
+
+        let title: String = cfg.get_deserialized("book.title").unwrap();
+        assert_eq!(title, "Some Book");
+        let depth: usize = cfg.get_deserialized("build.max-depth").unwrap();
+        assert_eq!(depth, 3);
+    }
+
+    #[test]
+    fn errors_from_get_deserialized_are_precise() {
+        let src = r#"
+        [output.html.search]
+        limit = "lots"
+        "#;
+
+        let cfg = Config::from_str(src).unwrap();
+        let message = |key: &str| cfg.get_deserialized::<u32, _>(key).unwrap_err().to_string();
+
+        assert!(message("output.html.search.limit").starts_with(
+            "Invalid value for `output.html.search.limit`"
+        ));
+        assert_eq!(
+            message("output.html.search.depth"),
+            "`output.html.search` has no `depth` key"
+        );
+        assert_eq!(
+            message("output.html.search.limit.max"),
+            "`output.html.search.limit` isn't a table, so it can't contain `max`"
+        );
+        assert_eq!(message("preprocessor.links"), "There is no `preprocessor` in the config");
+    }
+
+    #[test]
+    fn resolve_paths_relative_to_the_book_root() {
+        let temp = TempDir::new("book").unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("source")).unwrap();
+        File::create(root.join("custom.css")).unwrap();
+
+        let src = r#"
+        [book]
+        src = "source"
+        sub-books = ["./guide/../api"]
+
+        [build]
+        build-dir = "./out/../public"
+
+        [output.html]
+        theme = "my-theme"
+        additional-css = ["./custom.css"]
+
+        [output.html.playpen]
+        editor = "editor"
+        "#;
+        let mut cfg = Config::from_str(src).unwrap();
+        cfg.resolve_paths(&root).unwrap();
+        let html = cfg.html_config().unwrap();
+
+        assert_eq!(cfg.book.src, root.join("source"));
+        assert_eq!(cfg.book.sub_books, vec![root.join("source").join("api")]);
+        assert_eq!(cfg.build.build_dir, root.join("public"));
+        assert_eq!(html.theme, Some(root.join("my-theme")));
+        assert_eq!(html.additional_css, vec![root.join("custom.css")]);
+        assert_eq!(html.playpen.editor, root.join("editor"));
+    }
+
+    #[test]
+    fn missing_additional_files_are_errors() {
+        let temp = TempDir::new("book").unwrap();
+        fs::create_dir(temp.path().join("src")).unwrap();
+
+        let src = "[output.html]\nadditional-js = [\"missing.js\"]\n";
+        let mut cfg = Config::from_str(src).unwrap();
+
+        assert!(cfg.resolve_paths(temp.path()).is_err());
+    }
+
+    #[test]
+    fn get_the_extra_table() {
+        let src = r#"
+        [extra]
+        product_version = "1.2.3"
+        support_email = "help@example.com"
+        "#;
+
+        let cfg = Config::from_str(src).unwrap();
+        let extra = cfg.extra().unwrap();
+
+        assert_eq!(extra.len(), 2);
+        assert_eq!(
+            extra.get("product_version"),
+            Some(&Value::String(String::from("1.2.3")))
+        );
+        assert!(Config::default().extra().is_none());
+    }
+
+    #[test]
+    fn load_preprocessor_tables() {
+        let src = r#"
+        [preprocessor.links]
+        before = ["index"]
+        renderers = ["html"]
+        custom = 42
+
+        [preprocessor.index]
+        "#;
+
+        let cfg = Config::from_str(src).unwrap();
+        let got = cfg.preprocessors().unwrap();
+
+        assert_eq!(got.len(), 2);
+        assert_eq!(got["links"].before, vec![String::from("index")]);
+        assert!(got["links"].runs_for("html"));
+        assert!(!got["links"].runs_for("epub"));
+        assert!(got["index"].runs_for("epub"));
+
+        let links = cfg.get_preprocessor("links").unwrap();
+        assert_eq!(links.get("custom"), Some(&Value::Integer(42)));
+    }
+
+    #[test]
+    fn suggest_fixes_for_unknown_keys() {
+        assert_eq!(
+            unknown_key_message("book", "ttile", BOOK_KEYS),
+            "Ignoring the unknown configuration key `book.ttile`, did you mean `book.title`?"
+        );
+        assert_eq!(
+            unknown_key_message("build", "destination", BUILD_KEYS),
+            "Ignoring the deprecated configuration key `build.destination`, it has been \
+             renamed to `build-dir`"
+        );
+        assert_eq!(
+            unknown_key_message("output.html", "something-else", HTML_KEYS),
+            "Ignoring the unknown configuration key `output.html.something-else`"
+        );
+    }
+
+    #[test]
+    fn html_config_values_are_checked_when_loading() {
+        let src = r#"
+        [output.html]
+        curly-quotes = "yes please"
+        "#;
+
+        let err = Config::from_str(src).unwrap_err();
+        assert!(err.iter().any(|e| e.to_string().contains("output.html.curly-quotes")));
+    }
+
+    #[test]
+    fn summary_indent_has_to_be_a_number_of_spaces_or_tabs() {
+        let cfg = Config::from_str("[build]\nsummary-indent = \"tabs\"\n").unwrap();
+        assert_eq!(cfg.build.summary_indent, Some(Indentation::Tabs));
+        let round_tripped = Config::from_str(&cfg.to_commented_toml().unwrap()).unwrap();
+        assert_eq!(round_tripped.build, cfg.build);
+
+        assert!(Config::from_str("[build]\nsummary-indent = 0\n").is_err());
+        assert!(Config::from_str("[build]\nsummary-indent = \"wide\"\n").is_err());
+    }
+
+    #[test]
+    fn commented_toml_has_every_option() {
+        let cfg = Config::default();
+        let got = cfg.to_commented_toml().unwrap();
+
+        assert!(got.contains("[book]\n# The book's title.\n# title = \"My Book\"\n"));
+        assert!(got.contains("# create-missing = true\n"));
+        assert!(got.contains("[build.lints]\n"));
+        assert!(got.contains("# trailing-whitespace = \"allow\"\n"));
+        assert!(got.contains("[output.html.playpen]\n"));
+
+        let parsed = Config::from_str(&got).unwrap();
+        assert_eq!(parsed.book, cfg.book);
+        assert_eq!(parsed.build, cfg.build);
+        assert_eq!(parsed.html_config(), Some(HtmlConfig::default()));
+    }
+
+    #[test]
+    fn commented_toml_round_trips() {
+        let src = format!("{}\n[output.random]\nfoo = 5\n", COMPLEX_CONFIG);
+        let cfg = Config::from_str(&src).unwrap();
+
+        let got = cfg.to_commented_toml().unwrap();
+
+        assert!(got.contains("\ncreate-missing = false\n"));
+        assert_eq!(Config::from_str(&got).unwrap(), cfg);
+    }
+
+    #[test]
+    fn get_a_renderers_own_config() {
+        #[derive(Debug, Default, Deserialize, PartialEq)]
+        struct EpubConfig {
+            cover: Option<String>,
+        }
+
+        let src = r#"
+        [output.epub]
+        cover = "cover.png"
+        "#;
+
+        let cfg = Config::from_str(src).unwrap();
+
+        let table = cfg.get_renderer("epub").unwrap();
+        assert_eq!(table.get("cover"), Some(&Value::String(String::from("cover.png"))));
+        assert!(cfg.get_renderer("pdf").is_none());
+
+        let got: EpubConfig = cfg.get_renderer_config("epub").unwrap();
+        assert_eq!(got.cover, Some(String::from("cover.png")));
+        let got: EpubConfig = cfg.get_renderer_config("pdf").unwrap();
+        assert_eq!(got, EpubConfig::default());
+    }
+
+    #[test]
+    fn mutate_some_stuff() {
+        // really this is just a sanity check to make sure the borrow checker
+        // is happy...
+        let src = COMPLEX_CONFIG;
+        let mut config = Config::from_str(src).unwrap();
+        let key = "output.html.playpen.editable";
+
+        assert_eq!(config.get(key).unwrap(), &Value::Boolean(true));
+        *config.get_mut(key).unwrap() = Value::Boolean(false);
+        assert_eq!(config.get(key).unwrap(), &Value::Boolean(false));
+    }
+
+    /// The config file format has slightly changed (metadata stuff is now under
+    /// the `book` table instead of being at the top level) so we're adding a
+    /// **temporary** compatibility check. You should be able to still load the
+    /// old format, emitting a warning.
+    #[test]
+    fn can_still_load_the_previous_format() {
+        let src = r#"
+        title = "mdBook Documentation"
+        description = "Create book from markdown files. Like Gitbook but implemented in Rust"
+        authors = ["Mathieu David"]
+        source = "./source"
+
+        [output.html]
+        destination = "my-book" # the output files will be generated in `root/my-book` instead of `root/book`
+        theme = "my-theme"
+        curly-quotes = true
+        google-analytics = "123456"
+        additional-css = ["custom.css", "custom2.css"]
+        additional-js = ["custom.js"]
+        "#;
+
+        let book_should_be = BookConfig {
+            title: Some(String::from("mdBook Documentation")),
+            description: Some(String::from(
+                "Create book from markdown files. Like Gitbook but implemented in Rust",
+            )),
+            authors: vec![String::from("Mathieu David")],
+            src: PathBuf::from("./source"),
+            ..Default::default()
+        };
+
+        let build_should_be = BuildConfig {
+            build_dir: PathBuf::from("my-book"),
+            create_missing: true,
+            max_depth: None,
+            use_heading_titles: false,
+            orphans: OrphanPolicy::Warn,
+            lints: LintConfig::default(),
+            deny_warnings: false,
+            allow_symlinks: true,
+            summary_indent: None,
+        };
+
+        let html_should_be = HtmlConfig {
+            theme: Some(PathBuf::from("my-theme")),
+            curly_quotes: true,
+            google_analytics: Some(String::from("123456")),
+            additional_css: vec![PathBuf::from("custom.css"), PathBuf::from("custom2.css")],
+            additional_js: vec![PathBuf::from("custom.js")],
+            ..Default::default()
+        };
+
+        let got = Config::from_str(src).unwrap();
+        assert_eq!(got.book, book_should_be);
+        assert_eq!(got.build, build_should_be);
+        assert_eq!(got.html_config().unwrap(), html_should_be);
+    }
+
+    #[test]
+    fn invalid_values_say_which_key_is_wrong() {
+        let src = r#"
+        [book]
+        title = "Some Book"
+        unknown-key = "is only a warning"
+
+        [build]
+        create-missing = "yes please"
+        "#;
+
+        let err = Config::from_str(src).unwrap_err();
+
+        let message = err.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(": ");
+        assert!(message.contains("`build.create-missing`"), "{}", message);
+    }
+}
@@ -108,6 +108,8 @@ extern crate serde;
 extern crate serde_derive;
 #[macro_use]
 extern crate serde_json;
+#[cfg(feature = "yaml")]
+extern crate serde_yaml;
 extern crate tempdir;
 extern crate toml;
 
@@ -115,9 +117,9 @@ extern crate toml;
 #[macro_use]
 extern crate pretty_assertions;
 
-mod preprocess;
 pub mod book;
 pub mod config;
+pub mod preprocess;
 pub mod renderer;
 pub mod theme;
 pub mod utils;
@@ -142,9 +144,28 @@ pub mod errors {
                 display("{}: {}", message, String::from_utf8_lossy(&output.stdout))
             }
 
-            ParseError(line: usize, col: usize, message: String) {
+            ParseError(line: usize, col: usize, kind: ::book::ParseErrorKind) {
                 description("A SUMMARY.md parsing error")
-                display("Error at line {}, column {}: {}", line, col, message)
+                display("Error at line {}, column {}: {}", line, col, kind)
+            }
+
+            Encoding(path: ::std::path::PathBuf, reason: String) {
+                description("A file isn't encoded as UTF-8")
+                display("{} must be saved as UTF-8 ({})", path.display(), reason)
+            }
+
+            MissingChapter(name: String,
+                           location: ::std::path::PathBuf,
+                           span: Option<::book::Span>,
+                           suggestion: Option<::std::path::PathBuf>) {
+                description("A chapter's source file doesn't exist")
+                display("The file for \"{}\" doesn't exist, {}{}",
+                        name,
+                        location.display(),
+                        match *suggestion {
+                            Some(ref s) => format!(" (did you mean `{}`?)", s.display()),
+                            None => String::new(),
+                        })
             }
         }
     }
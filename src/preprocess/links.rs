@@ -1,10 +1,57 @@
 use std::path::{Path, PathBuf};
 use regex::{CaptureMatches, Captures, Regex};
 use utils::fs::file_to_string;
+use book::Book;
 use errors::*;
+use super::{Preprocessor, PreprocessorContext};
 
 const ESCAPE_CHAR: char = '\\';
 
+/// The preprocessor which expands `{{#include}}` and `{{#playpen}}` links
+/// into the contents of the files they point at. `MDBook` always runs it, and
+/// it can be ordered against other preprocessors by its name, `links`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LinkPreprocessor;
+
+impl LinkPreprocessor {
+    /// Create a new `LinkPreprocessor`.
+    pub fn new() -> LinkPreprocessor {
+        LinkPreprocessor
+    }
+}
+
+impl Preprocessor for LinkPreprocessor {
+    fn name(&self) -> &str {
+        "links"
+    }
+
+    fn run(&self, ctx: &PreprocessorContext, book: &mut Book) -> Result<()> {
+        let src_dir = ctx.root.join(&ctx.config.book.src);
+        let mut result = Ok(());
+
+        book.for_each_mut(|ch| {
+            if result.is_err() || ch.is_draft() {
+                return;
+            }
+
+            let base = match ch.path.parent() {
+                Some(dir) => src_dir.join(dir),
+                None => src_dir.clone(),
+            };
+            let expanded = replace_all(&ch.content, base);
+            match expanded {
+                Ok(content) => ch.content = content,
+                Err(e) => {
+                    let msg = format!("Unable to expand the links in {}", ch.path.display());
+                    result = Err(Error::with_chain(e, msg));
+                }
+            }
+        });
+
+        result
+    }
+}
+
 pub fn replace_all<P: AsRef<Path>>(s: &str, path: P) -> Result<String> {
     // When replacing one thing in a string by something with a different length,
     // the indices after that will not correspond,
@@ -22,6 +69,17 @@ pub fn replace_all<P: AsRef<Path>>(s: &str, path: P) -> Result<String> {
     Ok(replaced)
 }
 
+/// Get the paths of every file pulled in by an `{{#include}}` or
+/// `{{#playpen}}` link, relative to the directory containing `s`.
+pub fn dependencies(s: &str) -> Vec<PathBuf> {
+    find_links(s)
+        .filter_map(|link| match link.link {
+            LinkType::Include(path) | LinkType::Playpen(path, _) => Some(path),
+            LinkType::Escaped => None,
+        })
+        .collect()
+}
+
 #[derive(PartialOrd, PartialEq, Debug, Clone)]
 enum LinkType<'a> {
     Escaped,
@@ -130,6 +188,12 @@ fn find_links(contents: &str) -> LinkIter {
 //      Tests
 //
 
+#[test]
+fn test_dependencies() {
+    let s = "{{#include file.rs}}\n\\{{#include escaped.rs}}\n{{#playpen ../listing.rs editable}}";
+    assert_eq!(dependencies(s), vec![PathBuf::from("file.rs"), PathBuf::from("../listing.rs")]);
+}
+
 #[test]
 fn test_find_links_no_link() {
     let s = "Some random text without link...";
@@ -251,3 +315,40 @@ fn test_find_all_link_types() {
                    link_text: "{{#playpen my.rs editable no_run should_panic}}",
                });
 }
+
+#[test]
+fn test_link_preprocessor_expands_chapters_relative_to_their_directory() {
+    use std::fs::{self, File};
+    use std::io::Write;
+    use tempdir::TempDir;
+    use book::{BookItem, Chapter};
+    use config::Config;
+
+    let temp = TempDir::new("book").unwrap();
+    let nested = temp.path().join("src").join("nested");
+    fs::create_dir_all(&nested).unwrap();
+    File::create(nested.join("listing.rs"))
+        .unwrap()
+        .write_all(b"fn main() {}")
+        .unwrap();
+
+    let ctx = PreprocessorContext {
+        root: temp.path().to_path_buf(),
+        config: Config::default(),
+        renderer: String::from("html"),
+    };
+    let content = String::from("{{#include listing.rs}}");
+    let mut book = Book {
+        sections: vec![
+            BookItem::Chapter(Chapter::new("Nested", content, "nested/chapter.md")),
+            BookItem::Chapter(Chapter::new("Draft", String::from("{{#include x.rs}}"), "")),
+        ],
+    };
+
+    LinkPreprocessor::new().run(&ctx, &mut book).unwrap();
+
+    match book.sections[0] {
+        BookItem::Chapter(ref ch) => assert_eq!(ch.content, "fn main() {}"),
+        ref other => panic!("Expected a chapter, found {:?}", other),
+    }
+}
@@ -1 +1,283 @@
+//! Preprocessors, which get a chance to modify a book's chapters after it has
+//! been loaded and before it is handed to the renderer.
+
 pub mod links;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use book::Book;
+use config::{Config, PreprocessorConfig};
+use errors::*;
+
+/// Extra information a `Preprocessor` may need while it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreprocessorContext {
+    /// The book's root directory.
+    pub root: PathBuf,
+    /// The book's configuration.
+    pub config: Config,
+    /// The name of the renderer the book is being prepared for.
+    pub renderer: String,
+}
+
+/// Something which can modify a book before it gets rendered.
+///
+/// A preprocessor's settings live in the `[preprocessor.<name>]` table of
+/// `book.toml`, which is also where the order preprocessors run in and the
+/// renderers they run for are configured.
+pub trait Preprocessor {
+    /// The name used for this preprocessor's `[preprocessor.<name>]` table.
+    fn name(&self) -> &str;
+
+    /// Modify the book's chapters in place.
+    fn run(&self, ctx: &PreprocessorContext, book: &mut Book) -> Result<()>;
+}
+
+/// Run every preprocessor which applies to `ctx.renderer`, in the order
+/// required by their `before` and `after` settings.
+pub fn run_all(
+    preprocessors: &[Box<Preprocessor>],
+    ctx: &PreprocessorContext,
+    book: &mut Book,
+) -> Result<()> {
+    let settings = ctx.config.preprocessors()?;
+
+    for index in order(preprocessors, &settings, &ctx.renderer)? {
+        let preprocessor = &preprocessors[index];
+        debug!("[*] Running the {} preprocessor", preprocessor.name());
+        preprocessor
+            .run(ctx, book)
+            .chain_err(|| format!("The {} preprocessor failed", preprocessor.name()))?;
+    }
+
+    Ok(())
+}
+
+/// Work out which preprocessors run for `renderer`, returning their indices
+/// in the order they should run. Preprocessors with no ordering constraints
+/// between them keep the order they were added in.
+fn order(
+    preprocessors: &[Box<Preprocessor>],
+    settings: &BTreeMap<String, PreprocessorConfig>,
+    renderer: &str,
+) -> Result<Vec<usize>> {
+    for problem in unknown_preprocessors(preprocessors, settings) {
+        warn!("{}", problem);
+    }
+
+    let default = PreprocessorConfig::default();
+    let selected: Vec<usize> = (0..preprocessors.len())
+        .filter(|&i| {
+            settings
+                .get(preprocessors[i].name())
+                .unwrap_or(&default)
+                .runs_for(renderer)
+        })
+        .collect();
+    let position = |name: &str| {
+        selected
+            .iter()
+            .position(|&i| preprocessors[i].name() == name)
+    };
+
+    // edges[a] holds every preprocessor which has to wait for `a`
+    let mut edges = vec![Vec::new(); selected.len()];
+    let mut waiting_on = vec![0; selected.len()];
+    for (a, &index) in selected.iter().enumerate() {
+        let cfg = settings.get(preprocessors[index].name()).unwrap_or(&default);
+
+        for name in &cfg.before {
+            if let Some(b) = position(name) {
+                edges[a].push(b);
+                waiting_on[b] += 1;
+            }
+        }
+        for name in &cfg.after {
+            if let Some(b) = position(name) {
+                edges[b].push(a);
+                waiting_on[a] += 1;
+            }
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(selected.len());
+    let mut done = vec![false; selected.len()];
+    while ordered.len() < selected.len() {
+        let next = match (0..selected.len()).find(|&i| !done[i] && waiting_on[i] == 0) {
+            Some(next) => next,
+            None => {
+                let stuck: Vec<&str> = (0..selected.len())
+                    .filter(|&i| !done[i])
+                    .map(|i| preprocessors[selected[i]].name())
+                    .collect();
+                bail!(
+                    "The preprocessors {} can't be ordered because their `before` and \
+                     `after` settings form a cycle",
+                    stuck.join(", ")
+                );
+            }
+        };
+
+        done[next] = true;
+        for &b in &edges[next] {
+            waiting_on[b] -= 1;
+        }
+        ordered.push(selected[next]);
+    }
+
+    Ok(ordered)
+}
+
+/// Find the `[preprocessor.<name>]` tables, and the names in their `before`
+/// and `after` settings, which don't belong to any of the `preprocessors`.
+/// These are most likely typos.
+fn unknown_preprocessors(
+    preprocessors: &[Box<Preprocessor>],
+    settings: &BTreeMap<String, PreprocessorConfig>,
+) -> Vec<String> {
+    let exists = |name: &str| preprocessors.iter().any(|p| p.name() == name);
+    let mut problems = Vec::new();
+
+    for (name, cfg) in settings {
+        if !exists(name) {
+            problems.push(format!(
+                "Ignoring the [preprocessor.{}] table, there is no preprocessor called \"{}\"",
+                name, name
+            ));
+        }
+
+        for other in cfg.before.iter().chain(&cfg.after) {
+            if !exists(other) {
+                problems.push(format!(
+                    "The {} preprocessor is ordered against \"{}\", but there is no \
+                     preprocessor with that name",
+                    name, other
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use book::{BookItem, Chapter};
+
+    struct Named(&'static str);
+
+    impl Preprocessor for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn run(&self, _ctx: &PreprocessorContext, _book: &mut Book) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn names(preprocessors: &[Box<Preprocessor>], indices: &[usize]) -> Vec<String> {
+        indices
+            .iter()
+            .map(|&i| preprocessors[i].name().to_string())
+            .collect()
+    }
+
+    fn settings(src: &str) -> BTreeMap<String, PreprocessorConfig> {
+        Config::from_str(src).unwrap().preprocessors().unwrap()
+    }
+
+    #[test]
+    fn order_preprocessors_by_their_constraints() {
+        let src = r#"
+        [preprocessor.toc]
+        after = ["glossary"]
+
+        [preprocessor.links]
+        before = ["glossary"]
+
+        [preprocessor.pdf-only]
+        renderers = ["pdf"]
+        "#;
+        let preprocessors: Vec<Box<Preprocessor>> = vec![
+            Box::new(Named("toc")),
+            Box::new(Named("glossary")),
+            Box::new(Named("pdf-only")),
+            Box::new(Named("links")),
+        ];
+
+        let got = order(&preprocessors, &settings(src), "html").unwrap();
+
+        assert_eq!(names(&preprocessors, &got), vec!["links", "glossary", "toc"]);
+    }
+
+    #[test]
+    fn cyclic_constraints_are_an_error() {
+        let src = r#"
+        [preprocessor.first]
+        after = ["second"]
+
+        [preprocessor.second]
+        after = ["first"]
+        "#;
+        let preprocessors: Vec<Box<Preprocessor>> =
+            vec![Box::new(Named("first")), Box::new(Named("second"))];
+
+        assert!(order(&preprocessors, &settings(src), "html").is_err());
+    }
+
+    #[test]
+    fn settings_for_missing_preprocessors_are_reported() {
+        let src = r#"
+        [preprocessor.links]
+        before = ["glosary"]
+
+        [preprocessor.tco]
+        "#;
+        let preprocessors: Vec<Box<Preprocessor>> = vec![Box::new(Named("links"))];
+
+        let got = unknown_preprocessors(&preprocessors, &settings(src));
+
+        assert_eq!(got.len(), 2);
+        assert!(got[0].contains("glosary"));
+        assert!(got[1].contains("[preprocessor.tco]"));
+    }
+
+    #[test]
+    fn preprocessors_can_modify_the_book() {
+        struct Uppercase;
+
+        impl Preprocessor for Uppercase {
+            fn name(&self) -> &str {
+                "uppercase"
+            }
+
+            fn run(&self, ctx: &PreprocessorContext, book: &mut Book) -> Result<()> {
+                assert_eq!(ctx.renderer, "html");
+                book.for_each_mut(|ch| ch.content = ch.content.to_uppercase());
+                Ok(())
+            }
+        }
+
+        let ctx = PreprocessorContext {
+            root: PathBuf::from("."),
+            config: Config::default(),
+            renderer: String::from("html"),
+        };
+        let mut book = Book {
+            sections: vec![
+                BookItem::Chapter(Chapter::new("Intro", "hello".to_string(), "intro.md")),
+            ],
+        };
+        let preprocessors: Vec<Box<Preprocessor>> = vec![Box::new(Uppercase)];
+
+        run_all(&preprocessors, &ctx, &mut book).unwrap();
+
+        match book.sections[0] {
+            BookItem::Chapter(ref ch) => assert_eq!(ch.content, "HELLO"),
+            _ => panic!(),
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use renderer::html_handlebars::helpers;
-use preprocess;
 use renderer::Renderer;
 use book::MDBook;
 use book::{BookItem, Chapter};
@@ -35,16 +34,8 @@ impl HtmlHandlebars {
                    -> Result<()> {
         // FIXME: This should be made DRY-er and rely less on mutable state
         match *item {
-            BookItem::Chapter(ref ch)  => 
-            {
-                let content = ch.content.clone();
-                let base = ch.path.parent()
-                    .map(|dir| ctx.src_dir.join(dir))
-                    .expect("All chapters must have a parent directory");
-
-                // Parse and expand links
-                let content = preprocess::links::replace_all(&content, base)?;
-                let content = utils::render_markdown(&content, ctx.html_config.curly_quotes);
+            BookItem::Chapter(ref ch) if !ch.is_draft() => {
+                let content = utils::render_markdown(&ch.content, ctx.html_config.curly_quotes);
                 print_content.push_str(&content);
 
                 // Update the context with data for this file
@@ -67,6 +58,10 @@ impl HtmlHandlebars {
                 ctx.data.insert("path".to_owned(), json!(path));
                 ctx.data.insert("content".to_owned(), json!(content));
                 ctx.data.insert("chapter_title".to_owned(), json!(ch.name));
+                ctx.data.insert("chapter_attributes".to_owned(), json!(ch.attributes));
+                ctx.data.insert("chapter_metadata".to_owned(), json!(ch.metadata));
+                ctx.data.insert("chapter_word_count".to_owned(), json!(ch.word_count));
+                ctx.data.insert("chapter_reading_time".to_owned(), json!(ch.reading_time()));
                 ctx.data.insert("title".to_owned(), json!(title));
                 ctx.data.insert("path_to_root".to_owned(),
                                 json!(utils::fs::path_to_root(&ch.path)));
@@ -240,9 +235,13 @@ impl HtmlHandlebars {
 
 
 impl Renderer for HtmlHandlebars {
+    fn name(&self) -> &str {
+        "html"
+    }
+
     fn render(&self, book: &MDBook) -> Result<()> {
         let html_config = book.config.html_config().unwrap_or_default();
-        let src_dir = book.root.join(&book.config.book.src);
+        let src_dir = book.source_dir();
 
         debug!("[fn]: render");
         let mut handlebars = Handlebars::new();
@@ -281,17 +280,31 @@ impl Renderer for HtmlHandlebars {
         fs::create_dir_all(&destination)
             .chain_err(|| "Unexpected error when constructing destination path")?;
 
-        for (i, item) in book.iter().enumerate() {
+        // The first chapter with a page is also used as the index page
+        let mut is_index = true;
+
+        for item in book.iter() {
             let ctx = RenderItemContext {
                 book: book,
                 handlebars: &handlebars,
                 destination: destination.to_path_buf(),
-                src_dir: src_dir.clone(),
                 data: data.clone(),
-                is_index: i == 0,
+                is_index: is_index,
                 html_config: html_config.clone(),
             };
-            self.render_item(item, ctx, &mut print_content)?;
+            self.render_item(item, ctx, &mut print_content)
+                .chain_err(|| match *item {
+                    BookItem::Chapter(ref ch) => {
+                        format!("Unable to render \"{}\" ({})", ch.name, ch.path.display())
+                    }
+                    _ => String::from("Unable to render an item in the book"),
+                })?;
+
+            if let BookItem::Chapter(ref ch) = *item {
+                if !ch.is_draft() {
+                    is_index = false;
+                }
+            }
         }
 
         // Print version
@@ -340,6 +353,13 @@ fn make_data(book: &MDBook, config: &Config) -> Result<serde_json::Map<String, s
         data.insert("livereload".to_owned(), json!(livereload));
     }
 
+    // The author's own variables from the `[extra]` table
+    if let Some(extra) = config.extra() {
+        let extra = serde_json::to_value(extra)
+            .chain_err(|| "Unable to convert the [extra] table")?;
+        data.insert("extra".to_owned(), extra);
+    }
+
     // Add google analytics tag
     if let Some(ref ga) = config.html_config().and_then(|html| html.google_analytics) {
         data.insert("google_analytics".to_owned(), json!(ga));
@@ -405,17 +425,28 @@ fn make_data(book: &MDBook, config: &Config) -> Result<serde_json::Map<String, s
                     chapter.insert("section".to_owned(), json!(section.to_string()));
                 }
 
-                chapter.insert("name".to_owned(), json!(ch.name));
+                // the TOC renders any inline code in the chapter's name
+                let name = ch.markdown_name.as_ref().unwrap_or(&ch.name);
+                chapter.insert("name".to_owned(), json!(name));
+                if let Some(ref title) = ch.title {
+                    chapter.insert("title".to_owned(), json!(title));
+                }
                 let path = ch.path.to_str().ok_or_else(|| {
                                                            io::Error::new(io::ErrorKind::Other,
                                                                           "Could not convert path \
                                                                            to str")
                                                        })?;
                 chapter.insert("path".to_owned(), json!(path));
+                if let Some(ref fragment) = ch.fragment {
+                    chapter.insert("fragment".to_owned(), json!(fragment));
+                }
             }
             BookItem::Separator => {
                 chapter.insert("spacer".to_owned(), json!("_spacer_"));
             }
+            BookItem::PartTitle(ref title) => {
+                chapter.insert("part".to_owned(), json!(title));
+            }
         }
 
         chapters.push(chapter);
@@ -601,7 +632,6 @@ struct RenderItemContext<'a> {
     handlebars: &'a Handlebars,
     book: &'a MDBook,
     destination: PathBuf,
-    src_dir: PathBuf,
     data: serde_json::Map<String, serde_json::Value>,
     is_index: bool,
     html_config: HtmlConfig,
@@ -101,7 +101,10 @@ fn render(
                     Path::new(p).with_extension("html")
                     .to_str()
                     .ok_or_else(|| RenderError::new("Link could not be converted to str"))
-                    .map(|p| context.insert("link".to_owned(), json!(p.replace("\\", "/"))))
+                    .map(|p| {
+                        let link = p.replace("\\", "/").replace("#", "%23");
+                        context.insert("link".to_owned(), json!(link))
+                    })
             })?;
 
     debug!("[*]: Render template");
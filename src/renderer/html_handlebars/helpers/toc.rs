@@ -34,6 +34,20 @@ impl HelperDef for RenderToc {
                 continue;
             }
 
+            // Part title, these only ever appear at the top level
+            if let Some(title) = item.get("part") {
+                while current_level > 1 {
+                    rc.writer.write_all(b"</ul>")?;
+                    rc.writer.write_all(b"</li>")?;
+                    current_level -= 1;
+                }
+
+                rc.writer.write_all(b"<li class=\"part-title\">")?;
+                rc.writer.write_all(escape_html(title).as_bytes())?;
+                rc.writer.write_all(b"</li>")?;
+                continue;
+            }
+
             let level = if let Some(s) = item.get("section") {
                 s.matches('.').count()
             } else {
@@ -67,21 +81,21 @@ impl HelperDef for RenderToc {
                 if !path.is_empty() {
                     rc.writer.write_all(b"<a href=\"")?;
 
-                    let tmp = Path::new(item.get("path").expect("Error: path should be Some(_)"))
-                        .with_extension("html")
-                        .to_str()
-                        .unwrap()
-                        // Hack for windows who tends to use `\` as separator instead of `/`
-                        .replace("\\", "/");
-
                     // Add link
-                    rc.writer.write_all(tmp.as_bytes())?;
+                    let href = chapter_href(path, item.get("fragment"));
+                    rc.writer.write_all(href.as_bytes())?;
                     rc.writer.write_all(b"\"")?;
 
                     if path == &current {
                         rc.writer.write_all(b" class=\"active\"")?;
                     }
 
+                    if let Some(title) = item.get("title") {
+                        rc.writer.write_all(b" title=\"")?;
+                        rc.writer.write_all(escape_html(title).as_bytes())?;
+                        rc.writer.write_all(b"\"")?;
+                    }
+
                     rc.writer.write_all(b">")?;
                     true
                 } else {
@@ -134,3 +148,53 @@ impl HelperDef for RenderToc {
         Ok(())
     }
 }
+
+/// Build the `href` linking to a chapter (and optionally a fragment within
+/// it), escaped so it can go straight into a quoted attribute.
+fn chapter_href(path: &str, fragment: Option<&String>) -> String {
+    let mut href = Path::new(path)
+        .with_extension("html")
+        .to_str()
+        .unwrap()
+        // Hack for windows who tends to use `\` as separator instead of `/`
+        .replace("\\", "/")
+        // a `#` in the file name would otherwise start the fragment
+        .replace("#", "%23");
+
+    if let Some(fragment) = fragment {
+        href.push('#');
+        href.push_str(fragment);
+    }
+
+    escape_html(&href)
+}
+
+/// Escape text so it can be written into HTML as-is, either as an element's
+/// content or inside a quoted attribute.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part_titles_are_escaped() {
+        let got = escape_html(r#"Tips & <Tricks> for "quoting""#);
+
+        assert_eq!(got, "Tips &amp; &lt;Tricks&gt; for &quot;quoting&quot;");
+    }
+
+    #[test]
+    fn chapter_links_are_escaped() {
+        let fragment = String::from("x\"><script>");
+
+        let got = chapter_href("notes/a&b.md", Some(&fragment));
+
+        assert_eq!(got, "notes/a&amp;b.html#x&quot;&gt;&lt;script&gt;");
+    }
+}
@@ -5,5 +5,9 @@ mod html_handlebars;
 use errors::*;
 
 pub trait Renderer {
+    /// The name used for this renderer's `[output.<name>]` table, which
+    /// preprocessors can use to decide whether they should run.
+    fn name(&self) -> &str;
+
     fn render(&self, book: &::book::MDBook) -> Result<()>;
 }
@@ -67,6 +67,35 @@ pub fn path_to_root<P: Into<PathBuf>>(path: P) -> String {
 }
 
 
+/// Remove the `.` and `..` components from a relative path, without looking
+/// at the file system.
+///
+/// ```rust
+/// # extern crate mdbook;
+/// #
+/// # use std::path::{Path, PathBuf};
+/// # use mdbook::utils::fs::collapse_path;
+/// #
+/// # fn main() {
+/// let path = Path::new("chapter/./../listings/main.rs");
+/// assert_eq!(collapse_path(path), PathBuf::from("listings/main.rs"));
+/// # }
+/// ```
+pub fn collapse_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut collapsed = PathBuf::new();
+
+    for component in path.as_ref().components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                collapsed.pop();
+            }
+            other => collapsed.push(other.as_os_str()),
+        }
+    }
+
+    collapsed
+}
 
 /// This function creates a file and returns it. But before creating the file
 /// it checks every directory in the path to see if it exists,
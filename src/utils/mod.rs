@@ -3,7 +3,86 @@ pub mod fs;
 use pulldown_cmark::{html, Event, Options, Parser, Tag, OPTION_ENABLE_FOOTNOTES,
                      OPTION_ENABLE_TABLES};
 use std::borrow::Cow;
+use std::cmp;
+
+/// The languages a block of front matter can be written in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FrontMatterFormat {
+    /// TOML, delimited by `+++` lines.
+    Toml,
+    /// YAML, delimited by `---` lines.
+    Yaml,
+}
+
+/// Find a block of TOML front matter, delimited by `+++` lines, at the very
+/// start of `text`. Returns the TOML inside the block and the byte offset
+/// where the rest of the text starts.
+pub fn front_matter(text: &str) -> Option<(&str, usize)> {
+    let block = front_matter_block(text, "+++");
+    if block.is_none() && text.split('\n').next().map(|line| line.trim_right()) == Some("+++") {
+        warn!("A front matter block was never closed, treating it as normal text");
+    }
+
+    block
+}
+
+/// Find a block of front matter at the very start of `text` like
+/// `front_matter()`, accepting YAML delimited by `---` lines as well as
+/// TOML. A `---` line with no closing `---` is just a horizontal rule.
+pub fn any_front_matter(text: &str) -> Option<(FrontMatterFormat, &str, usize)> {
+    if let Some((block, start)) = front_matter(text) {
+        return Some((FrontMatterFormat::Toml, block, start));
+    }
+
+    front_matter_block(text, "---").map(|(block, start)| (FrontMatterFormat::Yaml, block, start))
+}
+
+/// Find the text between a `delimiter` line at the start of `text` and the
+/// next `delimiter` line.
+fn front_matter_block<'a>(text: &'a str, delimiter: &str) -> Option<(&'a str, usize)> {
+    let mut lines = text.split('\n');
+    if lines.next().map(|line| line.trim_right()) != Some(delimiter) {
+        return None;
+    }
+
+    let start = match text.find('\n') {
+        Some(ix) => ix + 1,
+        None => return None,
+    };
+    let mut end = start;
+
+    for line in lines {
+        if line.trim_right() == delimiter {
+            let body_start = cmp::min(end + line.len() + 1, text.len());
+            return Some((&text[start..end], body_start));
+        }
+
+        end += line.len() + 1;
+    }
+
+    None
+}
+
+/// The Levenshtein distance between two strings.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..b.len() + 1).collect();
 
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == b_char { 0 } else { 1 };
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+            current.push(cmp::min(substitution, cmp::min(insertion, deletion)));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
 
 ///
 ///
@@ -105,6 +184,42 @@ fn convert_quotes_to_curly(original_text: &str) -> String {
 
 #[cfg(test)]
 mod tests {
+    mod front_matter {
+        use super::super::{any_front_matter, FrontMatterFormat};
+
+        #[test]
+        fn it_finds_toml_and_yaml_blocks() {
+            let toml = "+++\nauthor = \"Jane\"\n+++\n# Chapter\n";
+            let yaml = "---\nauthor: Jane\n---\n# Chapter\n";
+
+            assert_eq!(
+                any_front_matter(toml),
+                Some((FrontMatterFormat::Toml, "author = \"Jane\"\n", 24))
+            );
+            assert_eq!(
+                any_front_matter(yaml),
+                Some((FrontMatterFormat::Yaml, "author: Jane\n", 21))
+            );
+        }
+
+        #[test]
+        fn a_lone_horizontal_rule_isnt_front_matter() {
+            assert_eq!(any_front_matter("---\n# Chapter\n"), None);
+        }
+    }
+
+    mod edit_distance {
+        use super::super::edit_distance;
+
+        #[test]
+        fn it_counts_single_character_edits() {
+            assert_eq!(edit_distance("", ""), 0);
+            assert_eq!(edit_distance("kitten", "sitting"), 3);
+            assert_eq!(edit_distance("chapter1.md", "chapter_1.md"), 1);
+            assert_eq!(edit_distance("abc", ""), 3);
+        }
+    }
+
     mod render_markdown {
         use super::super::render_markdown;
 
@@ -2,7 +2,8 @@ extern crate mdbook;
 extern crate tempdir;
 
 use std::path::PathBuf;
-use std::fs;
+use std::fs::{self, File};
+use std::io::{Read, Write};
 use mdbook::MDBook;
 use mdbook::config::Config;
 use tempdir::TempDir;
@@ -68,3 +69,70 @@ fn book_toml_isnt_required() {
 
     md.build().unwrap();
 }
+
+/// The title and authors given to `mdbook init` end up in `book.toml`.
+#[test]
+fn init_records_the_title_and_authors() {
+    let temp = TempDir::new("mdbook").unwrap();
+
+    let mut cfg = Config::default();
+    cfg.book.title = Some(String::from("My Book"));
+    cfg.book.authors = vec![String::from("Jane Doe")];
+    MDBook::init(temp.path()).with_config(cfg).build().unwrap();
+
+    let got = MDBook::load(temp.path()).unwrap();
+    assert_eq!(got.config.book.title, Some(String::from("My Book")));
+    assert_eq!(got.config.book.authors, vec![String::from("Jane Doe")]);
+}
+
+/// An existing `SUMMARY.md` is kept, and the chapters it mentions are created.
+#[test]
+fn init_keeps_an_existing_summary() {
+    let temp = TempDir::new("mdbook").unwrap();
+    fs::create_dir(temp.path().join("src")).unwrap();
+    let summary = "# Summary\n\n- [Intro](intro.md)\n";
+    File::create(temp.path().join("src").join("SUMMARY.md"))
+        .unwrap()
+        .write_all(summary.as_bytes())
+        .unwrap();
+
+    MDBook::init(temp.path()).build().unwrap();
+
+    let mut got = String::new();
+    File::open(temp.path().join("src").join("SUMMARY.md"))
+        .unwrap()
+        .read_to_string(&mut got)
+        .unwrap();
+    assert_eq!(got, summary);
+    assert!(temp.path().join("src").join("intro.md").exists());
+    assert!(!temp.path().join("src").join("chapter_1.md").exists());
+}
+
+/// Running `mdbook init` where there are already chapters but no
+/// `SUMMARY.md` writes one listing them.
+#[test]
+fn init_generates_a_summary_for_existing_chapters() {
+    let temp = TempDir::new("mdbook").unwrap();
+    let src = temp.path().join("src");
+    fs::create_dir(&src).unwrap();
+    File::create(src.join("1-intro.md"))
+        .unwrap()
+        .write_all(b"# Introduction\n")
+        .unwrap();
+    File::create(src.join("2-usage.md"))
+        .unwrap()
+        .write_all(b"# Usage\n")
+        .unwrap();
+
+    MDBook::init(temp.path()).build().unwrap();
+
+    let mut got = String::new();
+    File::open(src.join("SUMMARY.md"))
+        .unwrap()
+        .read_to_string(&mut got)
+        .unwrap();
+    assert!(got.starts_with("# Summary\n"));
+    assert!(got.contains("- [Introduction](1-intro.md)\n"));
+    assert!(got.contains("- [Usage](2-usage.md)\n"));
+    assert!(!src.join("chapter_1.md").exists());
+}
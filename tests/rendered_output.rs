@@ -2,13 +2,15 @@ extern crate mdbook;
 #[macro_use]
 extern crate pretty_assertions;
 extern crate select;
+extern crate tempdir;
 extern crate walkdir;
 
 mod dummy_book;
 
 use dummy_book::{assert_contains_strings, DummyBook};
 
-use std::fs;
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::Path;
 use std::ffi::OsStr;
 use walkdir::{DirEntry, WalkDir, WalkDirIterator};
@@ -18,6 +20,7 @@ use mdbook::errors::*;
 use mdbook::utils::fs::file_to_string;
 use mdbook::config::Config;
 use mdbook::MDBook;
+use tempdir::TempDir;
 
 
 const BOOK_ROOT: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/dummy_book");
@@ -50,6 +53,48 @@ fn by_default_mdbook_generates_rendered_content_in_the_book_directory() {
     assert!(temp.path().join("book").join("index.html").exists());
 }
 
+#[test]
+fn books_can_be_loaded_with_a_profile() {
+    let temp = DummyBook::new().build().unwrap();
+    let src = "[profile.release.build]\nbuild-dir = \"public\"\n";
+    File::create(temp.path().join("book.toml"))
+        .unwrap()
+        .write_all(src.as_bytes())
+        .unwrap();
+
+    let mut md = MDBook::load_with_profile(temp.path(), "release").unwrap();
+    md.build().unwrap();
+
+    assert!(temp.path().join("public").join("index.html").exists());
+    assert!(!temp.path().join("book").exists());
+}
+
+#[test]
+fn every_language_of_a_multilingual_book_is_rendered() {
+    let temp = TempDir::new("mdbook").unwrap();
+    let files = vec![
+        ("book.toml", "[book]\nmultilingual = true\nlanguage = \"en\"\n"),
+        ("src/en/SUMMARY.md", "- [Intro](intro.md)\n- [Usage](usage.md)\n"),
+        ("src/en/intro.md", "# Intro\n"),
+        ("src/en/usage.md", "# Usage\n"),
+        ("src/fr/SUMMARY.md", "- [Introduction](intro.md)\n"),
+        ("src/fr/intro.md", "# Introduction\n"),
+    ];
+    for (name, content) in files {
+        let path = temp.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(path).unwrap().write_all(content.as_bytes()).unwrap();
+    }
+
+    let mut md = MDBook::load(temp.path()).unwrap();
+    md.build().unwrap();
+
+    let dest = temp.path().join("book");
+    assert!(dest.join("en").join("intro.html").exists());
+    assert!(dest.join("en").join("usage.html").exists());
+    assert!(dest.join("fr").join("intro.html").exists());
+}
+
 #[test]
 fn make_sure_bottom_level_files_contain_links_to_chapters() {
     let temp = DummyBook::new().build().unwrap();
@@ -278,6 +323,38 @@ fn create_missing_file_with_config() {
     assert!(temp.path().join("src").join("intro.md").exists());
 }
 
+/// The source directory can be moved with the `book.src` setting, but it has
+/// to exist.
+#[test]
+fn load_a_book_from_a_custom_source_directory() {
+    let temp = DummyBook::new().build().unwrap();
+    fs::rename(temp.path().join("src"), temp.path().join("source")).unwrap();
+
+    let mut cfg = Config::default();
+    cfg.book.src = "source".into();
+    assert!(MDBook::load_with_config(temp.path(), cfg).is_ok());
+
+    let mut cfg = Config::default();
+    cfg.book.src = "missing".into();
+    assert!(MDBook::load_with_config(temp.path(), cfg).is_err());
+}
+
+/// Each build saves the chapters' content hashes so the next one can tell
+/// what changed.
+#[test]
+fn builds_remember_which_chapters_changed() {
+    let temp = DummyBook::new().build().unwrap();
+    let mut md = MDBook::load(temp.path()).unwrap();
+    assert!(md.previous_content_hashes().is_none());
+
+    md.build().unwrap();
+    // the hashes are kept out of the rendered book
+    assert!(temp.path().join(".mdbook").join("content-hashes.json").exists());
+
+    let md = MDBook::load(temp.path()).unwrap();
+    assert!(md.previous_content_hashes().is_some());
+}
+
 /// This makes sure you can include a Rust file with `{{#playpen example.rs}}`.
 /// Specification is in `book-example/src/format/rust.md`
 #[test]